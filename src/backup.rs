@@ -0,0 +1,266 @@
+//! Backups of original image metadata
+//!
+//! Before a command modifies an image, the metadata stored in the image is
+//! recorded in a per-run *journal*, allowing the run to be undone later. Each
+//! run is stored in its own directory (named by the run ID, which is based on
+//! the time the run started), containing an index of the modified files and
+//! one metadata snapshot per file:
+//!
+//! ```text
+//! backups/
+//! └── 20261018T141240/
+//!     ├── journal.toml
+//!     ├── 0001.exif
+//!     └── 0002.exif
+//! ```
+//!
+//! Only the first snapshot of each file is kept, such that restoring a run
+//! always reverts files to their state before the run. Snapshots only hold
+//! the metadata of a file, not its image data, so restoring a file keeps any
+//! edits made to the image itself after the run.
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::negative::{Negative, NegativeError};
+
+/// Name of the index file of each run
+const INDEX: &str = "journal.toml";
+
+/// Backup errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum BackupError {
+    /// Generic I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    /// Image metadata could not be read or written
+    #[error(transparent)]
+    NegativeError(#[from] NegativeError),
+
+    /// Journal index could not be parsed
+    #[error(transparent)]
+    TomlDeError(#[from] toml::de::Error),
+
+    /// Journal index could not be serialized
+    #[error(transparent)]
+    TomlSerError(#[from] toml::ser::Error),
+
+    /// No journal exists for the given run
+    #[error("No backup found for run `{0}`")]
+    UnknownRun(String),
+}
+
+/// Index of a journal
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize)]
+struct Index {
+    command: String,
+    created: NaiveDateTime,
+    #[serde(default, rename = "file")]
+    files: Vec<Entry>,
+}
+
+/// Journal entry, referring to the snapshot of a single file
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    snapshot: String,
+}
+
+/// Journal of the original metadata of files modified during a run
+///
+/// The journal directory is only created once the first file is recorded,
+/// such that runs which don't modify any files leave no trace.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Journal {
+    root: PathBuf,
+    id: String,
+    index: Index,
+}
+
+impl Journal {
+    /// Start a new journal in the given backup directory
+    pub fn new(root: &Path, command: &str) -> Self {
+        let created = chrono::Local::now().naive_local();
+        Self {
+            root: root.to_owned(),
+            id: created.format("%Y%m%dT%H%M%S").to_string(),
+            index: Index {
+                command: command.to_owned(),
+                created,
+                files: Vec::new(),
+            },
+        }
+    }
+
+    /// Open the journal of the given run in the given backup directory
+    pub fn open(root: &Path, id: &str) -> Result<Self, BackupError> {
+        let path = root.join(id).join(INDEX);
+        let index = match std::fs::read_to_string(&path) {
+            Ok(index) => toml::from_str(&index)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Err(BackupError::UnknownRun(id.to_owned()))?
+            }
+            Err(error) => Err(error)?,
+        };
+        Ok(Self {
+            root: root.to_owned(),
+            id: id.to_owned(),
+            index,
+        })
+    }
+
+    /// List all journals in the given backup directory, oldest first
+    pub fn list(root: &Path) -> Result<Vec<Self>, BackupError> {
+        let entries = match std::fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => Err(error)?,
+        };
+        let mut journals = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.path().join(INDEX).is_file() {
+                let id = entry.file_name().to_string_lossy().into_owned();
+                journals.push(Self::open(root, &id)?);
+            }
+        }
+        journals.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(journals)
+    }
+
+    /// Get the ID of the run
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the name of the command which started the run
+    pub fn command(&self) -> &str {
+        &self.index.command
+    }
+
+    /// Get the date & time the run started
+    pub fn created(&self) -> NaiveDateTime {
+        self.index.created
+    }
+
+    /// Iterate over the files recorded in the journal
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.index.files.iter().map(|entry| entry.path.as_path())
+    }
+
+    /// Record the metadata currently stored by the given negative
+    ///
+    /// Files which have already been recorded are skipped, keeping their
+    /// original snapshot. Paths are recorded as absolute paths, allowing the
+    /// run to be restored from any working directory.
+    pub fn record(&mut self, negative: &Negative) -> Result<(), BackupError> {
+        let path = std::path::absolute(negative.path())?;
+        if self.index.files.iter().any(|entry| entry.path == path) {
+            return Ok(());
+        }
+        if self.index.files.is_empty() {
+            self.create_dir()?;
+        }
+        let snapshot = format!("{:04}.exif", self.index.files.len() + 1);
+        std::fs::write(self.dir().join(&snapshot), negative.snapshot()?)?;
+        self.index.files.push(Entry { path, snapshot });
+        std::fs::write(self.dir().join(INDEX), toml::to_string(&self.index)?)?;
+        Ok(())
+    }
+
+    /// Restore the given file to its recorded state
+    pub fn restore(&self, path: &Path) -> Result<(), BackupError> {
+        let entry = self
+            .index
+            .files
+            .iter()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+        let snapshot = std::fs::read(self.dir().join(&entry.snapshot))?;
+        Negative::new_from_path(path)?.restore(&snapshot)?;
+        Ok(())
+    }
+
+    /// Directory holding the journal
+    fn dir(&self) -> PathBuf {
+        self.root.join(&self.id)
+    }
+
+    /// Create the journal directory, picking a unique run ID if needed
+    fn create_dir(&mut self) -> Result<(), BackupError> {
+        std::fs::create_dir_all(&self.root)?;
+        let base = self.id.clone();
+        for n in 2.. {
+            match std::fs::create_dir(self.dir()) {
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                    self.id = format!("{base}-{n}");
+                }
+                result => return Ok(result?),
+            }
+        }
+        unreachable!("run IDs should not be exhausted")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::Merger;
+    use crate::negative::ApplyMetadata;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn record_and_restore() -> Result<(), BackupError> {
+        let dir = tempfile::tempdir()?;
+        let root = dir.path().join("backups");
+        let path = std::path::absolute(dir.path().join("20160513-A0012+001.tiff"))?;
+        std::fs::copy("tests/data/20160513-A0012+001.tiff", &path)?;
+        let original = Negative::new_from_path(&path)?;
+
+        // Runs without any recorded files leave no trace
+        assert!(Journal::list(&root)?.is_empty());
+        let mut journal = Journal::new(&root, "tag");
+        assert!(Journal::list(&root)?.is_empty());
+
+        // Record & modify the file twice, keeping the first snapshot only
+        for author in ["Simon Sigurdhsson", "Someone Else"] {
+            let mut negative = Negative::new_from_path(&path)?;
+            journal.record(&negative)?;
+            negative.apply_author_data(
+                &crate::metadata::Metadata {
+                    author: crate::metadata::Author {
+                        name: author.into(),
+                        url: None,
+                    },
+                    license: None,
+                    scan: None,
+                },
+                &None,
+                &mut Merger::default(),
+            )?;
+            negative.save()?;
+        }
+        assert_eq!(journal.files().collect::<Vec<_>>(), [path.as_path()]);
+
+        // The journal can be listed and reopened, and the file restored
+        let journals = Journal::list(&root)?;
+        assert_eq!(journals, [journal.clone()]);
+        let journal = Journal::open(&root, journal.id())?;
+        assert_eq!(journal.command(), "tag");
+        assert_ne!(Negative::new_from_path(&path)?.diff(&original), vec![]);
+        journal.restore(&path)?;
+        assert_eq!(Negative::new_from_path(&path)?.diff(&original), vec![]);
+
+        // Unknown runs are reported as such
+        assert!(matches!(
+            Journal::open(&root, "19700101T000000"),
+            Err(BackupError::UnknownRun(_))
+        ));
+        Ok(())
+    }
+}
@@ -3,11 +3,16 @@ use std::io::{BufReader, Read};
 use std::path::PathBuf;
 use std::process::ExitCode;
 
-use ::clap::{Args, Parser, Subcommand};
-use color_eyre::eyre::{Result, WrapErr};
+use ::clap::{Args, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{bail, Result, WrapErr};
+use itertools::Itertools;
 
 use crate::negative::ApplyMetadata;
-use crate::{cmds, metadata, negative, rolls};
+use crate::output::{self, OutputFormat};
+use crate::{
+    backup, cmds, config, development, filter, gear, inputs, map, matching, merge, metadata,
+    negative, rolls, types,
+};
 
 #[doc(hidden)]
 mod shadow {
@@ -63,7 +68,8 @@ impl Cli {
 
     /// Run the selected subcommand
     pub fn run_command(self) -> Result<ExitCode> {
-        self.command.run()
+        let config = load_config(self.global_opts.config)?;
+        self.command.run(&config)
     }
 }
 
@@ -73,22 +79,59 @@ struct GlobalOpts {
     #[clap(long, global = true, value_name = "WHEN", default_value = "auto")]
     color: clap::ColorChoice,
 
+    /// Configuration file [default: $XDG_CONFIG_HOME/filmrolls/config.toml]
+    #[clap(long, global = true, value_name = "FILE", env = "FILMROLLS_CONFIG")]
+    config: Option<PathBuf>,
+
     #[command(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
 
 #[derive(Args)]
-#[group(required = true, multiple = false)]
+#[group(required = false, multiple = false)]
 struct FilmRoll {
     /// Input film roll data file(s)
+    ///
+    /// If omitted, the roll data files (or glob patterns) listed as `rolls` in
+    /// the configuration file are used.
     #[clap(long, short = 'r', value_parser, value_name = "FILE")]
     rolls: Vec<clio::Input>,
 }
 
 impl FilmRoll {
-    /// Read & parse the given film roll data file
-    fn into_rolls(self) -> impl Iterator<Item = Result<rolls::Roll>> {
-        self.rolls.into_iter().flat_map(|input| {
+    /// Read & parse the given (or configured) film roll data files
+    fn into_rolls(
+        self,
+        config: &config::Config,
+    ) -> Result<impl Iterator<Item = Result<rolls::Roll>>> {
+        Ok(self.into_sourced_rolls(config)?.map_ok(|(roll, _)| roll))
+    }
+
+    /// Read & parse the given (or configured) film roll data files
+    ///
+    /// Each roll is returned along with the path of the file it was read from.
+    fn into_sourced_rolls(
+        self,
+        config: &config::Config,
+    ) -> Result<impl Iterator<Item = Result<(rolls::Roll, PathBuf)>>> {
+        let inputs: Vec<clio::Input> = if self.rolls.is_empty() {
+            config
+                .roll_paths()
+                .wrap_err("Failed to find configured roll data files")?
+                .into_iter()
+                .map(|path| {
+                    clio::Input::new(&path).wrap_err_with(|| {
+                        format!("Failed to read roll data from {}", path.display())
+                    })
+                })
+                .try_collect()?
+        } else {
+            self.rolls
+        };
+        if inputs.is_empty() {
+            bail!("No roll data given; use `--rolls` or set `rolls` in the configuration file");
+        }
+        Ok(inputs.into_iter().flat_map(|input| {
             let path = input.path().path();
             let reader = BufReader::new(input.clone());
             use rolls::SourceError::UnsupportedFormat;
@@ -98,66 +141,486 @@ impl FilmRoll {
             {
                 "text/xml" => RollIter::XmlSource(rolls::from_filmrolls(reader)),
                 "application/json" => RollIter::JsonSource(rolls::from_lightme(reader)),
+                "text/x-toml" => RollIter::TomlSource(rolls::from_toml(reader)),
                 mime => RollIter::from_error(UnsupportedFormat(mime.to_owned())),
             }
-            .map(move |result| -> Result<rolls::Roll> {
-                result.wrap_err_with(|| format!("Failed to read roll data from {}", path.display()))
+            .map(move |result| -> Result<(rolls::Roll, PathBuf)> {
+                let roll = result.wrap_err_with(|| {
+                    format!("Failed to read roll data from {}", path.display())
+                })?;
+                Ok((roll, path.to_owned()))
             })
             .collect::<Vec<_>>()
-        })
+        }))
     }
 }
 
 #[derive(Args)]
-#[group(required = true, multiple = false)]
+struct RollFilter {
+    /// Only include rolls with an ID matching the glob pattern ID (may be given multiple times)
+    #[clap(long = "id", short, value_name = "ID")]
+    ids: Vec<glob::Pattern>,
+
+    /// Only include rolls shot using a camera whose name contains CAMERA
+    #[clap(long)]
+    camera: Option<String>,
+
+    /// Only include frames shot on a film stock whose name contains FILM
+    #[clap(long)]
+    film: Option<String>,
+
+    /// Only include frames shot using a lens whose name contains LENS
+    #[clap(long)]
+    lens: Option<String>,
+
+    /// Only include rolls unloaded (or frames shot) on or after DATE
+    #[clap(long, value_name = "DATE")]
+    since: Option<types::TomlDateTime>,
+
+    /// Only include rolls loaded (or frames shot) on or before DATE
+    #[clap(long, value_name = "DATE")]
+    until: Option<types::TomlDateTime>,
+
+    /// Only include frames shot within `--radius` of the decimal position LAT,LON
+    #[clap(long, value_name = "LAT,LON", allow_hyphen_values = true)]
+    near: Option<types::Position>,
+
+    /// Radius of the `--near` area, in kilometres
+    #[clap(long, value_name = "KM", default_value = "1", requires = "near")]
+    radius: f64,
+}
+
+impl RollFilter {
+    fn into_filter(self) -> filter::Filter {
+        filter::Filter {
+            ids: self.ids,
+            camera: self.camera,
+            film: self.film,
+            lens: self.lens,
+            since: self.since.map(Into::into),
+            until: self.until.map(Into::into),
+            near: self.near.map(|near| (near, self.radius)),
+        }
+    }
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = false)]
+struct Development {
+    /// Input development record file, keyed by roll ID
+    #[clap(long, short = 'd', value_parser, value_name = "FILE")]
+    development: Option<clio::Input>,
+}
+
+impl Development {
+    /// Read & parse the given development record file (if any)
+    fn into_records(self) -> Result<development::Records> {
+        self.development
+            .map(|input| read_toml(input, "development records"))
+            .unwrap_or_else(|| Ok(Default::default()))
+    }
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = false)]
+struct Gear {
+    /// Input gear inventory file, with camera & lens serial numbers
+    #[clap(long, short = 'g', value_parser, value_name = "FILE")]
+    gear: Option<clio::Input>,
+}
+
+impl Gear {
+    /// Read & parse the given gear inventory file (if any)
+    fn into_inventory(self) -> Result<gear::Inventory> {
+        self.gear
+            .map(|input| read_toml(input, "gear inventory"))
+            .unwrap_or_else(|| Ok(Default::default()))
+    }
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = false)]
+struct Mapping {
+    /// Input frame-to-file mapping (CSV or TOML), overriding order-based matching
+    ///
+    /// Relative image paths are resolved against the directory of FILE.
+    #[clap(
+        long,
+        value_parser,
+        value_name = "FILE",
+        requires = "id",
+        conflicts_with_all = ["images", "files_from", "pattern", "frames", "include_gaps"]
+    )]
+    mapping: Option<clio::Input>,
+}
+
+impl Mapping {
+    /// Read & parse the given frame-to-file mapping (if any)
+    fn into_mapping(self) -> Result<Option<matching::Mapping>> {
+        self.mapping
+            .map(|mut input| {
+                let path = input.path().path().to_owned();
+                let mapping = match mime_guess::from_path(&path)
+                    .first_or_octet_stream()
+                    .essence_str()
+                {
+                    "text/csv" => {
+                        let mut buf = String::new();
+                        input.read_to_string(&mut buf).wrap_err_with(|| {
+                            format!("Failed to read frame mapping from {}", path.display())
+                        })?;
+                        matching::Mapping::from_csv(&buf).wrap_err_with(|| {
+                            format!("Failed to parse frame mapping from {}", path.display())
+                        })?
+                    }
+                    _ => read_toml::<matching::Mapping>(input, "frame mapping")?,
+                };
+                Ok(mapping.relative_to(path.parent().unwrap_or(&path)))
+            })
+            .transpose()
+    }
+}
+
+#[derive(Args)]
+struct Matching {
+    /// Use data from roll with id ID
+    ///
+    /// If omitted, the roll ID of each image is inferred from its file name
+    /// (using the `roll` part of PATTERN) or the name of its parent directory.
+    #[clap(long, short)]
+    id: Option<String>,
+
+    /// Match images to frames by file name instead of by order
+    ///
+    /// PATTERN is either a template such as `{date}-{roll}+{frame:03}`, matching
+    /// the entire file name (excluding the extension), or a regular expression
+    /// with named `roll` and `frame` capture groups.
+    #[clap(long, short = 'p', value_name = "PATTERN")]
+    pattern: Option<matching::FilenamePattern>,
+
+    /// Match images by order to the given frame numbers, e.g. `1-4,6-36`
+    #[clap(long, value_name = "FRAMES", conflicts_with = "pattern")]
+    frames: Option<matching::FrameSelection>,
+
+    /// Match images by order to all frames, including unlogged frames
+    #[clap(long, conflicts_with_all = ["pattern", "frames"])]
+    include_gaps: bool,
+
+    /// Match variants following an image (e.g. `001-crop.tiff`) by order with its frame
+    #[clap(long, conflicts_with_all = ["pattern", "mapping"])]
+    group_variants: bool,
+
+    #[clap(flatten)]
+    mapping: Mapping,
+}
+
+/// Images matched with a frame (if any) of a roll
+type Pairs<'a> = Vec<(
+    &'a rolls::Roll,
+    Option<&'a rolls::Frame>,
+    negative::Negative,
+)>;
+
+/// Images whose frame number (of a roll) has no log entry
+type Unmatched<'a> = Vec<(&'a rolls::Roll, usize, negative::Negative)>;
+
+/// Paths of the roll data files read, keyed by roll ID
+type Sources = std::collections::HashMap<String, PathBuf>;
+
+impl Matching {
+    /// Fall back to the file name pattern of the given configuration
+    ///
+    /// The configured pattern is only used if no other way of matching images
+    /// to frames was given on the command line.
+    fn with_config(mut self, config: &config::Config) -> Self {
+        if self.mapping.mapping.is_none() && self.frames.is_none() && !self.include_gaps {
+            self.pattern = self.pattern.or_else(|| config.pattern.clone());
+        }
+        self
+    }
+
+    /// Load either the given roll, or all rolls if no ID was given
+    ///
+    /// Returns `None` (after reporting the missing roll) if no roll with the
+    /// given ID could be found. The rolls are returned along with the path of
+    /// the file each roll was read from, keyed by roll ID.
+    fn load_rolls(
+        &self,
+        film_roll: FilmRoll,
+        config: &config::Config,
+        records: &development::Records,
+        inventory: &gear::Inventory,
+    ) -> Result<Option<(Vec<rolls::Roll>, Sources)>> {
+        let mut sources = Sources::new();
+        let all_rolls = film_roll
+            .into_sourced_rolls(config)?
+            .map_ok(|(roll, path)| {
+                sources.entry(roll.id.clone()).or_insert(path);
+                roll
+            });
+        let rolls: Vec<rolls::Roll> = match &self.id {
+            Some(id) => match cmds::find_roll(all_rolls, id)? {
+                Some(roll) => vec![roll],
+                None => {
+                    println!("Could not find film roll with ID `{id}`");
+                    return Ok(None);
+                }
+            },
+            None => all_rolls.try_collect()?,
+        };
+        let rolls = rolls
+            .into_iter()
+            .map(|roll| roll.with_development(records).with_gear(inventory))
+            .collect_vec();
+        Ok(Some((rolls, sources)))
+    }
+
+    /// Match the given images with the frames of the given rolls
+    ///
+    /// All images are matched before returning, such that nothing is modified
+    /// unless all images could be matched. Images whose frame has no log entry
+    /// are returned separately.
+    fn match_images(
+        self,
+        rolls: &[rolls::Roll],
+        images: Images,
+    ) -> Result<(Pairs<'_>, Unmatched<'_>)> {
+        let mapping = self.mapping.into_mapping()?;
+
+        // Group images by roll, inferring the roll ID if not given
+        let groups = match &self.id {
+            Some(_) => vec![(&rolls[0], images.into_negatives()?.try_collect()?)],
+            None => cmds::group_negatives_by_roll(
+                rolls,
+                images.into_negatives()?,
+                self.pattern.as_ref(),
+            )?,
+        };
+
+        // Match frames & images of each roll
+        let mut pairs = Vec::new();
+        let mut unmatched = Vec::new();
+        for (roll, negatives) in groups {
+            let negatives = negatives.into_iter().map(Ok);
+            let (matched, missing) = match (&mapping, &self.pattern) {
+                (Some(mapping), _) => {
+                    (cmds::match_negatives_by_mapping(roll, mapping)?, Vec::new())
+                }
+                (None, Some(pattern)) => cmds::match_negatives_by_name(roll, negatives, pattern)?,
+                (None, None) if self.frames.is_some() || self.include_gaps => {
+                    cmds::match_negatives_with_gaps(
+                        roll,
+                        self.frames.as_ref(),
+                        negatives,
+                        self.group_variants,
+                    )?
+                }
+                (None, None) => (
+                    cmds::match_negatives(roll.frames.iter(), negatives, self.group_variants)?,
+                    Vec::new(),
+                ),
+            };
+            pairs.extend(matched.into_iter().map(|(f, n)| (roll, Some(f), n)));
+            unmatched.extend(missing.into_iter().map(|(f, n)| (roll, f, n)));
+        }
+        Ok((pairs, unmatched))
+    }
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = false)]
 struct Metadata {
     /// Author metadata
+    ///
+    /// If omitted, the `metadata` file of the configuration file is used.
     #[clap(long, short = 'm', value_parser, value_name = "FILE")]
-    meta: clio::Input,
+    meta: Option<clio::Input>,
 }
 
 impl Metadata {
-    /// Read & parse the given author metadata file
-    fn into_meta(mut self) -> Result<metadata::Metadata> {
-        let mut buf = String::new();
-        self.meta.read_to_string(&mut buf).wrap_err_with(|| {
-            format!(
-                "Failed to read author metadata from {}",
-                self.meta.path().display()
-            )
-        })?;
-        toml::de::from_str(&buf).wrap_err_with(|| {
-            format!(
-                "Failed to parse author metadata from {}",
-                self.meta.path().display()
-            )
-        })
+    /// Read & parse the given (or configured) author metadata file
+    fn into_meta(self, config: &config::Config) -> Result<metadata::Metadata> {
+        let input = match (self.meta, &config.metadata) {
+            (Some(input), _) => input,
+            (None, Some(path)) => clio::Input::new(path).wrap_err_with(|| {
+                format!("Failed to read author metadata from {}", path.display())
+            })?,
+            (None, None) => bail!(
+                "No author metadata given; use `--meta` or set `metadata` in the configuration file"
+            ),
+        };
+        read_toml(input, "author metadata")
     }
 }
 
 #[derive(Args)]
-#[group(required = false, multiple = false)]
+#[group(required = false, multiple = true)]
+struct Scan {
+    /// Scanner (or camera scanning rig) make & model
+    #[clap(long, value_name = "NAME")]
+    scanner: Option<String>,
+
+    /// Scanning software
+    #[clap(long, value_name = "NAME")]
+    scan_software: Option<String>,
+
+    /// Scanning resolution, in pixels per inch
+    #[clap(long, value_name = "PPI")]
+    scan_resolution: Option<u32>,
+
+    /// Scanning date (and optionally time)
+    #[clap(long, value_name = "DATE")]
+    scan_date: Option<types::TomlDateTime>,
+
+    /// Film holder used when scanning
+    #[clap(long, value_name = "NAME")]
+    film_holder: Option<String>,
+}
+
+impl Scan {
+    /// Merge the given arguments into a (possibly empty) scanning profile
+    ///
+    /// Arguments given on the command line take precedence over the profile,
+    /// and `None` is returned if the resulting profile is empty.
+    fn into_scan(self, profile: Option<metadata::Scan>) -> Option<metadata::Scan> {
+        let profile = profile.unwrap_or_default();
+        let scan = metadata::Scan {
+            scanner: self.scanner.or(profile.scanner),
+            software: self.scan_software.or(profile.software),
+            resolution: self.scan_resolution.or(profile.resolution),
+            date: self.scan_date.map(Into::into).or(profile.date),
+            holder: self.film_holder.or(profile.holder),
+        };
+        (scan != metadata::Scan::default()).then_some(scan)
+    }
+}
+
+#[derive(Args)]
+struct Merge {
+    /// Merge existing metadata of GROUP using MODE, e.g. `location=keep`
+    ///
+    /// MODE is `overwrite` (the default), `keep`, `fill-missing` or `append`
+    /// (adding to lists such as the authors). Without a GROUP, the mode applies
+    /// to all groups. Overrides the `[merge]` policy of the configuration file.
+    #[clap(long = "merge", value_name = "[GROUP=]MODE")]
+    merge_rules: Vec<merge::MergeRule>,
+}
+
+impl Merge {
+    /// Combine the given rules with the configured merge policy
+    fn into_policy(self, config: &config::Config) -> merge::MergePolicy {
+        config.merge.clone().with_rules(self.merge_rules)
+    }
+}
+
+#[derive(Args)]
+#[group(required = false, multiple = true)]
 struct Images {
-    /// Image file(s) to modify
+    /// Image file(s) to modify, or directories containing them
     #[clap(value_parser)]
     images: Vec<PathBuf>,
+
+    /// Search directories for images recursively
+    #[clap(long)]
+    recursive: bool,
+
+    /// Read image paths from FILE, one per line (`-` for standard input)
+    #[clap(long, value_parser, value_name = "FILE")]
+    files_from: Option<clio::Input>,
+
+    /// Keep images in the given order instead of sorting them in natural order
+    #[clap(long)]
+    keep_order: bool,
 }
 
 impl Images {
     /// Read metadata from all input images
-    fn into_negatives(self) -> impl Iterator<Item = Result<negative::Negative>> {
-        self.images
+    ///
+    /// Directories are expanded into the images they contain, and all images
+    /// are sorted in natural order (unless `--keep-order` is given) before
+    /// being read.
+    fn into_negatives(self) -> Result<impl Iterator<Item = Result<negative::Negative>>> {
+        let mut paths = self.images;
+        if let Some(input) = self.files_from {
+            let path = input.path().to_string();
+            let list = inputs::read_file_list(BufReader::new(input))
+                .wrap_err_with(|| format!("Failed to read image list from {path}"))?;
+            paths.extend(list);
+        }
+        let images = inputs::collect_images(paths, self.recursive, self.keep_order)
+            .wrap_err("Failed to collect input images")?;
+        Ok(images
             .into_iter()
-            .map(|p| negative::Negative::new_from_path(p.as_ref()).map_err(Into::into))
+            .map(|p| negative::Negative::new_from_path(p.as_ref()).map_err(Into::into)))
+    }
+}
+
+/// Output format of `stats`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(ValueEnum)]
+enum StatsFormat {
+    /// Human-readable tables
+    Table,
+    /// JSON object
+    Json,
+}
+
+impl StatsFormat {
+    /// The format corresponding to a configured output format, if any
+    ///
+    /// Statistics have no record-based representation, so the tabular record
+    /// formats (e.g. CSV) fall back to human-readable tables.
+    fn from_output(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Json => Self::Json,
+            _ => Self::Table,
+        }
     }
 }
 
+/// Roll data format written by `export`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(ValueEnum)]
+enum ExportFormat {
+    /// Film Rolls XML
+    #[value(alias = "xml")]
+    Filmrolls,
+    /// lightme JSON
+    #[value(alias = "json")]
+    Lightme,
+    /// Native TOML roll data
+    Toml,
+    /// Flat CSV, one row per frame
+    Csv,
+}
+
+/// Map format written by `map`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(ValueEnum)]
+enum MapFormat {
+    /// GPX waypoints, with one track per roll
+    Gpx,
+    /// KML placemarks, with one folder per roll
+    Kml,
+    /// GeoJSON point features
+    #[value(name = "geojson")]
+    GeoJson,
+}
+
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // constructed once, when parsing arguments
 enum Commands {
     /// List ID and additional data for all film rolls in input
     ListRolls {
         #[clap(flatten)]
         film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        filter: RollFilter,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
     },
 
     /// List frames from film roll with ID in input
@@ -168,21 +631,200 @@ enum Commands {
         /// Use data from roll with id ID
         #[clap(long, short)]
         id: String,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
     },
 
-    /// Write EXIF tags to a set of images using data from film roll with ID in input
-    Tag {
+    /// List frames matching the given filters across all film rolls in input
+    FindFrames {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        filter: RollFilter,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
+    },
+
+    /// Show roll data and development record for film roll with ID in input
+    ShowRoll {
         #[clap(flatten)]
         film_roll: FilmRoll,
 
+        #[clap(flatten)]
+        development: Development,
+
         /// Use data from roll with id ID
         #[clap(long, short)]
         id: String,
+    },
+
+    /// Show shooting statistics for all film rolls in input
+    ///
+    /// Counts logged frames per camera, lens, film stock, year, aperture,
+    /// shutter speed and focal length, and summarizes the number of frames
+    /// per roll and the number of days each roll was loaded.
+    Stats {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        filter: RollFilter,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<StatsFormat>,
+    },
+
+    /// Export film rolls in input to another roll data format
+    ///
+    /// Development records and gear serial numbers are included in the output
+    /// where the format supports them.
+    Export {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        development: Development,
+
+        #[clap(flatten)]
+        gear: Gear,
+
+        #[clap(flatten)]
+        filter: RollFilter,
+
+        /// Output format
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        format: ExportFormat,
+
+        /// Output file
+        #[clap(long, short, value_parser, value_name = "FILE", default_value = "-")]
+        output: clio::Output,
+    },
+
+    /// Export positions of frames in input as a GPX, KML or GeoJSON map
+    ///
+    /// Each frame matching the given filters is written with its roll ID,
+    /// frame number, date, film, lens and exposure. If images are given, they
+    /// are matched with the frames of their rolls (by order, or by file name
+    /// if PATTERN is given) and linked from the corresponding frames.
+    Map {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        filter: RollFilter,
+
+        /// Output format
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        format: MapFormat,
+
+        /// Output file
+        #[clap(long, short, value_parser, value_name = "FILE", default_value = "-")]
+        output: clio::Output,
+
+        /// Match images to frames by file name instead of by order
+        ///
+        /// PATTERN is either a template such as `{date}-{roll}+{frame:03}`, matching
+        /// the entire file name (excluding the extension), or a regular expression
+        /// with named `roll` and `frame` capture groups. The roll ID of each image
+        /// is inferred from its file name (using the `roll` part of PATTERN) or
+        /// the name of its parent directory.
+        #[clap(long, short = 'p', value_name = "PATTERN")]
+        pattern: Option<matching::FilenamePattern>,
+
+        #[clap(flatten)]
+        images: Images,
+    },
+
+    /// Write EXIF tags to a set of images using data from film roll with ID in input
+    Tag {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        development: Development,
+
+        #[clap(flatten)]
+        gear: Gear,
+
+        #[clap(flatten)]
+        matching: Matching,
+
+        /// Tag images of unlogged frames using roll data only
+        #[clap(long)]
+        tag_unlogged: bool,
+
+        /// Also apply author metadata from FILE, in the same pass
+        ///
+        /// The copyright year is taken from the date of each frame. Any scanning
+        /// profile in FILE is used as a fallback for the scanning options.
+        #[clap(long, short = 'm', value_parser, value_name = "FILE")]
+        meta: Option<clio::Input>,
+
+        #[clap(flatten)]
+        scan: Scan,
+
+        #[clap(flatten)]
+        merge: Merge,
 
         /// Don't actually modify any files
-        #[clap(long, short = 'n')]
+        #[clap(long, short = 'n', overrides_with = "no_dry_run")]
         dry_run: bool,
 
+        /// Modify files even if `dry-run` is set in the configuration file
+        #[clap(long, overrides_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// List every EXIF tag and XMP property added, changed or removed
+        ///
+        /// Changes are described as text, or as one record per field using any
+        /// other output format than `table`.
+        #[clap(long)]
+        diff: bool,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
+
+        #[clap(flatten)]
+        images: Images,
+    },
+
+    /// Verify that a set of images carry the data of film roll with ID in input
+    ///
+    /// Reports every EXIF tag or XMP property which `tag` would have written
+    /// differently, without modifying any files.
+    Verify {
+        #[clap(flatten)]
+        film_roll: FilmRoll,
+
+        #[clap(flatten)]
+        development: Development,
+
+        #[clap(flatten)]
+        gear: Gear,
+
+        #[clap(flatten)]
+        matching: Matching,
+
+        #[clap(flatten)]
+        merge: Merge,
+
+        #[clap(flatten)]
+        images: Images,
+    },
+
+    /// Show film-related metadata already held by a set of images
+    Inspect {
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
+
         #[clap(flatten)]
         images: Images,
     },
@@ -192,123 +834,605 @@ enum Commands {
         #[clap(flatten)]
         metadata: Metadata,
 
+        #[clap(flatten)]
+        scan: Scan,
+
+        #[clap(flatten)]
+        merge: Merge,
+
         /// Don't actually modify any files
-        #[clap(long, short = 'n')]
+        #[clap(long, short = 'n', overrides_with = "no_dry_run")]
         dry_run: bool,
 
+        /// Modify files even if `dry-run` is set in the configuration file
+        #[clap(long, overrides_with = "dry_run")]
+        no_dry_run: bool,
+
+        /// List every EXIF tag and XMP property added, changed or removed
+        ///
+        /// Changes are described as text, or as one record per field using any
+        /// other output format than `table`.
+        #[clap(long)]
+        diff: bool,
+
+        /// Output format [default: table, unless configured otherwise]
+        #[clap(long, short, value_enum, value_name = "FORMAT")]
+        output: Option<OutputFormat>,
+
         #[clap(flatten)]
         images: Images,
     },
+
+    /// Restore the original metadata of images modified by a previous run
+    ///
+    /// Before `tag` or `apply-metadata` modify any image, its original metadata
+    /// is backed up. Without RUN, all backed-up runs are listed; with RUN, every
+    /// image modified by that run is restored to its state before the run.
+    Restore {
+        /// ID of the run to restore
+        run: Option<String>,
+
+        /// Don't actually modify any files
+        #[clap(long, short = 'n')]
+        dry_run: bool,
+    },
 }
 
 impl Commands {
     /// Run the selected subcommand
-    fn run(self) -> Result<ExitCode> {
+    fn run(self, config: &config::Config) -> Result<ExitCode> {
         match self {
-            Self::ListRolls { film_roll } => {
-                let table = cmds::list_rolls(film_roll.into_rolls())?;
-                println!("{}", Self::format_table(table).trim_fmt());
+            Self::ListRolls {
+                film_roll,
+                filter,
+                output,
+            } => {
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                let filter = filter.into_filter();
+                let rolls = film_roll
+                    .into_rolls(config)?
+                    .filter_ok(|roll| filter.matches_roll(roll));
+                if output == OutputFormat::Table {
+                    let table = cmds::list_rolls(rolls)?;
+                    println!("{}", output::format_table(table).trim_fmt());
+                } else {
+                    let records = cmds::roll_records(rolls)?;
+                    print!("{}", output::format_records(&records, output)?);
+                }
                 Ok(ExitCode::SUCCESS)
             }
-            Self::ListFrames { film_roll, id } => {
-                if let Some(roll) = cmds::find_roll(film_roll.into_rolls(), &id)? {
-                    let table = cmds::list_frames(roll);
-                    println!("{}", Self::format_table(table).trim_fmt());
+            Self::ListFrames {
+                film_roll,
+                id,
+                output,
+            } => {
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                if let Some(roll) = cmds::find_roll(film_roll.into_rolls(config)?, &id)? {
+                    if output == OutputFormat::Table {
+                        let table = cmds::list_frames(roll);
+                        println!("{}", output::format_table(table).trim_fmt());
+                    } else {
+                        let records = cmds::frame_records(&roll);
+                        print!("{}", output::format_records(&records, output)?);
+                    }
                     Ok(ExitCode::SUCCESS)
                 } else {
                     println!("Could not find film roll with ID `{id}`");
                     Ok(ExitCode::FAILURE)
                 }
             }
-            Self::Tag {
+            Self::FindFrames {
                 film_roll,
+                filter,
+                output,
+            } => {
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                let filter = filter.into_filter();
+                let mut rolls: Vec<_> = film_roll
+                    .into_rolls(config)?
+                    .filter_ok(|roll| filter.matches_roll(roll))
+                    .try_collect()?;
+                rolls.sort_by(|a, b| a.id.cmp(&b.id));
+                let frames = cmds::find_frames(&rolls, &filter);
+                if output == OutputFormat::Table {
+                    let table = cmds::list_found_frames(frames);
+                    println!("{}", output::format_table(table).trim_fmt());
+                } else {
+                    let records = cmds::found_frame_records(frames);
+                    print!("{}", output::format_records(&records, output)?);
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::ShowRoll {
+                film_roll,
+                development,
                 id,
+            } => {
+                let records = development.into_records()?;
+                if let Some(roll) = cmds::find_roll(film_roll.into_rolls(config)?, &id)? {
+                    let table = cmds::show_roll(&roll.with_development(&records));
+                    println!("{}", output::format_table(table).trim_fmt());
+                    Ok(ExitCode::SUCCESS)
+                } else {
+                    println!("Could not find film roll with ID `{id}`");
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+            Self::Stats {
+                film_roll,
+                filter,
+                output,
+            } => {
+                let output = output
+                    .or(config.output.map(StatsFormat::from_output))
+                    .unwrap_or(StatsFormat::Table);
+                let filter = filter.into_filter();
+                let rolls = film_roll
+                    .into_rolls(config)?
+                    .filter_ok(|roll| filter.matches_roll(roll));
+                let stats = cmds::roll_statistics(rolls)?;
+                match output {
+                    StatsFormat::Table => {
+                        let tables = cmds::show_statistics(&stats)
+                            .into_iter()
+                            .map(|table| output::format_table(table).trim_fmt());
+                        println!("{}", tables.format("\n\n"));
+                    }
+                    StatsFormat::Json => {
+                        println!("{}", serde_json::to_string_pretty(&stats)?);
+                    }
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::Export {
+                film_roll,
+                development,
+                gear,
+                filter,
+                format,
+                mut output,
+            } => {
+                let filter = filter.into_filter();
+                let records = development.into_records()?;
+                let inventory = gear.into_inventory()?;
+                let rolls: Vec<_> = film_roll
+                    .into_rolls(config)?
+                    .filter_ok(|roll| filter.matches_roll(roll))
+                    .map_ok(|roll| roll.with_development(&records).with_gear(&inventory))
+                    .try_collect()?;
+                let path = output.path().to_string();
+                match format {
+                    ExportFormat::Filmrolls => rolls::to_filmrolls(&rolls, &mut output),
+                    ExportFormat::Lightme => rolls::to_lightme(&rolls, &mut output),
+                    ExportFormat::Toml => rolls::to_toml(&rolls, &mut output),
+                    ExportFormat::Csv => rolls::to_csv(&rolls, &mut output),
+                }
+                .wrap_err_with(|| format!("Failed to write roll data to {path}"))?;
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::Map {
+                film_roll,
+                filter,
+                format,
+                mut output,
+                pattern,
+                images,
+            } => {
+                let pattern = pattern.or_else(|| config.pattern.clone());
+                let filter = filter.into_filter();
+                let mut rolls: Vec<_> = film_roll
+                    .into_rolls(config)?
+                    .filter_ok(|roll| filter.matches_roll(roll))
+                    .try_collect()?;
+                rolls.sort_by(|a, b| a.id.cmp(&b.id));
+
+                // Match images with the frames of their rolls, if any were given
+                let negatives = images.into_negatives()?;
+                let mut images = Vec::new();
+                for (roll, negatives) in
+                    cmds::group_negatives_by_roll(&rolls, negatives, pattern.as_ref())?
+                {
+                    let negatives = negatives.into_iter().map(Ok);
+                    images.extend(match &pattern {
+                        Some(pattern) => cmds::match_negatives_by_name(roll, negatives, pattern)?.0,
+                        None => cmds::match_negatives(roll.frames.iter(), negatives, false)?,
+                    });
+                }
+
+                let waypoints =
+                    cmds::find_frames(&rolls, &filter).map(|(roll, number, frame)| map::Waypoint {
+                        roll,
+                        number,
+                        frame,
+                        image: images
+                            .iter()
+                            .find(|(f, _)| std::ptr::eq(*f, frame))
+                            .map(|(_, negative)| negative.path()),
+                    });
+                let path = output.path().to_string();
+                match format {
+                    MapFormat::Gpx => map::to_gpx(waypoints, &mut output),
+                    MapFormat::Kml => map::to_kml(waypoints, &mut output),
+                    MapFormat::GeoJson => map::to_geojson(waypoints, &mut output),
+                }
+                .wrap_err_with(|| format!("Failed to write map to {path}"))?;
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::Tag {
+                film_roll,
+                development,
+                gear,
+                matching,
+                tag_unlogged,
+                meta,
+                scan,
+                merge,
                 dry_run,
+                no_dry_run,
+                diff,
+                output,
                 images,
             } => {
-                if let Some(roll) = cmds::find_roll(film_roll.into_rolls(), &id)? {
-                    // Match frames & images, apply metadata, and optionally save to file
-                    let negatives =
-                        cmds::match_negatives(roll.frames.iter(), images.into_negatives())?
-                            .into_iter()
-                            .map(|(frame, mut negative)| {
-                                negative.apply_roll_data(&roll)?;
-                                negative.apply_frame_data(frame)?;
-                                if !dry_run {
-                                    negative.save()?;
-                                }
-                                Ok(negative)
-                            });
-
-                    // Print a brief summary of the images being modified
-                    let table = cmds::list_negatives(negatives)?;
-                    println!("{}", Self::format_table(table).trim_fmt());
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                let dry_run = dry_run || (config.write.dry_run && !no_dry_run);
+                let metadata: Option<metadata::Metadata> = meta
+                    .map(|input| read_toml(input, "author metadata"))
+                    .transpose()?;
+                let scan = scan.into_scan(metadata.as_ref().and_then(|m| m.scan.clone()));
+                let policy = merge.into_policy(config);
+                let records = development.into_records()?;
+                let inventory = gear.into_inventory()?;
+
+                // Load rolls, and match frames & images before modifying anything
+                let matching = matching.with_config(config);
+                let Some((rolls, sources)) =
+                    matching.load_rolls(film_roll, config, &records, &inventory)?
+                else {
+                    return Ok(ExitCode::FAILURE);
+                };
+                let (mut pairs, mut unmatched) = matching.match_images(&rolls, images)?;
+                let mut numbers = pairs
+                    .iter()
+                    .map(|(roll, frame, _)| frame.and_then(|frame| roll.frame_number(frame)))
+                    .collect_vec();
+
+                // Optionally tag images of unlogged frames with roll data only, and
+                // otherwise report them (on stderr, if stdout is reserved for
+                // machine-readable output) without modifying any image
+                if tag_unlogged {
+                    numbers.extend(unmatched.iter().map(|(_, number, _)| Some(*number)));
+                    pairs.extend(unmatched.drain(..).map(|(roll, _, n)| (roll, None, n)));
+                } else if !unmatched.is_empty() {
+                    let unmatched = unmatched
+                        .into_iter()
+                        .map(|(roll, frame, negative)| (roll.id.as_str(), frame, negative));
+                    let table = output::format_table(cmds::list_unmatched(unmatched));
+                    let hint = "No images were tagged; use `--tag-unlogged` to tag them \
+                                using roll data only";
+                    if output == OutputFormat::Table {
+                        println!("No frame logged for the following images:");
+                        println!("{}", table.trim_fmt());
+                        println!("{hint}");
+                    } else {
+                        eprintln!("No frame logged for the following images:");
+                        eprintln!("{}", table.trim_fmt());
+                        eprintln!("{hint}");
+                    }
+                    return Ok(ExitCode::FAILURE);
+                }
+
+                // Link multiple scans of the same frame to each other
+                let originals = pairs.iter().map(|(_, _, n)| n.clone()).collect_vec();
+                cmds::link_variants(&mut pairs)?;
+
+                // Apply metadata, and optionally back up & save to file
+                let mut journal = backup::Journal::new(&config.backup_dir(), "tag");
+                let timestamp = chrono::SubsecRound::trunc_subsecs(chrono::Utc::now(), 0);
+                let pairs = pairs.into_iter().zip(numbers).zip(originals);
+                let negatives = pairs.map(|(((roll, frame, mut negative), number), original)| {
+                    let mut merger = merge::Merger::new(&policy);
+                    negative.apply_roll_data(roll, &mut merger)?;
+                    if let Some(frame) = frame {
+                        negative.apply_frame_data(frame, &mut merger)?;
+                    }
+                    if let Some(metadata) = &metadata {
+                        let date = frame.map(|frame| frame.datetime.date());
+                        negative.apply_author_data(metadata, &date, &mut merger)?;
+                    }
+                    if let Some(scan) = &scan {
+                        negative.apply_scan_data(scan, &mut merger)?;
+                    }
+
+                    // Only record provenance of (and save) images actually modified
+                    if negative.diff(&original).is_empty() {
+                        return Ok((original, negative));
+                    }
+                    negative.record_provenance(&negative::Provenance {
+                        roll: roll.id.clone(),
+                        frame: number,
+                        source: sources.get(&roll.id).cloned(),
+                        load: roll.load,
+                        unload: roll.unload,
+                        timestamp,
+                    })?;
+                    if !dry_run {
+                        Self::back_up(&mut journal, &negative)?;
+                        negative.save()?;
+                    }
+                    Ok((original, negative))
+                });
+
+                // Print a brief summary of (or the changes to) the images being modified
+                Self::print_negatives(negatives, diff, output)?;
+                Self::print_backup(&journal);
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::Verify {
+                film_roll,
+                development,
+                gear,
+                matching,
+                merge,
+                images,
+            } => {
+                let policy = merge.into_policy(config);
+                let records = development.into_records()?;
+                let inventory = gear.into_inventory()?;
+
+                // Load rolls, and match frames & images
+                let matching = matching.with_config(config);
+                let Some((rolls, _)) =
+                    matching.load_rolls(film_roll, config, &records, &inventory)?
+                else {
+                    return Ok(ExitCode::FAILURE);
+                };
+                let (pairs, unmatched) = matching.match_images(&rolls, images)?;
+
+                // Recompute what tagging would write, and compare with each image
+                let mut expected = pairs.clone();
+                cmds::link_variants(&mut expected)?;
+                let differences: Vec<_> = pairs
+                    .iter()
+                    .zip(expected)
+                    .map(
+                        |((_, _, actual), (roll, frame, mut expected))| -> Result<_> {
+                            let mut merger = merge::Merger::new(&policy);
+                            expected.apply_roll_data(roll, &mut merger)?;
+                            if let Some(frame) = frame {
+                                expected.apply_frame_data(frame, &mut merger)?;
+                            }
+                            Ok((actual.path(), actual.diff(&expected)))
+                        },
+                    )
+                    .try_collect()?;
+
+                // Report any differences, and any images which could not be matched
+                let mismatched = differences
+                    .iter()
+                    .flat_map(|(path, diff)| diff.iter().map(|d| (*path, d)))
+                    .collect_vec();
+                if !mismatched.is_empty() {
+                    let table = cmds::list_differences(mismatched.iter().copied());
+                    println!("{}", output::format_table(table).trim_fmt());
+                }
+                if unmatched.is_empty() && mismatched.is_empty() {
+                    println!("All images match the logged roll data");
                     Ok(ExitCode::SUCCESS)
+                } else if unmatched.is_empty() {
+                    Ok(ExitCode::FAILURE)
                 } else {
-                    println!("Could not find film roll with ID `{id}`");
+                    let unmatched = unmatched
+                        .into_iter()
+                        .map(|(roll, frame, negative)| (roll.id.as_str(), frame, negative));
+                    let table = cmds::list_unmatched(unmatched);
+                    println!("No frame logged for the following images:");
+                    println!("{}", output::format_table(table).trim_fmt());
                     Ok(ExitCode::FAILURE)
                 }
             }
+            Self::Inspect { output, images } => {
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                let details: Vec<_> = images
+                    .into_negatives()?
+                    .map_ok(|negative| negative.details())
+                    .try_collect()?;
+                match output {
+                    OutputFormat::Table => {
+                        let table = cmds::inspect_negatives(&details);
+                        println!("{}", output::format_table(table).trim_fmt());
+                    }
+                    _ => print!("{}", output::format_records(&details, output)?),
+                }
+                Ok(ExitCode::SUCCESS)
+            }
             Self::ApplyMetadata {
                 metadata,
+                scan,
+                merge,
                 dry_run,
+                no_dry_run,
+                diff,
+                output,
                 images,
             } => {
-                // Load negatives, apply metadata, and optionally save to file
-                let metadata = metadata.into_meta()?;
-                let negatives = images.into_negatives().map(|negative| {
+                let output = output.or(config.output).unwrap_or(OutputFormat::Table);
+                let dry_run = dry_run || (config.write.dry_run && !no_dry_run);
+                // Load negatives, apply metadata, and optionally back up & save to file
+                let metadata = metadata.into_meta(config)?;
+                let scan = scan.into_scan(metadata.scan.clone());
+                let policy = merge.into_policy(config);
+                let mut journal = backup::Journal::new(&config.backup_dir(), "apply-metadata");
+                let negatives = images.into_negatives()?.map(|negative| {
                     negative.and_then(|mut negative| {
-                        negative.apply_author_data(&metadata, &None)?;
-                        if !dry_run {
+                        let original = negative.clone();
+                        let mut merger = merge::Merger::new(&policy);
+                        negative.apply_author_data(&metadata, &None, &mut merger)?;
+                        if let Some(scan) = &scan {
+                            negative.apply_scan_data(scan, &mut merger)?;
+                        }
+
+                        // Only back up & save images actually modified
+                        if !dry_run && !negative.diff(&original).is_empty() {
+                            Self::back_up(&mut journal, &negative)?;
                             negative.save()?;
                         }
-                        Ok(negative)
+                        Ok((original, negative))
                     })
                 });
 
-                // Print a brief summary of the images being modified
+                // Print a brief summary of (or the changes to) the images being modified
+                Self::print_negatives(negatives, diff, output)?;
+                Self::print_backup(&journal);
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::Restore { run, dry_run } => {
+                let root = config.backup_dir();
+                let Some(run) = run else {
+                    let journals = backup::Journal::list(&root).wrap_err_with(|| {
+                        format!("Failed to read backups from {}", root.display())
+                    })?;
+                    let table = cmds::list_backups(&journals);
+                    println!("{}", output::format_table(table).trim_fmt());
+                    return Ok(ExitCode::SUCCESS);
+                };
+                let journal = backup::Journal::open(&root, &run)
+                    .wrap_err_with(|| format!("Failed to read backups from {}", root.display()))?;
+
+                // Restore each image, and print a brief summary of the restored images
+                let negatives = journal.files().map(|path| -> Result<_> {
+                    let restore = || -> Result<_> {
+                        if !dry_run {
+                            journal.restore(path)?;
+                        }
+                        Ok(negative::Negative::new_from_path(path)?)
+                    };
+                    restore().wrap_err_with(|| {
+                        format!("Failed to restore metadata of {}", path.display())
+                    })
+                });
                 let table = cmds::list_negatives(negatives)?;
-                println!("{}", Self::format_table(table).trim_fmt());
+                println!("{}", output::format_table(table).trim_fmt());
                 Ok(ExitCode::SUCCESS)
             }
         }
     }
 
-    // Apply formatting to the given table
-    fn format_table(mut table: comfy_table::Table) -> comfy_table::Table {
-        use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
-        use comfy_table::ContentArrangement;
-        table
-            .load_preset(UTF8_HORIZONTAL_ONLY)
-            .set_content_arrangement(ContentArrangement::Dynamic);
-        table
+    /// Back up the original metadata of the given negative before saving it
+    fn back_up(journal: &mut backup::Journal, negative: &negative::Negative) -> Result<()> {
+        journal.record(negative).wrap_err_with(|| {
+            format!(
+                "Failed to back up metadata of {}",
+                negative.path().display()
+            )
+        })
+    }
+
+    /// Report the run ID of the given journal, if any images were backed up
+    fn print_backup(journal: &backup::Journal) {
+        if journal.files().next().is_some() {
+            let id = journal.id();
+            eprintln!(
+                "Original metadata backed up as run {id} (undo using `filmrolls restore {id}`)"
+            );
+        }
     }
+
+    /// Print a summary of the given negatives in the given output format
+    ///
+    /// Negatives are given along with their original state; if `diff` is set,
+    /// the changes made to each negative are printed instead of the summary.
+    fn print_negatives<I>(negatives: I, diff: bool, output: OutputFormat) -> Result<()>
+    where
+        I: Iterator<Item = Result<(negative::Negative, negative::Negative)>>,
+    {
+        if diff {
+            let changes: Vec<_> = negatives
+                .map_ok(|(original, negative)| {
+                    let changes = cmds::negative_changes(&original, &negative);
+                    (negative.path().to_owned(), changes)
+                })
+                .try_collect()?;
+            if output == OutputFormat::Table {
+                for (path, changes) in &changes {
+                    print!("{}", cmds::describe_changes(path, changes));
+                }
+            } else {
+                let records = changes.into_iter().flat_map(|(_, c)| c).collect_vec();
+                print!("{}", output::format_records(&records, output)?);
+            }
+            return Ok(());
+        }
+        let negatives = negatives.map_ok(|(_, negative)| negative);
+        if output == OutputFormat::Table {
+            let table = cmds::list_negatives(negatives)?;
+            println!("{}", output::format_table(table).trim_fmt());
+        } else {
+            let records = cmds::negative_records(negatives)?;
+            print!("{}", output::format_records(&records, output)?);
+        }
+        Ok(())
+    }
+}
+
+/// Read & parse the given configuration file, or the default one (if any)
+///
+/// Settings given using `FILMROLLS_*` environment variables override those
+/// of the configuration file.
+fn load_config(path: Option<PathBuf>) -> Result<config::Config> {
+    let path = path.or_else(|| config::Config::default_path().filter(|p| p.exists()));
+    let config = match path {
+        Some(path) => {
+            let input = clio::Input::new(&path).wrap_err_with(|| {
+                format!("Failed to read configuration from {}", path.display())
+            })?;
+            let config: config::Config = read_toml(input, "configuration")?;
+            config.relative_to(path.parent().unwrap_or(&path))
+        }
+        None => config::Config::default(),
+    };
+    Ok(config.with_env(|name| std::env::var_os(name))?)
+}
+
+/// Read & parse a TOML file, describing its contents as `what` in errors
+fn read_toml<T: serde::de::DeserializeOwned>(mut input: clio::Input, what: &str) -> Result<T> {
+    let mut buf = String::new();
+    input
+        .read_to_string(&mut buf)
+        .wrap_err_with(|| format!("Failed to read {what} from {}", input.path().display()))?;
+    toml::de::from_str(&buf)
+        .wrap_err_with(|| format!("Failed to parse {what} from {}", input.path().display()))
 }
 
-enum RollIter<E, XmlIter, JsonIter>
+// The error variant is only ever created once per input file
+#[allow(clippy::large_enum_variant)]
+enum RollIter<E, XmlIter, JsonIter, TomlIter>
 where
     XmlIter: Iterator<Item = Result<rolls::Roll, E>>,
     JsonIter: Iterator<Item = Result<rolls::Roll, E>>,
+    TomlIter: Iterator<Item = Result<rolls::Roll, E>>,
 {
     XmlSource(XmlIter),
     JsonSource(JsonIter),
+    TomlSource(TomlIter),
     Error(std::iter::Once<Result<rolls::Roll, E>>),
 }
 
-impl<E, XmlIter, JsonIter> RollIter<E, XmlIter, JsonIter>
+impl<E, XmlIter, JsonIter, TomlIter> RollIter<E, XmlIter, JsonIter, TomlIter>
 where
     XmlIter: Iterator<Item = Result<rolls::Roll, E>>,
     JsonIter: Iterator<Item = Result<rolls::Roll, E>>,
+    TomlIter: Iterator<Item = Result<rolls::Roll, E>>,
 {
     pub fn from_error(error: E) -> Self {
         Self::Error(std::iter::once(Err(error)))
     }
 }
 
-impl<E, XmlIter, JsonIter> Iterator for RollIter<E, XmlIter, JsonIter>
+impl<E, XmlIter, JsonIter, TomlIter> Iterator for RollIter<E, XmlIter, JsonIter, TomlIter>
 where
     XmlIter: Iterator<Item = Result<rolls::Roll, E>>,
     JsonIter: Iterator<Item = Result<rolls::Roll, E>>,
+    TomlIter: Iterator<Item = Result<rolls::Roll, E>>,
 {
     type Item = Result<rolls::Roll, E>;
 
@@ -316,6 +1440,7 @@ where
         match self {
             Self::XmlSource(iter) => iter.next(),
             Self::JsonSource(iter) => iter.next(),
+            Self::TomlSource(iter) => iter.next(),
             Self::Error(iter) => iter.next(),
         }
     }
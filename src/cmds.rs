@@ -1,12 +1,148 @@
 //! Command-line interface implementations
+use std::path::PathBuf;
 use std::string::ToString;
 
-use chrono::Timelike;
-use color_eyre::eyre::{Report, Result};
+use chrono::{NaiveDateTime, Timelike};
+use color_eyre::eyre::{Report, Result, WrapErr};
 use comfy_table::Table;
 use itertools::{EitherOrBoth, Itertools};
+use rust_decimal::Decimal;
+use serde::Serialize;
 
-use crate::{negative, rolls};
+use crate::output::{Record, Rfc3339};
+use crate::types::{Aperture, ExposureBias, FilmSpeed, ShutterSpeed};
+use crate::{backup, filter, matching, negative, rolls, stats};
+
+/// Film roll record, as listed by `list-rolls`
+#[serde_with::serde_as]
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct RollRecord {
+    pub id: String,
+    pub frames: usize,
+    pub film: Option<rolls::Film>,
+    pub speed: FilmSpeed,
+    pub camera: Option<rolls::Camera>,
+    #[serde_as(as = "Rfc3339")]
+    pub load: NaiveDateTime,
+    #[serde_as(as = "Rfc3339")]
+    pub unload: NaiveDateTime,
+}
+
+impl Record for RollRecord {
+    const FIELDS: &'static [&'static str] =
+        &["id", "frames", "film", "speed", "camera", "load", "unload"];
+}
+
+/// Frame record, as listed by `list-frames`
+///
+/// The film and film speed are those of the individual frame (for sheet
+/// film), or otherwise those of the roll. All fields but the frame number
+/// are empty for frames which were not logged.
+#[serde_with::serde_as]
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct FrameRecord {
+    pub frame: usize,
+    pub film: Option<rolls::Film>,
+    pub speed: Option<FilmSpeed>,
+    pub lens: Option<rolls::Lens>,
+    #[serde(serialize_with = "rust_decimal::serde::float_option::serialize")]
+    pub focal_length: Option<Decimal>,
+    pub aperture: Option<Aperture>,
+    pub shutter_speed: Option<ShutterSpeed>,
+    pub compensation: Option<ExposureBias>,
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub date: Option<NaiveDateTime>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub note: Option<String>,
+}
+
+impl Record for FrameRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "frame",
+        "film",
+        "speed",
+        "lens",
+        "focal_length",
+        "aperture",
+        "shutter_speed",
+        "compensation",
+        "date",
+        "latitude",
+        "longitude",
+        "note",
+    ];
+}
+
+/// Frame record along with its roll ID, as listed by `find-frames`
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct FoundFrameRecord {
+    pub roll: String,
+    #[serde(flatten)]
+    pub frame: FrameRecord,
+}
+
+impl Record for FoundFrameRecord {
+    const FIELDS: &'static [&'static str] = &[
+        "roll",
+        "frame",
+        "film",
+        "speed",
+        "lens",
+        "focal_length",
+        "aperture",
+        "shutter_speed",
+        "compensation",
+        "date",
+        "latitude",
+        "longitude",
+        "note",
+    ];
+}
+
+/// Negative record, as listed by `tag` and `apply-metadata`
+#[serde_with::serde_as]
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct NegativeRecord {
+    pub roll: Option<String>,
+    pub frame: Option<usize>,
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub date: Option<NaiveDateTime>,
+    pub path: PathBuf,
+}
+
+impl Record for NegativeRecord {
+    const FIELDS: &'static [&'static str] = &["roll", "frame", "date", "path"];
+}
+
+/// Metadata change record, as listed by `tag` and `apply-metadata` using `--diff`
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct ChangeRecord {
+    pub path: PathBuf,
+    pub field: String,
+    pub change: Change,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl Record for ChangeRecord {
+    const FIELDS: &'static [&'static str] = &["path", "field", "change", "old", "new"];
+}
+
+/// Kind of change made to a metadata field
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Added,
+    Changed,
+    Removed,
+}
 
 /// Generate a `Table` containing the given `rolls`
 ///
@@ -37,14 +173,7 @@ where
             table.add_row(vec![
                 roll.id.to_string(),
                 roll.frames.len().to_string(),
-                format!(
-                    "{} @ {}",
-                    roll.film
-                        .as_ref()
-                        .map(ToString::to_string)
-                        .unwrap_or_default(),
-                    roll.speed
-                ),
+                format_film(&roll),
                 roll.camera
                     .as_ref()
                     .map(ToString::to_string)
@@ -62,6 +191,27 @@ where
         })
 }
 
+/// Collect records of the given `rolls`, sorted by roll ID
+///
+/// This is the machine-readable equivalent of [`list_rolls`], and likewise
+/// returns the first error of the input iterator (if any).
+pub fn roll_records<I>(rolls: I) -> Result<Vec<RollRecord>>
+where
+    I: Iterator<Item = Result<rolls::Roll>>,
+{
+    rolls
+        .map_ok(|roll| RollRecord {
+            frames: roll.frames.len(),
+            film: roll.film,
+            speed: roll.speed,
+            camera: roll.camera,
+            load: truncate_seconds(roll.load),
+            unload: truncate_seconds(roll.unload),
+            id: roll.id,
+        })
+        .process_results(|records| records.sorted_by(|a, b| a.id.cmp(&b.id)).collect())
+}
+
 /// Find a specific roll given its ID
 ///
 /// This function searches through the input iterator to find a matching
@@ -85,12 +235,14 @@ where
 /// Generate a `Table` containing information about a given roll
 ///
 /// This function generates a [comfy-table] `Table` containing information
-/// about all frames in the input film roll.
+/// about all frames in the input film roll. For sheet film, the frame number
+/// is labelled as the holder ID, and an additional column lists the film and
+/// film speed of each individual sheet.
 ///
 /// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
 pub fn list_frames(roll: rolls::Roll) -> Table {
     let mut table = Table::new();
-    table.set_header(vec![
+    let mut header = vec![
         "#",          // frame_nbr
         "Lens",       // frame.lens
         "Focal len.", // frame.focal_length
@@ -100,16 +252,22 @@ pub fn list_frames(roll: rolls::Roll) -> Table {
         "Date",       // frame.datetime
         "Location",   // frame.position
         "Notes",      // frame.note
-    ]);
+    ];
+    if roll.sheet_film {
+        header[0] = "Holder";
+        header.insert(1, "Film"); // frame.film + frame.speed
+    }
+    table.set_header(header);
     roll.frames
-        .into_iter()
+        .iter()
         .enumerate()
         .fold(table, |mut table, (idx, frame)| {
             let frame_nbr = idx + 1;
             table.add_row(
                 frame
+                    .as_ref()
                     .map(|frame| {
-                        vec![
+                        let mut row = vec![
                             frame_nbr.to_string(), //
                             frame
                                 .lens
@@ -147,7 +305,23 @@ pub fn list_frames(roll: rolls::Roll) -> Table {
                                 .as_ref()
                                 .map(ToString::to_string)
                                 .unwrap_or_default(),
-                        ]
+                        ];
+                        if roll.sheet_film {
+                            row.insert(
+                                1,
+                                format!(
+                                    "{} @ {}",
+                                    frame
+                                        .film
+                                        .as_ref()
+                                        .or(roll.film.as_ref())
+                                        .map(ToString::to_string)
+                                        .unwrap_or_default(),
+                                    frame.speed.unwrap_or(roll.speed)
+                                ),
+                            );
+                        }
+                        row
                     })
                     .unwrap_or_else(|| vec![frame_nbr.to_string()]),
             );
@@ -155,30 +329,491 @@ pub fn list_frames(roll: rolls::Roll) -> Table {
         })
 }
 
+/// Collect records of all frames in a given roll
+///
+/// This is the machine-readable equivalent of [`list_frames`].
+pub fn frame_records(roll: &rolls::Roll) -> Vec<FrameRecord> {
+    roll.frames
+        .iter()
+        .enumerate()
+        .map(|(idx, frame)| frame_record(roll, idx + 1, frame.as_ref()))
+        .collect()
+}
+
+/// Find all frames matching `filter` in the given `rolls`
+///
+/// Only logged frames are included, along with their roll and frame number.
+pub fn find_frames<'a>(
+    rolls: &'a [rolls::Roll],
+    filter: &'a filter::Filter,
+) -> impl Iterator<Item = (&'a rolls::Roll, usize, &'a rolls::Frame)> + 'a {
+    rolls.iter().flat_map(move |roll| {
+        roll.frames
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, frame)| Some((roll, idx + 1, frame.as_ref()?)))
+            .filter(|(roll, _, frame)| filter.matches_frame(roll, frame))
+    })
+}
+
+/// Generate a `Table` containing frames from several rolls
+///
+/// This function generates a [comfy-table] `Table` containing the roll ID,
+/// frame number, film and key exposure data of each of the input frames.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn list_found_frames<'a, I>(frames: I) -> Table
+where
+    I: IntoIterator<Item = (&'a rolls::Roll, usize, &'a rolls::Frame)>,
+{
+    fn format<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(ToString::to_string).unwrap_or_default()
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Roll",     // roll.id
+        "#",        // frame_nbr
+        "Film",     // frame.film + frame.speed
+        "Lens",     // frame.lens
+        "Aperture", // frame.aperture
+        "Shutter",  // frame.shutter_speed
+        "Date",     // frame.datetime
+        "Location", // frame.position
+    ]);
+    frames
+        .into_iter()
+        .fold(table, |mut table, (roll, frame_nbr, frame)| {
+            table.add_row(vec![
+                roll.id.clone(),
+                frame_nbr.to_string(),
+                format!(
+                    "{} @ {}",
+                    format(&frame.film.as_ref().or(roll.film.as_ref())),
+                    frame.speed.unwrap_or(roll.speed)
+                ),
+                format(&frame.lens),
+                format(&frame.aperture),
+                format(&frame.shutter_speed),
+                truncate_seconds(frame.datetime).to_string(),
+                frame.position.to_string(),
+            ]);
+            table
+        })
+}
+
+/// Collect records of frames from several rolls
+///
+/// This is the machine-readable equivalent of [`list_found_frames`].
+pub fn found_frame_records<'a, I>(frames: I) -> Vec<FoundFrameRecord>
+where
+    I: IntoIterator<Item = (&'a rolls::Roll, usize, &'a rolls::Frame)>,
+{
+    frames
+        .into_iter()
+        .map(|(roll, frame_nbr, frame)| FoundFrameRecord {
+            roll: roll.id.clone(),
+            frame: frame_record(roll, frame_nbr, Some(frame)),
+        })
+        .collect()
+}
+
+/// Generate a `Table` containing detailed information about a given roll
+///
+/// This function generates a two-column [comfy-table] `Table` containing
+/// the roll data, including the development record of the roll (if any).
+/// Fields missing from the development record are left empty.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn show_roll(roll: &rolls::Roll) -> Table {
+    let development = roll.development.clone().unwrap_or_default();
+    let mut table = Table::new();
+    table.add_rows(vec![
+        vec!["ID".to_owned(), roll.id.to_string()],
+        vec!["Film".to_owned(), format_film(roll)],
+        vec![
+            "Camera".to_owned(),
+            roll.camera
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        ],
+        vec!["Frames".to_owned(), roll.frames.len().to_string()],
+        vec![
+            "Loaded".to_owned(),
+            roll.load
+                .with_nanosecond(0)
+                .expect("should be possible to set nanoseconds to zero")
+                .to_string(),
+        ],
+        vec![
+            "Unloaded".to_owned(),
+            roll.unload
+                .with_nanosecond(0)
+                .expect("should be possible to set nanoseconds to zero")
+                .to_string(),
+        ],
+        vec![
+            "Developer".to_owned(),
+            development.developer_dilution().unwrap_or_default(),
+        ],
+        vec![
+            "Time".to_owned(),
+            development
+                .time
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        ],
+        vec![
+            "Temperature".to_owned(),
+            development
+                .temperature
+                .map(|t| format!("{} °C", t.normalize()))
+                .unwrap_or_default(),
+        ],
+        vec![
+            "Agitation".to_owned(),
+            development
+                .agitation
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        ],
+        vec![
+            "Push/pull".to_owned(),
+            development.push_pull().unwrap_or_default(),
+        ],
+        vec!["Lab".to_owned(), development.lab.unwrap_or_default()],
+        vec![
+            "Developed".to_owned(),
+            development
+                .date
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        ],
+    ]);
+    table
+}
+
+/// Compute shooting statistics of the given `rolls`
+///
+/// If any of the film rolls resolve to an error, this function will return
+/// that error instead; all rolls must be successfully parsed in order to
+/// compute valid statistics.
+pub fn roll_statistics<I>(rolls: I) -> Result<stats::Statistics>
+where
+    I: Iterator<Item = Result<rolls::Roll>>,
+{
+    let rolls: Vec<_> = rolls.try_collect()?;
+    Ok(stats::Statistics::new(&rolls))
+}
+
+/// Generate `Table`s containing the given shooting statistics
+///
+/// This function generates a two-column [comfy-table] `Table` summarizing
+/// the number of rolls & frames, followed by one `Table` listing the number
+/// of frames for each camera (along with its most-used film stock), lens,
+/// film stock, year, aperture, shutter speed and focal length. Tables which
+/// would be empty are left out.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn show_statistics(stats: &stats::Statistics) -> Vec<Table> {
+    fn summary(summary: Option<stats::Summary>) -> String {
+        summary
+            .map(|s| format!("{:.1} (min {:.1}, max {:.1})", s.mean, s.min, s.max))
+            .unwrap_or_default()
+    }
+
+    fn counts<T, F>(header: &str, counts: &[stats::Count<T>], format: F) -> Table
+    where
+        F: Fn(&T) -> String,
+    {
+        let mut table = Table::new();
+        table.set_header(vec![header, "Frames"]);
+        counts.iter().fold(table, |mut table, count| {
+            table.add_row(vec![format(&count.value), count.frames.to_string()]);
+            table
+        })
+    }
+
+    let mut overview = Table::new();
+    overview.add_rows(vec![
+        vec!["Rolls".to_owned(), stats.rolls.to_string()],
+        vec!["Frames".to_owned(), stats.frames.to_string()],
+        vec!["Frames per roll".to_owned(), summary(stats.frames_per_roll)],
+        vec!["Days loaded".to_owned(), summary(stats.days_loaded)],
+    ]);
+
+    let mut cameras = Table::new();
+    cameras.set_header(vec![
+        "Camera",         // count.value
+        "Frames",         // count.frames
+        "Most-used film", // camera_film.film
+    ]);
+    let cameras = stats.cameras.iter().fold(cameras, |mut table, count| {
+        table.add_row(vec![
+            count.value.to_string(),
+            count.frames.to_string(),
+            stats
+                .camera_films
+                .iter()
+                .find(|camera_film| camera_film.camera == count.value)
+                .map(|camera_film| camera_film.film.to_string())
+                .unwrap_or_default(),
+        ]);
+        table
+    });
+
+    [
+        overview,
+        cameras,
+        counts("Lens", &stats.lenses, ToString::to_string),
+        counts("Film", &stats.films, ToString::to_string),
+        counts("Year", &stats.years, ToString::to_string),
+        counts("Aperture", &stats.apertures, ToString::to_string),
+        counts("Shutter", &stats.shutter_speeds, ToString::to_string),
+        counts("Focal len.", &stats.focal_lengths, |focal_length| {
+            format!("{focal_length} mm")
+        }),
+    ]
+    .into_iter()
+    .filter(|table| table.row_count() > 0)
+    .collect()
+}
+
 /// Get a list of frame/negative pairs
 ///
 /// Constructs a list of frame/negative pairs by matching each input frame
 /// with the corresponding negative, where the order of the images is assumed
-/// to match the frame order. If the number of images does not match the
-/// number of frames, or if an error occurs while opening any image, an error
-/// is returned instead.
+/// to match the frame order. If `variants` is set, variants of an image (see
+/// [`matching::is_variant_of`]) immediately following it are matched with
+/// the same frame. If the number of images does not match the number of
+/// frames, or if an error occurs while opening any image, an error is
+/// returned instead.
 pub fn match_negatives<'a>(
     frames: impl Iterator<Item = &'a Option<rolls::Frame>>,
     negatives: impl Iterator<Item = Result<negative::Negative>>,
+    variants: bool,
 ) -> Result<Vec<(&'a rolls::Frame, negative::Negative)>> {
     frames
         .filter_map(|s| s.as_ref())
-        .zip_longest(negatives)
+        .zip_longest(group_variants(negatives, variants))
         .map(|pair| match pair {
             EitherOrBoth::Left(_) | EitherOrBoth::Right(_) => {
                 Err(Report::msg("Frame count does not match image count"))
             }
             EitherOrBoth::Both(_, Err(err)) => Err(err)?,
-            EitherOrBoth::Both(frame, Ok(negative)) => Ok((frame, negative)),
+            EitherOrBoth::Both(frame, Ok(group)) => {
+                Ok(group.into_iter().map(move |negative| (frame, negative)))
+            }
+        })
+        .flatten_ok()
+        .try_collect()
+}
+
+/// Group negatives by the film roll they belong to
+///
+/// The roll ID of each negative is inferred from its path (see
+/// [`matching::infer_roll`]), and the negatives are grouped by roll while
+/// retaining their relative order. If an error occurs while opening any
+/// image, or if the roll ID of any image cannot be inferred or does not
+/// match any of the given rolls, an error is returned instead.
+pub fn group_negatives_by_roll<'a>(
+    rolls: &'a [rolls::Roll],
+    negatives: impl Iterator<Item = Result<negative::Negative>>,
+    pattern: Option<&matching::FilenamePattern>,
+) -> Result<Vec<(&'a rolls::Roll, Vec<negative::Negative>)>> {
+    let mut groups: Vec<(&rolls::Roll, Vec<negative::Negative>)> = Vec::new();
+    for negative in negatives {
+        let negative = negative?;
+        let path = negative.path().display().to_string();
+        let id = matching::infer_roll(negative.path(), pattern)
+            .ok_or_else(|| Report::msg(format!("Could not infer roll ID: {path}")))?;
+        let roll = rolls.iter().find(|roll| roll.id == id).ok_or_else(|| {
+            Report::msg(format!("Could not find film roll with ID `{id}`: {path}"))
+        })?;
+        match groups.iter_mut().find(|(r, _)| std::ptr::eq(*r, roll)) {
+            Some((_, group)) => group.push(negative),
+            None => groups.push((roll, vec![negative])),
+        }
+    }
+    Ok(groups)
+}
+
+/// Frame/negative pairs, along with negatives lacking a logged frame
+pub type Matches<'a> = (
+    Vec<(&'a rolls::Frame, negative::Negative)>,
+    Vec<(usize, negative::Negative)>,
+);
+
+/// Get a list of frame/negative pairs, accounting for unlogged frames
+///
+/// Constructs a list of frame/negative pairs by matching the given frame
+/// numbers with the negatives, where the order of the images is assumed to
+/// match the order of the frame numbers. If no frame numbers are given, all
+/// frame slots of the roll are used, including frames which were not logged.
+/// Images matching a frame with no log entry are returned separately along
+/// with their frame number. As with [`match_negatives`], variants are matched
+/// with the same frame as the image they follow if `variants` is set. If the
+/// number of images does not match the number of frames, or if an error occurs
+/// while opening any image, an error is returned instead.
+pub fn match_negatives_with_gaps<'a>(
+    roll: &'a rolls::Roll,
+    selection: Option<&matching::FrameSelection>,
+    negatives: impl Iterator<Item = Result<negative::Negative>>,
+    variants: bool,
+) -> Result<Matches<'a>> {
+    let numbers = match selection {
+        Some(selection) => selection.iter().collect_vec(),
+        None => (1..=roll.frames.len()).collect_vec(),
+    };
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    for pair in numbers
+        .into_iter()
+        .zip_longest(group_variants(negatives, variants))
+    {
+        match pair {
+            EitherOrBoth::Left(_) | EitherOrBoth::Right(_) => {
+                Err(Report::msg("Frame count does not match image count"))?
+            }
+            EitherOrBoth::Both(_, Err(err)) => Err(err)?,
+            EitherOrBoth::Both(number, Ok(group)) => {
+                match number.checked_sub(1).and_then(|i| roll.frames.get(i)) {
+                    Some(Some(frame)) => matched.extend(group.into_iter().map(|n| (frame, n))),
+                    _ => unmatched.extend(group.into_iter().map(|n| (number, n))),
+                }
+            }
+        }
+    }
+    Ok((matched, unmatched))
+}
+
+/// Get a list of frame/negative pairs based on a frame-to-file mapping
+///
+/// Constructs a list of frame/negative pairs from an explicit mapping,
+/// ignoring the order of the images entirely. The mapping is validated
+/// against the given `roll` before any image is opened; if it maps unknown
+/// frames or missing files, or if an error occurs while opening any image,
+/// an error is returned instead.
+pub fn match_negatives_by_mapping<'a>(
+    roll: &'a rolls::Roll,
+    mapping: &matching::Mapping,
+) -> Result<Vec<(&'a rolls::Frame, negative::Negative)>> {
+    mapping
+        .validate(&roll.frames)
+        .wrap_err_with(|| format!("Invalid frame mapping for roll {}", roll.id))?;
+    mapping
+        .iter()
+        .map(|(number, path)| {
+            let frame = number
+                .checked_sub(1)
+                .and_then(|i| roll.frames.get(i))
+                .and_then(Option::as_ref)
+                .ok_or(matching::MappingError::UnknownFrame(number))?;
+            Ok((frame, negative::Negative::new_from_path(path)?))
         })
         .try_collect()
 }
 
+/// Get a list of frame/negative pairs based on file names
+///
+/// Constructs a list of frame/negative pairs by parsing the frame number
+/// (and optionally roll ID) from the file name of each image using the given
+/// `pattern`. Images whose frame has no log entry in the given `roll` are
+/// returned separately along with their frame number. Several variants of
+/// a frame (e.g. `001.tiff` and `001-crop.tiff`) may be matched with the same
+/// frame. If an error occurs while opening any image, if any file name does
+/// not match the pattern or names a different roll, or if several images
+/// without a variant suffix map to the same frame, an error is returned
+/// instead.
+pub fn match_negatives_by_name<'a>(
+    roll: &'a rolls::Roll,
+    negatives: impl Iterator<Item = Result<negative::Negative>>,
+    pattern: &matching::FilenamePattern,
+) -> Result<Matches<'a>> {
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+    let mut originals = std::collections::HashSet::new();
+    for negative in negatives {
+        let negative = negative?;
+        let path = negative.path().display().to_string();
+        let name = pattern
+            .parse_path(negative.path())
+            .ok_or_else(|| Report::msg(format!("File name does not match pattern: {path}")))?;
+        if let Some(id) = name.roll.as_ref().filter(|id| **id != roll.id) {
+            Err(Report::msg(format!(
+                "Image belongs to roll {id}, not {}: {path}",
+                roll.id
+            )))?;
+        }
+        if name.variant.is_none() && !originals.insert(name.frame) {
+            Err(Report::msg(format!(
+                "Multiple images match frame {}",
+                name.frame
+            )))?;
+        }
+        match name.frame.checked_sub(1).and_then(|i| roll.frames.get(i)) {
+            Some(Some(frame)) => matched.push((frame, negative)),
+            _ => unmatched.push((name.frame, negative)),
+        }
+    }
+    Ok((matched, unmatched))
+}
+
+/// Link all scans of the same frame to each other
+///
+/// Negatives matched with the same frame of the same roll are considered
+/// variants of a single scan. The variant with the shortest file name is
+/// taken to be the original, and all variants are given a shared XMP
+/// document ID (the existing ID of any variant, if one exists), with the other
+/// variants referring to the original as the image they were derived from.
+/// Existing document IDs are never overwritten; if the variants already have
+/// different document IDs, an error is returned instead.
+pub fn link_variants(
+    pairs: &mut [(&rolls::Roll, Option<&rolls::Frame>, negative::Negative)],
+) -> Result<()> {
+    let groups = pairs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (roll, frame, _))| {
+            Some(((*roll as *const _, (*frame)? as *const _), idx))
+        })
+        .into_group_map();
+    for indices in groups.into_values().filter(|indices| indices.len() > 1) {
+        let original = *indices
+            .iter()
+            .min_by_key(|idx| (pairs[**idx].2.path().as_os_str().len(), **idx))
+            .expect("groups should never be empty");
+        let (roll, _, negative) = &pairs[original];
+        let path = negative.path().to_owned();
+        let existing = indices
+            .iter()
+            .filter_map(|idx| pairs[*idx].2.document_id())
+            .unique()
+            .at_most_one()
+            .map_err(|_| {
+                Report::msg(format!(
+                    "Variants of a frame have conflicting document IDs: {}",
+                    path.display()
+                ))
+            })?;
+        let document_id = existing.unwrap_or_else(|| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            format!("xmp.did:{}/{stem}", roll.id)
+        });
+        for idx in indices {
+            let original = (idx != original).then(|| path.file_name()).flatten();
+            pairs[idx]
+                .2
+                .link_variant(&document_id, original.map(std::path::Path::new))?;
+        }
+    }
+    Ok(())
+}
+
 /// Generate a `Table` containing the given `rolls`
 ///
 /// This function generates a [comfy-table] `Table` containing information
@@ -194,14 +829,16 @@ where
 {
     let mut table = Table::new();
     table.set_header(vec![
-        "Roll", // negative.roll()
-        "Date", // negative.date()
-        "Path", // negative.path()
+        "Roll",  // negative.roll()
+        "Frame", // negative.frame()
+        "Date",  // negative.date()
+        "Path",  // negative.path()
     ]);
     negatives.try_fold(table, |mut table, negative| {
         let negative = negative?;
         table.add_row(vec![
             negative.roll().map(ToString::to_string).unwrap_or_default(),
+            negative.frame().map(|n| n.to_string()).unwrap_or_default(),
             negative
                 .date()
                 .as_ref()
@@ -213,42 +850,300 @@ where
     })
 }
 
+/// Collect records of the given `negatives`
+///
+/// This is the machine-readable equivalent of [`list_negatives`], and
+/// likewise returns the first error of the input iterator (if any).
+pub fn negative_records<I>(negatives: I) -> Result<Vec<NegativeRecord>>
+where
+    I: Iterator<Item = Result<negative::Negative>>,
+{
+    negatives
+        .map_ok(|negative| NegativeRecord {
+            roll: negative.roll().map(ToOwned::to_owned),
+            frame: negative.frame(),
+            date: negative.date(),
+            path: negative.path().to_owned(),
+        })
+        .try_collect()
+}
+
+/// Collect the metadata changes made to a negative
+///
+/// Every EXIF tag and XMP property of `modified` which was added, changed or
+/// removed compared to `original` is listed, ordered by field name.
+pub fn negative_changes(
+    original: &negative::Negative,
+    modified: &negative::Negative,
+) -> Vec<ChangeRecord> {
+    original
+        .diff(modified)
+        .into_iter()
+        .map(|difference| ChangeRecord {
+            path: modified.path().to_owned(),
+            field: difference.field,
+            change: match (&difference.actual, &difference.expected) {
+                (None, _) => Change::Added,
+                (_, None) => Change::Removed,
+                _ => Change::Changed,
+            },
+            old: difference.actual,
+            new: difference.expected,
+        })
+        .collect()
+}
+
+/// Describe the metadata changes made to a negative as text
+///
+/// The path of the negative is followed by one line per change, marking
+/// added fields with `+`, changed fields with `~` and removed fields with
+/// `-` (akin to a unified diff).
+pub fn describe_changes(path: &std::path::Path, changes: &[ChangeRecord]) -> String {
+    let mut text = format!("{}\n", path.display());
+    if changes.is_empty() {
+        text += "  (no changes)\n";
+    }
+    for change in changes {
+        let (old, new) = (
+            change.old.as_deref().unwrap_or_default(),
+            change.new.as_deref().unwrap_or_default(),
+        );
+        text += &match change.change {
+            Change::Added => format!("  + {}: {new}\n", change.field),
+            Change::Changed => format!("  ~ {}: {old} → {new}\n", change.field),
+            Change::Removed => format!("  - {}: {old}\n", change.field),
+        };
+    }
+    text
+}
+
+/// Generate a `Table` containing negatives lacking a logged frame
+///
+/// This function generates a [comfy-table] `Table` listing the given
+/// negatives along with the roll ID and frame number they were matched to,
+/// for reporting images whose frame has no log entry.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn list_unmatched<'a, I>(negatives: I) -> Table
+where
+    I: IntoIterator<Item = (&'a str, usize, negative::Negative)>,
+{
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Roll",  // roll.id
+        "Frame", // frame number
+        "Path",  // negative.path()
+    ]);
+    negatives
+        .into_iter()
+        .fold(table, |mut table, (roll, frame, negative)| {
+            table.add_row(vec![
+                roll.to_owned(),
+                frame.to_string(),
+                negative.path().display().to_string(),
+            ]);
+            table
+        })
+}
+
+/// Generate a `Table` summarizing the given backup journals
+///
+/// Each journal is listed with its run ID, the command which started the
+/// run, the date & time it started, and the number of images it modified.
+pub fn list_backups<'a, I>(journals: I) -> Table
+where
+    I: IntoIterator<Item = &'a backup::Journal>,
+{
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Run",     // journal.id()
+        "Command", // journal.command()
+        "Started", // journal.created()
+        "Images",  // journal.files()
+    ]);
+    journals.into_iter().fold(table, |mut table, journal| {
+        table.add_row(vec![
+            journal.id().to_owned(),
+            journal.command().to_owned(),
+            truncate_seconds(journal.created()).to_string(),
+            journal.files().count().to_string(),
+        ]);
+        table
+    })
+}
+
+/// Generate a `Table` containing metadata differences of negatives
+///
+/// This function generates a [comfy-table] `Table` listing each field whose
+/// value differs from the expected value, along with the path of the negative
+/// it belongs to. Missing or unexpected fields are listed with an empty value.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn list_differences<'a, I>(differences: I) -> Table
+where
+    I: IntoIterator<Item = (&'a std::path::Path, &'a negative::Difference)>,
+{
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Path",     // negative.path()
+        "Field",    // difference.field
+        "Expected", // difference.expected
+        "Actual",   // difference.actual
+    ]);
+    differences
+        .into_iter()
+        .fold(table, |mut table, (path, difference)| {
+            table.add_row(vec![
+                path.display().to_string(),
+                difference.field.clone(),
+                difference.expected.clone().unwrap_or_default(),
+                difference.actual.clone().unwrap_or_default(),
+            ]);
+            table
+        })
+}
+
+/// Generate a `Table` containing metadata read back from negatives
+///
+/// This function generates a [comfy-table] `Table` listing the film-related
+/// metadata already held by each of the given negatives, one row per image.
+///
+/// [comfy-table]: https://docs.rs/comfy-table/latest/comfy_table/
+pub fn inspect_negatives<'a, I>(details: I) -> Table
+where
+    I: IntoIterator<Item = &'a negative::Details>,
+{
+    fn format<T: ToString>(value: &Option<T>) -> String {
+        value.as_ref().map(ToString::to_string).unwrap_or_default()
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Path",       // details.path
+        "Roll",       // details.roll
+        "Frame",      // details.frame
+        "Camera",     // details.camera
+        "Lens",       // details.lens
+        "Film",       // details.film + details.iso
+        "Focal len.", // details.focal_length
+        "Aperture",   // details.aperture
+        "Shutter",    // details.shutter_speed
+        "Date",       // details.date
+        "Location",   // details.position
+        "Author",     // details.author
+        "License",    // details.license
+    ]);
+    details.into_iter().fold(table, |mut table, details| {
+        table.add_row(vec![
+            details.path.display().to_string(),
+            format(&details.roll),
+            format(&details.frame),
+            format(&details.camera),
+            format(&details.lens),
+            match (&details.film, details.iso) {
+                (Some(film), Some(iso)) => format!("{film} @ ISO {iso}"),
+                (Some(film), None) => film.to_owned(),
+                (None, Some(iso)) => format!("ISO {iso}"),
+                (None, None) => String::new(),
+            },
+            format(&details.focal_length),
+            format(&details.aperture),
+            format(&details.shutter_speed),
+            format(&details.date),
+            format(&details.position),
+            format(&details.author),
+            format(&details.license),
+        ]);
+        table
+    })
+}
+
+/// Group negatives with any variants immediately following them
+///
+/// If `enabled` is not set, every negative forms a group of its own.
+fn group_variants(
+    negatives: impl Iterator<Item = Result<negative::Negative>>,
+    enabled: bool,
+) -> impl Iterator<Item = Result<Vec<negative::Negative>>> {
+    negatives.peekable().batching(move |negatives| {
+        let mut group = vec![match negatives.next()? {
+            Ok(negative) => negative,
+            Err(err) => return Some(Err(err)),
+        }];
+        while let Some(variant) = negatives.next_if(|next| {
+            enabled
+                && next
+                    .as_ref()
+                    .is_ok_and(|next| matching::is_variant_of(group[0].path(), next.path()))
+        }) {
+            group.push(variant.expect("only successfully opened variants are grouped"));
+        }
+        Some(Ok(group))
+    })
+}
+
+/// Collect the record of a (possibly unlogged) frame in a given roll
+fn frame_record(roll: &rolls::Roll, frame_nbr: usize, frame: Option<&rolls::Frame>) -> FrameRecord {
+    FrameRecord {
+        frame: frame_nbr,
+        film: frame
+            .and_then(|frame| frame.film.as_ref().or(roll.film.as_ref()))
+            .cloned(),
+        speed: frame.map(|frame| frame.speed.unwrap_or(roll.speed)),
+        lens: frame.and_then(|frame| frame.lens.clone()),
+        focal_length: frame
+            .and_then(|frame| frame.focal_length)
+            .map(|focal_length| focal_length.real),
+        aperture: frame.and_then(|frame| frame.aperture),
+        shutter_speed: frame.and_then(|frame| frame.shutter_speed),
+        compensation: frame.and_then(|frame| frame.compensation),
+        date: frame.map(|frame| truncate_seconds(frame.datetime)),
+        latitude: frame.map(|frame| frame.position.lat),
+        longitude: frame.map(|frame| frame.position.lon),
+        note: frame.and_then(|frame| frame.note.clone()),
+    }
+}
+
+/// Truncate the given date & time to whole seconds
+fn truncate_seconds(datetime: NaiveDateTime) -> NaiveDateTime {
+    datetime
+        .with_nanosecond(0)
+        .expect("should be possible to set nanoseconds to zero")
+}
+
+/// Format the film and film speed of a roll, e.g. "Ilford Delta 100 @ 100/21°"
+fn format_film(roll: &rolls::Roll) -> String {
+    match (&roll.film, roll.sheet_film) {
+        (None, true) => "Sheet film".to_owned(),
+        (film, _) => format!(
+            "{} @ {}",
+            film.as_ref().map(ToString::to_string).unwrap_or_default(),
+            roll.speed
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::negative::*;
     use crate::rolls::*;
     use crate::types::*;
-    use chrono::NaiveDateTime;
     use itertools::assert_equal;
     use pretty_assertions::assert_eq;
 
     fn get_test_roll() -> Result<Roll> {
-        Ok(Roll {
-            id: "A0012".into(),
-            film: Some(Film("Ilford Delta 100".into())),
-            speed: FilmSpeed::from_din(21), // ISO 100/21°
-            camera: "Voigtländer Bessa R2M".try_into().ok(),
-            load: NaiveDateTime::default(),
-            unload: NaiveDateTime::default(),
-            frames: vec![
-                None,
-                Some(Frame {
-                    lens: "Voigtländer Color Skopar 35/2.5 Pancake II".try_into().ok(),
-                    aperture: Some(Aperture::from(rust_decimal::Decimal::new(56, 1))),
-                    shutter_speed: Some(ShutterSpeed::from(num_rational::Ratio::new(1, 500))),
-                    focal_length: None,
-                    compensation: None,
-                    datetime: NaiveDateTime::default(),
-                    position: Position {
-                        lat: 57.700767,
-                        lon: 11.953715,
-                    },
-                    note: None,
-                }),
-                None,
-            ],
-        })
+        let frame = Frame {
+            lens: "Voigtländer Color Skopar 35/2.5 Pancake II".try_into().ok(),
+            aperture: Some(Aperture::from(rust_decimal::Decimal::new(56, 1))),
+            shutter_speed: Some(ShutterSpeed::from(num_rational::Ratio::new(1, 500))),
+            position: Position {
+                lat: 57.700767,
+                lon: 11.953715,
+            },
+            ..fixtures::frame("2016-05-13T14:12:40")
+        };
+        Ok(fixtures::roll("A0012", vec![None, Some(frame), None]))
     }
 
     #[test]
@@ -278,6 +1173,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn show_statistics_single() {
+        let stats = roll_statistics(std::iter::once(get_test_roll()))
+            .expect("a single valid roll should not propagate any errors");
+        let mut tables = show_statistics(&stats);
+        assert_equal(
+            tables.iter_mut().map(|table| table.row_count()),
+            vec![4, 1, 1, 1, 1, 1, 1],
+        );
+        let stats = roll_statistics(std::iter::empty())
+            .expect("an empty iterator should not propagate any errors");
+        assert_eq!(show_statistics(&stats).len(), 1);
+    }
+
     #[test]
     fn find_roll_no_match() {
         let rolls = find_roll(std::iter::once(get_test_roll()), "A0013")
@@ -314,10 +1223,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn roll_records_single() {
+        let records = roll_records(std::iter::once(get_test_roll()))
+            .expect("a single valid roll should not propagate any errors");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, "A0012");
+        assert_eq!(records[0].frames, 3);
+        assert_eq!(records[0].speed, FilmSpeed::from_din(21));
+    }
+
+    #[test]
+    fn frame_records_one_match() {
+        let records = frame_records(&get_test_roll().unwrap());
+        assert_equal(records.iter().map(|record| record.frame), [1, 2, 3]);
+        assert_eq!(records[0].date, None);
+        assert_eq!(records[1].film, Some(Film("Ilford Delta 100".into())));
+        assert_eq!(records[1].speed, Some(FilmSpeed::from_din(21)));
+        assert_eq!(records[1].latitude, Some(57.700767));
+        assert_eq!(records[2].lens, None);
+    }
+
+    #[test]
+    fn list_frames_sheet_film() {
+        let roll = Roll {
+            sheet_film: true,
+            ..get_test_roll().unwrap()
+        };
+        let mut table = list_frames(roll);
+        assert_eq!(table.column_count(), 10);
+        assert_eq!(table.row_count(), 3);
+        assert_equal(
+            table.row_iter().map(comfy_table::Row::cell_count),
+            vec![1, 10, 1],
+        );
+        assert_eq!(
+            table
+                .row(1)
+                .and_then(|row| row.cell_iter().nth(1))
+                .map(comfy_table::Cell::content),
+            Some("Ilford Delta 100 @ 100/21°".into())
+        );
+    }
+
+    #[test]
+    fn show_roll_no_development() {
+        let mut table = show_roll(&get_test_roll().unwrap());
+        assert_eq!(table.column_count(), 2);
+        assert_eq!(table.row_count(), 13);
+        assert_eq!(
+            table
+                .row(6)
+                .and_then(|row| row.cell_iter().nth(1))
+                .map(comfy_table::Cell::content),
+            Some("".into())
+        );
+    }
+
+    #[test]
+    fn show_roll_development() {
+        let roll = Roll {
+            development: Some(crate::development::Development {
+                developer: Some("Kodak HC-110".into()),
+                dilution: Some("B".into()),
+                temperature: Some(rust_decimal::Decimal::new(200, 1)),
+                ..Default::default()
+            }),
+            ..get_test_roll().unwrap()
+        };
+        let table = show_roll(&roll);
+        assert_equal(
+            table
+                .row_iter()
+                .skip(6)
+                .take(3)
+                .filter_map(|row| row.cell_iter().nth(1))
+                .map(comfy_table::Cell::content),
+            vec!["Kodak HC-110 (B)".to_owned(), "".into(), "20 °C".into()],
+        );
+    }
+
     #[test]
     fn match_negatives_short() {
         let roll = get_test_roll().unwrap();
-        let _ = match_negatives(roll.frames.iter(), std::iter::empty())
+        let _ = match_negatives(roll.frames.iter(), std::iter::empty(), false)
             .expect_err("too few negatives should generate an error");
     }
 
@@ -327,6 +1316,7 @@ mod tests {
         let _ = match_negatives(
             roll.frames.iter(),
             std::iter::repeat(Negative::new()).map(Ok),
+            false,
         )
         .expect_err("too many negatives should generate an error");
     }
@@ -335,8 +1325,12 @@ mod tests {
     fn match_negatives_error() {
         let roll = get_test_roll().unwrap();
         let error = crate::rolls::SourceError::InvalidData("...");
-        let pairs = match_negatives(roll.frames.iter(), std::iter::once(Err(error.into())))
-            .expect_err("all errors should propagate to the caller");
+        let pairs = match_negatives(
+            roll.frames.iter(),
+            std::iter::once(Err(error.into())),
+            false,
+        )
+        .expect_err("all errors should propagate to the caller");
         assert_eq!(
             pairs.downcast_ref::<crate::rolls::SourceError>(),
             Some(&crate::rolls::SourceError::InvalidData("..."))
@@ -346,16 +1340,204 @@ mod tests {
     #[test]
     fn match_negatives_ok() {
         let roll = get_test_roll().unwrap();
-        let pairs = match_negatives(roll.frames.iter(), std::iter::once(Ok(Negative::new())))
-            .expect("matching lengths with no errors should not propagate any errors");
+        let pairs = match_negatives(
+            roll.frames.iter(),
+            std::iter::once(Ok(Negative::new())),
+            false,
+        )
+        .expect("matching lengths with no errors should not propagate any errors");
+        assert_eq!(pairs.len(), 1);
+    }
+
+    #[test]
+    fn match_negatives_variants() {
+        let roll = get_test_roll().unwrap();
+        let negatives = || {
+            ["001.tiff", "001a.tiff", "001-crop.tiff"]
+                .map(|path| Ok(Negative::new_with_path(path.as_ref())))
+                .into_iter()
+        };
+        let _ = match_negatives(roll.frames.iter(), negatives(), false)
+            .expect_err("variants should not be grouped unless asked to");
+        let pairs = match_negatives(roll.frames.iter(), negatives(), true)
+            .expect("variants of a single frame should not generate an error");
+        assert_eq!(pairs.len(), 3);
+        assert!(pairs
+            .iter()
+            .all(|(frame, _)| std::ptr::eq(*frame, roll.frames[1].as_ref().unwrap())));
+    }
+
+    #[test]
+    fn match_negatives_by_name_ok() {
+        let roll = get_test_roll().unwrap();
+        let pattern = "{date}-{roll}+{frame:03}".parse().unwrap();
+        let negatives = ["20160513-A0012+002.tiff", "20160513-A0012+003.tiff"]
+            .map(|path| Ok(Negative::new_with_path(path.as_ref())));
+        let (pairs, unmatched) = match_negatives_by_name(&roll, negatives.into_iter(), &pattern)
+            .expect("matching file names with no errors should not propagate any errors");
+        assert_eq!(pairs.len(), 1);
+        assert!(std::ptr::eq(pairs[0].0, roll.frames[1].as_ref().unwrap()));
+        assert_eq!(unmatched.len(), 1);
+        assert_eq!(unmatched[0].0, 3);
+    }
+
+    #[test]
+    fn match_negatives_by_name_variants() {
+        let roll = get_test_roll().unwrap();
+        let pattern = "{date}-{roll}+{frame:03}".parse().unwrap();
+        let negatives = ["20160513-A0012+002.tiff", "20160513-A0012+002-crop.tiff"]
+            .map(|path| Ok(Negative::new_with_path(path.as_ref())));
+        let (pairs, unmatched) = match_negatives_by_name(&roll, negatives.into_iter(), &pattern)
+            .expect("variants of a single frame should not generate an error");
+        assert_eq!(pairs.len(), 2);
+        assert!(unmatched.is_empty());
+    }
+
+    #[test]
+    fn match_negatives_by_name_errors() {
+        let roll = get_test_roll().unwrap();
+        let pattern = "{date}-{roll}+{frame:03}".parse().unwrap();
+        for paths in [
+            vec!["20160513-A0013+002.tiff"],
+            vec!["A0012-002.tiff"],
+            vec!["20160513-A0012+002.tiff", "20160514-A0012+002.tiff"],
+        ] {
+            let negatives = paths
+                .into_iter()
+                .map(|path| Ok(Negative::new_with_path(path.as_ref())));
+            let _ = match_negatives_by_name(&roll, negatives, &pattern)
+                .expect_err("mismatched file names should generate an error");
+        }
+    }
+
+    #[test]
+    fn match_negatives_with_gaps_all() {
+        let roll = get_test_roll().unwrap();
+        let negatives = std::iter::repeat(Negative::new()).map(Ok).take(3);
+        let (pairs, unmatched) = match_negatives_with_gaps(&roll, None, negatives, false)
+            .expect("matching all frame slots with no errors should not propagate any errors");
+        assert_eq!(pairs.len(), 1);
+        assert_equal(unmatched.iter().map(|(n, _)| *n), [1, 3]);
+
+        let _ = match_negatives_with_gaps(&roll, None, std::iter::once(Ok(Negative::new())), false)
+            .expect_err("too few negatives should generate an error");
+    }
+
+    #[test]
+    fn match_negatives_with_gaps_selection() {
+        let roll = get_test_roll().unwrap();
+        let selection = "2,4".parse().unwrap();
+        let negatives = std::iter::repeat(Negative::new()).map(Ok).take(2);
+        let (pairs, unmatched) =
+            match_negatives_with_gaps(&roll, Some(&selection), negatives, false)
+                .expect("matching selected frames with no errors should not propagate any errors");
+        assert!(std::ptr::eq(pairs[0].0, roll.frames[1].as_ref().unwrap()));
+        assert_equal(unmatched.iter().map(|(n, _)| *n), [4]);
+    }
+
+    #[test]
+    fn match_negatives_by_mapping_ok() {
+        let roll = get_test_roll().unwrap();
+        let mapping = matching::Mapping::from_csv("2,tests/data/20160513-A0012+001.tiff").unwrap();
+        let pairs = match_negatives_by_mapping(&roll, &mapping)
+            .expect("a valid mapping should not propagate any errors");
         assert_eq!(pairs.len(), 1);
+        assert!(std::ptr::eq(pairs[0].0, roll.frames[1].as_ref().unwrap()));
+    }
+
+    #[test]
+    fn match_negatives_by_mapping_error() {
+        let roll = get_test_roll().unwrap();
+        let mapping = matching::Mapping::from_csv("1,tests/data/20160513-A0012+001.tiff").unwrap();
+        let error = match_negatives_by_mapping(&roll, &mapping)
+            .expect_err("an invalid mapping should generate an error");
+        assert_eq!(
+            error.downcast_ref::<matching::MappingError>(),
+            Some(&matching::MappingError::UnknownFrame(1))
+        );
+    }
+
+    #[test]
+    fn group_negatives_by_roll_ok() {
+        let rolls = [
+            get_test_roll().unwrap(),
+            Roll {
+                id: "A0013".into(),
+                ..get_test_roll().unwrap()
+            },
+        ];
+        let negatives = ["A0013/1.tiff", "A0012/1.tiff", "A0013/2.tiff"]
+            .map(|path| Ok(Negative::new_with_path(path.as_ref())));
+        let groups = group_negatives_by_roll(&rolls, negatives.into_iter(), None)
+            .expect("known roll IDs with no errors should not propagate any errors");
+        assert_equal(
+            groups
+                .iter()
+                .map(|(roll, negatives)| (roll.id.as_str(), negatives.len())),
+            [("A0013", 2), ("A0012", 1)],
+        );
+    }
+
+    #[test]
+    fn group_negatives_by_roll_errors() {
+        let rolls = [get_test_roll().unwrap()];
+        for path in ["A0013/1.tiff", "1.tiff"] {
+            let negatives = std::iter::once(Ok(Negative::new_with_path(path.as_ref())));
+            let _ = group_negatives_by_roll(&rolls, negatives, None)
+                .expect_err("unknown roll IDs should generate an error");
+        }
+    }
+
+    #[test]
+    fn link_variants_ok() {
+        let roll = get_test_roll().unwrap();
+        let frame = roll.frames[1].as_ref();
+        let mut pairs = ["001a.tiff", "001.tiff", "002.tiff"]
+            .map(|path| (&roll, frame, Negative::new_with_path(path.as_ref())));
+        pairs[2].1 = None;
+        link_variants(&mut pairs).expect("linking variants should not generate an error");
+        assert_eq!(
+            pairs[0].2.document_id().as_deref(),
+            Some("xmp.did:A0012/001")
+        );
+        assert_eq!(
+            pairs[1].2.document_id().as_deref(),
+            Some("xmp.did:A0012/001")
+        );
+        assert_eq!(pairs[2].2.document_id(), None);
+    }
+
+    #[test]
+    fn link_variants_existing() {
+        let roll = get_test_roll().unwrap();
+        let frame = roll.frames[1].as_ref();
+        let mut pairs = ["001.tiff", "001a.tiff"]
+            .map(|path| (&roll, frame, Negative::new_with_path(path.as_ref())));
+        pairs[1].2.link_variant("xmp.did:existing", None).unwrap();
+        link_variants(&mut pairs).expect("a single existing ID should not generate an error");
+        assert_eq!(
+            pairs[0].2.document_id().as_deref(),
+            Some("xmp.did:existing")
+        );
+        assert_eq!(
+            pairs[1].2.document_id().as_deref(),
+            Some("xmp.did:existing")
+        );
+
+        pairs[0].2.link_variant("xmp.did:other", None).unwrap();
+        let _ = link_variants(&mut pairs)
+            .expect_err("conflicting existing IDs should generate an error");
+        assert_eq!(
+            pairs[1].2.document_id().as_deref(),
+            Some("xmp.did:existing")
+        );
     }
 
     #[test]
     fn list_negatives_empty() {
         let mut table = list_negatives(std::iter::empty()) //
             .expect("an empty iterator should not propagate any errors");
-        assert_eq!(table.column_count(), 3);
+        assert_eq!(table.column_count(), 4);
         assert_eq!(table.row_count(), 0);
     }
 
@@ -363,7 +1545,7 @@ mod tests {
     fn list_negatives_single() {
         let mut table = list_negatives(std::iter::once(Ok(Negative::new())))
             .expect("an iterator with no errors should not propagate any errors");
-        assert_eq!(table.column_count(), 3);
+        assert_eq!(table.column_count(), 4);
         assert_eq!(table.row_count(), 1);
     }
 
@@ -377,4 +1559,37 @@ mod tests {
             Some(&crate::rolls::SourceError::InvalidData("..."))
         );
     }
+
+    #[test]
+    fn describe_negative_changes() {
+        let path = std::path::Path::new("001.tiff");
+        let original = Negative::new_with_path(path);
+        let mut linked = original.clone();
+        linked
+            .link_variant("xmp.did:A1234/001", None)
+            .expect("variant link should be applicable to negative");
+        let mut relinked = original.clone();
+        relinked
+            .link_variant("xmp.did:A1234/002", None)
+            .expect("variant link should be applicable to negative");
+
+        let changes = negative_changes(&original, &linked);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].change, Change::Added);
+        assert_eq!(
+            describe_changes(path, &changes),
+            "001.tiff\n  + XMP xmpMM:DocumentID: xmp.did:A1234/001\n"
+        );
+        let changes = negative_changes(&linked, &relinked);
+        assert_eq!(
+            describe_changes(path, &changes),
+            "001.tiff\n  ~ XMP xmpMM:DocumentID: xmp.did:A1234/001 → xmp.did:A1234/002\n"
+        );
+        let changes = negative_changes(&linked, &original);
+        assert_eq!(changes[0].change, Change::Removed);
+        assert_eq!(
+            describe_changes(path, &negative_changes(&original, &original)),
+            "001.tiff\n  (no changes)\n"
+        );
+    }
 }
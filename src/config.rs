@@ -0,0 +1,364 @@
+//! User configuration
+//!
+//! Frequently repeated options can be given defaults in a TOML configuration
+//! file, read from `$XDG_CONFIG_HOME/filmrolls/config.toml` (or
+//! `~/.config/filmrolls/config.toml`) unless another file is given using
+//! `--config` or the `FILMROLLS_CONFIG` environment variable:
+//!
+//! ```toml
+//! rolls = ["~/logs/filmrolls.xml", "~/logs/lightme/*.json"]
+//! metadata = "~/meta.toml"
+//! output = "table"
+//! pattern = "{date}-{roll}+{frame:03}"
+//!
+//! [write]
+//! dry-run = true
+//! backup-dir = "~/filmrolls-backups"
+//!
+//! [merge]
+//! location = "keep"
+//! ```
+//!
+//! Relative paths are resolved against the directory of the configuration
+//! file, and a leading `~` is expanded to the home directory.
+//!
+//! Each setting can also be given using a `FILMROLLS_*` environment variable
+//! (see [`Config::with_env`]), taking precedence over the configuration file.
+//! Options given on the command line always take precedence over both.
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+use crate::matching::FilenamePattern;
+use crate::merge::{MergePolicy, MergeRule};
+use crate::output::OutputFormat;
+
+/// Configuration errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum ConfigError {
+    /// Roll data pattern is not a valid glob pattern
+    #[error("Invalid roll data pattern `{0}`")]
+    InvalidPattern(String, #[source] glob::PatternError),
+
+    /// Roll data file could not be read while expanding a pattern
+    #[error(transparent)]
+    GlobError(#[from] glob::GlobError),
+
+    /// Roll data pattern did not match any files
+    #[error("No roll data files match `{0}`")]
+    NoMatches(String),
+
+    /// Environment variable has an invalid value
+    #[error("Invalid value `{1}` of environment variable `{0}`")]
+    InvalidVariable(&'static str, String),
+}
+
+/// User configuration, providing defaults for command-line options
+#[serde_as]
+#[derive(Clone, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Default input film roll data files, possibly as glob patterns
+    #[serde(default)]
+    pub rolls: Vec<String>,
+    /// Default author metadata file
+    pub metadata: Option<PathBuf>,
+    /// Default output format of commands listing rolls, frames or images
+    pub output: Option<OutputFormat>,
+    /// Default file name pattern used to match images to frames
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub pattern: Option<FilenamePattern>,
+    /// Default write policy
+    #[serde(default)]
+    pub write: WritePolicy,
+    /// Default merge mode of each field group
+    #[serde(default)]
+    pub merge: MergePolicy,
+}
+
+/// Policy for commands modifying images
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WritePolicy {
+    /// Don't modify any files unless explicitly asked to
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Directory holding backups of the original metadata of modified files
+    pub backup_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Default location of the configuration file, if it can be determined
+    pub fn default_path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home_dir().map(|home| home.join(".config")))
+            .map(|dir| dir.join("filmrolls").join("config.toml"))
+    }
+
+    /// Resolve relative paths against the given base directory
+    ///
+    /// A leading `~` is expanded to the home directory before resolving.
+    pub fn relative_to(self, base: &Path) -> Self {
+        let resolve = |path: &Path| match path.strip_prefix("~") {
+            Ok(rest) => home_dir().map_or_else(|| path.to_owned(), |home| home.join(rest)),
+            Err(_) => base.join(path),
+        };
+        Self {
+            rolls: self
+                .rolls
+                .iter()
+                .map(|pattern| resolve(Path::new(pattern)).to_string_lossy().into_owned())
+                .collect(),
+            metadata: self.metadata.as_deref().map(resolve),
+            write: WritePolicy {
+                backup_dir: self.write.backup_dir.as_deref().map(resolve),
+                ..self.write
+            },
+            ..self
+        }
+    }
+
+    /// Override settings using `FILMROLLS_*` environment variables
+    ///
+    /// Variables are looked up using `lookup`, and empty variables are ignored.
+    /// Paths are used as given (i.e. relative to the working directory).
+    ///
+    /// | Variable               | Setting                                               |
+    /// |------------------------|-------------------------------------------------------|
+    /// | `FILMROLLS_ROLLS`      | `rolls`, separated like `PATH` (e.g. `a.xml:*.json`)  |
+    /// | `FILMROLLS_METADATA`   | `metadata`                                            |
+    /// | `FILMROLLS_OUTPUT`     | `output`                                              |
+    /// | `FILMROLLS_PATTERN`    | `pattern`                                             |
+    /// | `FILMROLLS_DRY_RUN`    | `write.dry-run` (`true`, `false`, `1` or `0`)         |
+    /// | `FILMROLLS_BACKUP_DIR` | `write.backup-dir`                                    |
+    /// | `FILMROLLS_MERGE`      | `merge`, as comma-separated rules (e.g. `author=keep`) |
+    pub fn with_env(
+        mut self,
+        lookup: impl Fn(&str) -> Option<OsString>,
+    ) -> Result<Self, ConfigError> {
+        use clap::ValueEnum;
+        let var = |name| lookup(name).filter(|value| !value.is_empty());
+        let string = |name: &'static str| {
+            var(name)
+                .map(|value| {
+                    value.into_string().map_err(|value| {
+                        ConfigError::InvalidVariable(name, value.to_string_lossy().into())
+                    })
+                })
+                .transpose()
+        };
+        let invalid = |name, value: &str| ConfigError::InvalidVariable(name, value.to_owned());
+        if let Some(rolls) = var("FILMROLLS_ROLLS") {
+            self.rolls = std::env::split_paths(&rolls)
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect();
+        }
+        if let Some(metadata) = var("FILMROLLS_METADATA") {
+            self.metadata = Some(metadata.into());
+        }
+        if let Some(output) = string("FILMROLLS_OUTPUT")? {
+            let format = OutputFormat::from_str(&output, true);
+            self.output = Some(format.map_err(|_| invalid("FILMROLLS_OUTPUT", &output))?);
+        }
+        if let Some(pattern) = string("FILMROLLS_PATTERN")? {
+            let pattern = pattern
+                .parse()
+                .map_err(|_| invalid("FILMROLLS_PATTERN", &pattern));
+            self.pattern = Some(pattern?);
+        }
+        if let Some(dry_run) = string("FILMROLLS_DRY_RUN")? {
+            self.write.dry_run = match dry_run.to_ascii_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => Err(invalid("FILMROLLS_DRY_RUN", &dry_run))?,
+            };
+        }
+        if let Some(backup_dir) = var("FILMROLLS_BACKUP_DIR") {
+            self.write.backup_dir = Some(backup_dir.into());
+        }
+        if let Some(merge) = string("FILMROLLS_MERGE")? {
+            let rules: Vec<MergeRule> = merge
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("FILMROLLS_MERGE", &merge))?;
+            self.merge = self.merge.with_rules(rules);
+        }
+        Ok(self)
+    }
+
+    /// Directory holding backups of the original metadata of modified files
+    ///
+    /// Unless configured otherwise, this is `$XDG_STATE_HOME/filmrolls/backups`
+    /// (or `~/.local/state/filmrolls/backups`), falling back to a `backups`
+    /// directory in the working directory if neither is available.
+    pub fn backup_dir(&self) -> PathBuf {
+        self.write.backup_dir.clone().unwrap_or_else(|| {
+            std::env::var_os("XDG_STATE_HOME")
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .or_else(|| home_dir().map(|home| home.join(".local").join("state")))
+                .map_or_else(
+                    || PathBuf::from("backups"),
+                    |dir| dir.join("filmrolls").join("backups"),
+                )
+        })
+    }
+
+    /// Expand the default roll data patterns into a list of files
+    ///
+    /// Files matching each pattern are sorted by path, and every pattern must
+    /// match at least one file.
+    pub fn roll_paths(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut paths = Vec::new();
+        for pattern in &self.rolls {
+            let matches = glob::glob(pattern)
+                .map_err(|error| ConfigError::InvalidPattern(pattern.clone(), error))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                Err(ConfigError::NoMatches(pattern.clone()))?;
+            }
+            paths.extend(matches);
+        }
+        Ok(paths)
+    }
+}
+
+/// Home directory of the current user, if known
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::{FieldGroup, MergeMode};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_config() -> Result<(), toml::de::Error> {
+        let config: Config = toml::from_str(
+            r#"
+            rolls = ["logs/*.xml", "/logs/lightme.json"]
+            metadata = "meta.toml"
+            output = "json"
+            pattern = "{date}-{roll}+{frame:03}"
+
+            [write]
+            dry-run = true
+            backup-dir = "~/backups"
+
+            [merge]
+            author = "append"
+            "#,
+        )?;
+        let config = config.relative_to(Path::new("config"));
+        assert_eq!(config.rolls, ["config/logs/*.xml", "/logs/lightme.json"]);
+        assert_eq!(config.metadata, Some(PathBuf::from("config/meta.toml")));
+        assert_eq!(config.output, Some(OutputFormat::Json));
+        assert!(config.pattern.is_some());
+        assert!(config.write.dry_run);
+        assert_eq!(config.merge.mode(FieldGroup::Author), MergeMode::Append);
+        assert_eq!(
+            config.backup_dir(),
+            home_dir().unwrap_or_else(|| "~".into()).join("backups")
+        );
+
+        let config: Config = toml::from_str("")?;
+        assert!(config.rolls.is_empty());
+        assert_eq!(config.write, WritePolicy::default());
+        assert_eq!(config.merge, MergePolicy::default());
+        assert!(toml::from_str::<Config>("roll = \"a.xml\"").is_err());
+        assert!(toml::from_str::<Config>("output = \"yaml\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn env_overrides() -> Result<(), ConfigError> {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+        let config = Config {
+            rolls: vec!["config/logs/*.xml".into()],
+            output: Some(OutputFormat::Json),
+            merge: MergePolicy::default().with_rules(["location=keep".parse().unwrap()]),
+            ..Default::default()
+        };
+
+        // Variables override the configuration file, and empty ones are ignored
+        let overridden = config.clone().with_env(env(&[
+            ("FILMROLLS_ROLLS", "a.xml:b/*.json"),
+            ("FILMROLLS_METADATA", "meta.toml"),
+            ("FILMROLLS_OUTPUT", "csv"),
+            ("FILMROLLS_PATTERN", ""),
+            ("FILMROLLS_DRY_RUN", "1"),
+            ("FILMROLLS_BACKUP_DIR", "backups"),
+            ("FILMROLLS_MERGE", "author=append,scan=keep"),
+        ]))?;
+        assert_eq!(overridden.rolls, ["a.xml", "b/*.json"]);
+        assert_eq!(overridden.metadata, Some(PathBuf::from("meta.toml")));
+        assert_eq!(overridden.output, Some(OutputFormat::Csv));
+        assert!(overridden.pattern.is_none());
+        assert!(overridden.write.dry_run);
+        assert_eq!(overridden.backup_dir(), PathBuf::from("backups"));
+        assert_eq!(overridden.merge.mode(FieldGroup::Author), MergeMode::Append);
+        assert_eq!(overridden.merge.mode(FieldGroup::Scan), MergeMode::Keep);
+        assert_eq!(overridden.merge.mode(FieldGroup::Location), MergeMode::Keep);
+
+        // Without any variables, the configuration file is used as is
+        let unchanged = config.clone().with_env(env(&[]))?;
+        assert_eq!(unchanged.rolls, config.rolls);
+        assert_eq!(unchanged.output, config.output);
+        assert_eq!(unchanged.merge, config.merge);
+
+        // Invalid values are reported
+        for vars in [
+            &[("FILMROLLS_OUTPUT", "yaml")],
+            &[("FILMROLLS_DRY_RUN", "maybe")],
+            &[("FILMROLLS_MERGE", "author=append,")],
+        ] {
+            assert!(matches!(
+                config.clone().with_env(env(vars)),
+                Err(ConfigError::InvalidVariable(..))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn expand_roll_paths() -> Result<(), ConfigError> {
+        let config = Config {
+            rolls: vec!["tests/data/*.json".into(), "tests/data/sheets.toml".into()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.roll_paths()?,
+            [
+                PathBuf::from("tests/data/lightme.json"),
+                PathBuf::from("tests/data/sheets.toml")
+            ]
+        );
+        let config = Config {
+            rolls: vec!["tests/data/*.yaml".into()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.roll_paths(),
+            Err(ConfigError::NoMatches(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+//! Film development definitions
+//!
+//! This module provides a structured record of how a film roll (or
+//! an individual sheet) was developed, which can be deserialized from
+//! a TOML sidecar file keyed by roll ID.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{serde_as, DeserializeAs, DeserializeFromStr, PickFirst, SerializeDisplay};
+
+/// A development time, e.g. "9:30" (minutes and seconds)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
+pub struct DevelopmentTime(pub Duration);
+
+impl<'de> DeserializeAs<'de, DevelopmentTime> for f64 {
+    fn deserialize_as<D>(deserializer: D) -> Result<DevelopmentTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        f64::deserialize(deserializer)
+            .and_then(|v| Duration::try_from_secs_f64(v * 60.0).map_err(D::Error::custom))
+            .map(DevelopmentTime)
+    }
+}
+
+impl std::str::FromStr for DevelopmentTime {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (minutes, seconds) = s.trim().split_once(':').unwrap_or((s.trim(), "0"));
+        let minutes: u64 = minutes.parse()?;
+        let seconds: u64 = seconds.parse()?;
+        Ok(Self(Duration::from_secs(minutes * 60 + seconds)))
+    }
+}
+
+impl std::fmt::Display for DevelopmentTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.0.as_secs();
+        write!(f, "{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// A full development record
+///
+/// All fields are optional, since most photographers only keep track of
+/// a subset of them. The temperature is given in degrees Celsius, and the
+/// push/pull processing in (signed) stops.
+#[serde_with::skip_serializing_none]
+#[serde_as]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Development {
+    pub developer: Option<String>,
+    pub dilution: Option<String>,
+    #[serde_as(as = "Option<PickFirst<(_, f64)>>")]
+    pub time: Option<DevelopmentTime>,
+    #[serde(serialize_with = "rust_decimal::serde::float_option::serialize")]
+    pub temperature: Option<rust_decimal::Decimal>,
+    pub agitation: Option<String>,
+    pub push: Option<i8>,
+    pub lab: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_toml_date",
+        serialize_with = "serialize_toml_date"
+    )]
+    pub date: Option<chrono::NaiveDate>,
+}
+
+impl Development {
+    /// A short description of the developer and dilution, e.g. "HC-110 (B)"
+    pub fn developer_dilution(&self) -> Option<String> {
+        match (&self.developer, &self.dilution) {
+            (Some(developer), Some(dilution)) => Some(format!("{developer} ({dilution})")),
+            (Some(developer), None) => Some(developer.to_owned()),
+            (None, Some(dilution)) => Some(format!("({dilution})")),
+            (None, None) => None,
+        }
+    }
+
+    /// A short description of the push/pull processing, e.g. "+1 stop"
+    pub fn push_pull(&self) -> Option<String> {
+        self.push.map(|push| match push {
+            0 => "Box speed".to_owned(),
+            1 | -1 => format!("{push:+} stop"),
+            _ => format!("{push:+} stops"),
+        })
+    }
+}
+
+/// Development records, keyed by roll ID
+pub type Records = HashMap<String, Development>;
+
+/// Convert a TOML local date to a `NaiveDate`
+fn deserialize_toml_date<'de, D>(de: D) -> Result<Option<chrono::NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    Option::<toml::value::Datetime>::deserialize(de)?
+        .map(|value| {
+            value
+                .date
+                .and_then(|d| {
+                    chrono::NaiveDate::from_ymd_opt(d.year.into(), d.month.into(), d.day.into())
+                })
+                .ok_or(Error::custom("expected a valid TOML date"))
+        })
+        .transpose()
+}
+
+/// Convert a `NaiveDate` to a TOML local date
+fn serialize_toml_date<S>(date: &Option<chrono::NaiveDate>, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    use chrono::Datelike;
+    use serde::ser::Error;
+    date.map(|d| -> Result<_, S::Error> {
+        Ok(toml::value::Datetime {
+            date: Some(toml::value::Date {
+                year: d.year().try_into().map_err(S::Error::custom)?,
+                month: d.month().try_into().map_err(S::Error::custom)?,
+                day: d.day().try_into().map_err(S::Error::custom)?,
+            }),
+            time: None,
+            offset: None,
+        })
+    })
+    .transpose()?
+    .serialize(ser)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
+    use toml::{de::Error, from_str};
+
+    #[test]
+    fn parse_development_time() {
+        assert_eq!(
+            "9:30".parse(),
+            Ok(DevelopmentTime(Duration::from_secs(570)))
+        );
+        assert_eq!("6".parse(), Ok(DevelopmentTime(Duration::from_secs(360))));
+        assert!("6:xx".parse::<DevelopmentTime>().is_err());
+        assert_eq!(
+            DevelopmentTime(Duration::from_secs(570)).to_string(),
+            "9:30"
+        );
+        assert_eq!(DevelopmentTime(Duration::from_secs(65)).to_string(), "1:05");
+    }
+
+    #[test]
+    fn minimal_document() -> Result<(), Error> {
+        let input = from_str::<Records>(
+            r#"
+            [A0012]
+            developer = "Kodak HC-110"
+            "#,
+        )?;
+        let expected = Records::from([(
+            "A0012".into(),
+            Development {
+                developer: Some("Kodak HC-110".into()),
+                ..Default::default()
+            },
+        )]);
+        assert_eq!(input, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn full_document() -> Result<(), Error> {
+        let input = from_str::<Records>(
+            r#"
+            [A0012]
+            developer = "Kodak HC-110"
+            dilution = "B"
+            time = "6:30"
+            temperature = 20.5
+            agitation = "4 inversions every 30 s"
+            push = 1
+            lab = "Home"
+            date = 2016-05-22
+
+            [A0020]
+            developer = "Ilford ID-11"
+            time = 9
+            "#,
+        )?;
+        let expected = Records::from([
+            (
+                "A0012".into(),
+                Development {
+                    developer: Some("Kodak HC-110".into()),
+                    dilution: Some("B".into()),
+                    time: Some(DevelopmentTime(Duration::from_secs(390))),
+                    temperature: Some(dec!(20.5)),
+                    agitation: Some("4 inversions every 30 s".into()),
+                    push: Some(1),
+                    lab: Some("Home".into()),
+                    date: NaiveDate::from_ymd_opt(2016, 5, 22),
+                },
+            ),
+            (
+                "A0020".into(),
+                Development {
+                    developer: Some("Ilford ID-11".into()),
+                    time: Some(DevelopmentTime(Duration::from_secs(540))),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        assert_eq!(input, expected);
+        let output = toml::to_string(&expected).expect("failed to serialize records");
+        assert_eq!(from_str::<Records>(&output)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn descriptions() {
+        let development = Development {
+            developer: Some("Kodak HC-110".into()),
+            dilution: Some("B".into()),
+            push: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            development.developer_dilution(),
+            Some("Kodak HC-110 (B)".into())
+        );
+        assert_eq!(development.push_pull(), Some("Box speed".into()));
+        assert_eq!(
+            Development {
+                push: Some(-1),
+                ..Default::default()
+            }
+            .push_pull(),
+            Some("-1 stop".into())
+        );
+        assert_eq!(
+            Development {
+                push: Some(2),
+                ..Default::default()
+            }
+            .push_pull(),
+            Some("+2 stops".into())
+        );
+        assert_eq!(Development::default().developer_dilution(), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! Roll & frame filters
+//!
+//! Filters select rolls by their ID (using glob patterns), camera and the
+//! time they spent loaded, and frames by their film stock, lens, date and
+//! location. Camera, film and lens names are matched case-insensitively
+//! against any part of the name.
+//!
+//! A roll matches the frame criteria if any of its logged frames does, so
+//! that e.g. filtering rolls by lens lists every roll on which that lens
+//! was used at least once.
+use chrono::NaiveDateTime;
+
+use crate::rolls::{Frame, Roll};
+use crate::types::Position;
+
+/// Criteria for selecting rolls and frames
+///
+/// All criteria which are set must match; an empty filter matches all rolls
+/// and frames.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Filter {
+    /// Roll ID patterns, any of which must match
+    pub ids: Vec<glob::Pattern>,
+    /// Part of the camera name
+    pub camera: Option<String>,
+    /// Part of the film stock name
+    pub film: Option<String>,
+    /// Part of the lens name
+    pub lens: Option<String>,
+    /// Earliest roll unload or frame date
+    pub since: Option<NaiveDateTime>,
+    /// Latest roll load or frame date
+    pub until: Option<NaiveDateTime>,
+    /// Center & radius (in kilometres) of the area frames were shot in
+    pub near: Option<(Position, f64)>,
+}
+
+impl Filter {
+    /// Check whether the given roll matches the filter
+    ///
+    /// Rolls are matched by date if they were loaded during any part of the
+    /// selected period, and by film stock, lens and location if any of their
+    /// logged frames match.
+    pub fn matches_roll(&self, roll: &Roll) -> bool {
+        self.matches_roll_data(roll)
+            && self.since.is_none_or(|since| roll.unload >= since)
+            && self.until.is_none_or(|until| roll.load <= until)
+            && (self.film.is_none() && self.lens.is_none() && self.near.is_none()
+                || roll
+                    .frames
+                    .iter()
+                    .flatten()
+                    .any(|frame| self.matches_frame_data(roll, frame)))
+    }
+
+    /// Check whether the given frame of the given roll matches the filter
+    ///
+    /// Frames are matched by date using the date of the frame itself.
+    pub fn matches_frame(&self, roll: &Roll, frame: &Frame) -> bool {
+        self.matches_roll_data(roll)
+            && self.matches_frame_data(roll, frame)
+            && self.since.is_none_or(|since| frame.datetime >= since)
+            && self.until.is_none_or(|until| frame.datetime <= until)
+    }
+
+    /// Check the roll ID & camera of the given roll
+    fn matches_roll_data(&self, roll: &Roll) -> bool {
+        (self.ids.is_empty() || self.ids.iter().any(|id| id.matches(&roll.id)))
+            && matches_name(&self.camera, roll.camera.as_ref())
+    }
+
+    /// Check the film, lens & location of the given frame
+    fn matches_frame_data(&self, roll: &Roll, frame: &Frame) -> bool {
+        matches_name(&self.film, frame.film.as_ref().or(roll.film.as_ref()))
+            && matches_name(&self.lens, frame.lens.as_ref())
+            && self
+                .near
+                .is_none_or(|(center, radius)| center.distance(&frame.position) <= radius)
+    }
+}
+
+/// Check whether a name contains the given pattern, ignoring case
+fn matches_name<T: ToString>(pattern: &Option<String>, name: Option<&T>) -> bool {
+    pattern.as_ref().is_none_or(|pattern| {
+        name.is_some_and(|name| {
+            name.to_string()
+                .to_lowercase()
+                .contains(&pattern.to_lowercase())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rolls::fixtures;
+    use pretty_assertions::assert_eq;
+
+    fn get_test_roll() -> Roll {
+        let frame = |lens: &str, datetime: &str, lat: f64| Frame {
+            lens: lens.try_into().ok(),
+            position: Position { lat, lon: 11.95 },
+            ..fixtures::frame(datetime)
+        };
+        fixtures::roll(
+            "A0012",
+            vec![
+                Some(frame("Color Skopar 35/2.5", "2016-04-01T12:00:00", 57.70)),
+                None,
+                Some(frame("Nokton 50/1.5", "2016-05-13T14:12:40", 59.33)),
+            ],
+        )
+    }
+
+    #[test]
+    fn empty_filter() {
+        let roll = get_test_roll();
+        let filter = Filter::default();
+        assert!(filter.matches_roll(&roll));
+        assert!(roll
+            .frames
+            .iter()
+            .flatten()
+            .all(|frame| filter.matches_frame(&roll, frame)));
+    }
+
+    #[test]
+    fn roll_filters() {
+        let roll = get_test_roll();
+        let matches = |filter: Filter| filter.matches_roll(&roll);
+        let pattern = |s: &str| glob::Pattern::new(s).unwrap();
+        let date = |s: &str| s.parse::<NaiveDateTime>().ok();
+        assert!(matches(Filter {
+            ids: vec![pattern("B*"), pattern("A00??")],
+            ..Default::default()
+        }));
+        assert!(!matches(Filter {
+            ids: vec![pattern("A002*")],
+            ..Default::default()
+        }));
+        assert!(matches(Filter {
+            camera: Some("bessa".into()),
+            film: Some("DELTA".into()),
+            lens: Some("Nokton".into()),
+            ..Default::default()
+        }));
+        assert!(!matches(Filter {
+            lens: Some("Heliar".into()),
+            ..Default::default()
+        }));
+        assert!(matches(Filter {
+            since: date("2016-05-01T00:00:00"),
+            until: date("2016-05-01T00:00:00"),
+            ..Default::default()
+        }));
+        assert!(!matches(Filter {
+            since: date("2016-06-01T00:00:00"),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn frame_filters() {
+        let roll = get_test_roll();
+        let frames = |filter: Filter| -> Vec<usize> {
+            roll.frames
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, frame)| Some((idx + 1, frame.as_ref()?)))
+                .filter(|(_, frame)| filter.matches_frame(&roll, frame))
+                .map(|(nbr, _)| nbr)
+                .collect()
+        };
+        assert_eq!(
+            frames(Filter {
+                lens: Some("skopar".into()),
+                ..Default::default()
+            }),
+            [1]
+        );
+        assert_eq!(
+            frames(Filter {
+                since: "2016-05-01T00:00:00".parse().ok(),
+                ..Default::default()
+            }),
+            [3]
+        );
+        let gothenburg = Position {
+            lat: 57.7089,
+            lon: 11.9746,
+        };
+        assert_eq!(
+            frames(Filter {
+                near: Some((gothenburg, 5.0)),
+                ..Default::default()
+            }),
+            [1]
+        );
+        assert_eq!(
+            frames(Filter {
+                near: Some((gothenburg, 500.0)),
+                ..Default::default()
+            }),
+            [1, 3]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Gear inventory definitions
+//!
+//! This module provides an inventory of cameras and lenses, keyed by the
+//! name used in the film roll data, which is used to look up the serial
+//! numbers (and owners) of specific copies of (possibly shared) gear.
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// Details of a specific camera body
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CameraDetails {
+    pub serial: Option<String>,
+    pub owner: Option<String>,
+}
+
+/// Details of a specific lens
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LensDetails {
+    pub serial: Option<String>,
+}
+
+/// A full gear inventory
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Inventory {
+    #[serde(default)]
+    pub camera: HashMap<String, CameraDetails>,
+    #[serde(default)]
+    pub lens: HashMap<String, LensDetails>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use toml::{de::Error, from_str};
+
+    #[test]
+    fn empty_document() -> Result<(), Error> {
+        assert_eq!(from_str::<Inventory>("")?, Default::default());
+        Ok(())
+    }
+
+    #[test]
+    fn full_document() -> Result<(), Error> {
+        let input = from_str::<Inventory>(
+            r#"
+            [camera."Voigtländer Bessa R2M"]
+            serial = "01234567"
+            owner = "Simon Sigurdhsson"
+
+            [lens."Voigtländer Color Skopar 35/2.5 Pancake II"]
+            serial = "7654321"
+            "#,
+        )?;
+        let expected = Inventory {
+            camera: HashMap::from([(
+                "Voigtländer Bessa R2M".into(),
+                CameraDetails {
+                    serial: Some("01234567".into()),
+                    owner: Some("Simon Sigurdhsson".into()),
+                },
+            )]),
+            lens: HashMap::from([(
+                "Voigtländer Color Skopar 35/2.5 Pancake II".into(),
+                LensDetails {
+                    serial: Some("7654321".into()),
+                },
+            )]),
+        };
+        assert_eq!(input, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn lens_owner() {
+        let input = from_str::<Inventory>(
+            r#"
+            [lens."Voigtländer Color Skopar 35/2.5 Pancake II"]
+            owner = "Simon Sigurdhsson"
+            "#,
+        );
+        assert!(input.is_err());
+    }
+}
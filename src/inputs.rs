@@ -0,0 +1,204 @@
+//! Collection of input image files
+//!
+//! This module expands the image arguments given on the command line,
+//! which may be files or directories, into a list of image files. Directories
+//! are searched for files with supported image extensions, skipping hidden
+//! files and sidecars, and all images are sorted in natural (numeric-aware)
+//! order, so that `10.tiff` is always matched after `2.tiff` regardless of
+//! the order in which the file system (or a shell glob) lists them.
+use std::cmp::Ordering;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use itertools::{EitherOrBoth, Itertools};
+
+/// File extensions of image formats supported when reading & writing metadata
+const IMAGE_EXTENSIONS: [&str; 10] = [
+    "heif", "hif", "heic", "jpeg", "jpg", "jxl", "png", "tiff", "tif", "webp",
+];
+
+/// Input collection errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum InputError {
+    /// Directory traversal error
+    #[error(transparent)]
+    WalkError(#[from] walkdir::Error),
+
+    /// Generic I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+}
+
+/// Check whether the given path has a supported image file extension
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Check whether the given path names a hidden file or directory
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+/// Compare two paths in natural (numeric-aware) order
+///
+/// Paths are compared component by component, with the file stem and
+/// extension compared separately such that `001.tiff` sorts before its
+/// variant `001-crop.tiff`.
+pub fn natural_cmp(a: &Path, b: &Path) -> Ordering {
+    fn key(path: &Path) -> impl Iterator<Item = std::borrow::Cow<'_, str>> {
+        let parent = path.parent().into_iter().flat_map(Path::components);
+        parent
+            .map(|component| component.as_os_str())
+            .chain(path.file_stem())
+            .chain(path.extension())
+            .map(|part| part.to_string_lossy())
+    }
+    key(a)
+        .zip_longest(key(b))
+        .map(|parts| match parts {
+            EitherOrBoth::Both(a, b) => natord::compare(&a, &b),
+            EitherOrBoth::Left(_) => Ordering::Greater,
+            EitherOrBoth::Right(_) => Ordering::Less,
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Read a list of image paths, one per line
+///
+/// Empty lines are ignored, and the paths are returned in the listed order.
+pub fn read_file_list(reader: impl BufRead) -> Result<Vec<PathBuf>, InputError> {
+    Ok(reader
+        .lines()
+        .filter_ok(|line| !line.trim().is_empty())
+        .map_ok(PathBuf::from)
+        .try_collect()?)
+}
+
+/// Expand the given files & directories into a list of images
+///
+/// Files are included as given, while directories are searched for images
+/// (recursively if `recursive` is set), skipping hidden files and any files
+/// without a supported image extension (such as XMP sidecars). All images are
+/// then sorted using [`natural_cmp`], unless `keep_order` is set, in which case
+/// only the images found in each directory are sorted.
+pub fn collect_images<I>(
+    paths: I,
+    recursive: bool,
+    keep_order: bool,
+) -> Result<Vec<PathBuf>, InputError>
+where
+    I: IntoIterator<Item = PathBuf>,
+{
+    let mut images = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            images.push(path);
+            continue;
+        }
+        let walker = walkdir::WalkDir::new(&path)
+            .min_depth(1)
+            .max_depth(if recursive { usize::MAX } else { 1 })
+            .into_iter()
+            .filter_entry(|entry| !is_hidden(entry.path()));
+        let mut found = Vec::new();
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_image(entry.path()) {
+                found.push(entry.into_path());
+            }
+        }
+        found.sort_by(|a, b| natural_cmp(a, b));
+        images.extend(found);
+    }
+    if !keep_order {
+        images.sort_by(|a, b| natural_cmp(a, b));
+    }
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn natural_order() {
+        let mut paths = [
+            "B/1.tiff",
+            "A/10.tiff",
+            "A/2.tiff",
+            "A/3-crop.tiff",
+            "A/3.tiff",
+            "A/1.tiff",
+        ]
+        .map(PathBuf::from);
+        paths.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            paths.map(|path| path.to_string_lossy().into_owned()),
+            [
+                "A/1.tiff",
+                "A/2.tiff",
+                "A/3.tiff",
+                "A/3-crop.tiff",
+                "A/10.tiff",
+                "B/1.tiff"
+            ]
+        );
+    }
+
+    #[test]
+    fn image_filters() {
+        assert!(is_image(Path::new("A0012/001.tiff")));
+        assert!(is_image(Path::new("A0012/001.JPG")));
+        assert!(!is_image(Path::new("A0012/001.xmp")));
+        assert!(!is_image(Path::new("A0012/001")));
+        assert!(is_hidden(Path::new("A0012/.001.tiff")));
+        assert!(!is_hidden(Path::new("A0012/001.tiff")));
+    }
+
+    #[test]
+    fn read_list() {
+        let list = "A0012/10.tiff\n\nA0012/2.tiff\n";
+        assert_eq!(
+            read_file_list(list.as_bytes()).unwrap(),
+            [
+                PathBuf::from("A0012/10.tiff"),
+                PathBuf::from("A0012/2.tiff")
+            ]
+        );
+    }
+
+    #[test]
+    fn collect_directory() {
+        let images = collect_images([PathBuf::from("tests/data")], false, false).unwrap();
+        assert_eq!(
+            images,
+            [
+                PathBuf::from("tests/data/20160513-A0012+001.tiff"),
+                PathBuf::from("tests/data/20160513-A0012+001-crop.tiff")
+            ]
+        );
+        let images =
+            collect_images([PathBuf::from("tests/data/mapping.csv")], false, false).unwrap();
+        assert_eq!(images, [PathBuf::from("tests/data/mapping.csv")]);
+    }
+
+    #[test]
+    fn collect_explicit_order() {
+        // Explicitly given images (e.g. expanded by a shell glob) are sorted
+        let paths = ["A0012/10.tiff", "A0012/2.tiff"].map(PathBuf::from);
+        assert_eq!(
+            collect_images(paths.clone(), false, false).unwrap(),
+            ["A0012/2.tiff", "A0012/10.tiff"].map(PathBuf::from)
+        );
+
+        // ...unless asked to keep the given order
+        assert_eq!(collect_images(paths.clone(), false, true).unwrap(), paths);
+    }
+}
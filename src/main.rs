@@ -4,11 +4,22 @@ use std::process::ExitCode;
 use ::clap::Parser;
 use color_eyre::eyre::Result;
 
+mod backup;
 mod clap;
 mod cmds;
+mod config;
+mod development;
+mod filter;
+mod gear;
+mod inputs;
+mod map;
+mod matching;
+mod merge;
 mod metadata;
 mod negative;
+mod output;
 mod rolls;
+mod stats;
 mod types;
 
 /// Application entry point
@@ -0,0 +1,291 @@
+//! Map exports of frame positions
+//!
+//! This module writes the positions of logged frames as GPX waypoints (along
+//! with one track per roll), KML placemarks (in one folder per roll), or
+//! GeoJSON point features. Each frame is described by its roll ID, frame
+//! number, date, film, lens & exposure, and optionally linked to its image.
+use std::path::Path;
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::rolls::{Film, Frame, Roll};
+use crate::types::FilmSpeed;
+
+mod geojson;
+mod gpx;
+mod kml;
+
+/// Map export errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum MapError {
+    /// XML serialization error
+    #[error(transparent)]
+    InvalidXml(#[from] quick_xml::se::SeError),
+
+    /// JSON serialization error
+    #[error(transparent)]
+    InvalidJson(#[from] serde_json::error::Error),
+
+    /// Generic I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+}
+
+/// A logged frame to be placed on a map
+#[derive(Clone, Copy, Debug)]
+pub struct Waypoint<'a> {
+    pub roll: &'a Roll,
+    pub number: usize,
+    pub frame: &'a Frame,
+    pub image: Option<&'a Path>,
+}
+
+impl<'a> Waypoint<'a> {
+    /// Short name of the frame, e.g. "A0012 #1"
+    fn name(&self) -> String {
+        format!("{} #{}", self.roll.id, self.number)
+    }
+
+    /// Film & film speed of the frame
+    fn film(&self) -> (Option<&'a Film>, FilmSpeed) {
+        (
+            self.frame.film.as_ref().or(self.roll.film.as_ref()),
+            self.frame.speed.unwrap_or(self.roll.speed),
+        )
+    }
+
+    /// Date & time of the frame, in RFC 3339 format
+    ///
+    /// GPX requires times in UTC. Logged times carry no time zone, so they are
+    /// written as UTC (with a `Z` suffix) just like the XMP dates written when
+    /// tagging, rather than converted using the local time zone.
+    fn date(&self) -> String {
+        let datetime = self.frame.datetime.and_utc();
+        datetime.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    }
+
+    /// Human-readable description of the film, lens & exposure of the frame
+    ///
+    /// For example, "Ilford Delta 100 @ 100/21°, Color Skopar 35/2.5, ƒ/5.6,
+    /// 1/500 s"; any missing values are left out.
+    fn description(&self) -> String {
+        let film = match self.film() {
+            (Some(film), speed) => Some(format!("{film} @ {speed}")),
+            (None, _) => None,
+        };
+        let frame = self.frame;
+        [
+            film,
+            frame.lens.as_ref().map(ToString::to_string),
+            frame.focal_length.as_ref().map(ToString::to_string),
+            frame.aperture.as_ref().map(ToString::to_string),
+            frame.shutter_speed.as_ref().map(ToString::to_string),
+            frame.compensation.as_ref().map(|c| format!("{c} EV")),
+        ]
+        .into_iter()
+        .flatten()
+        .join(", ")
+    }
+
+    /// Link to the image of the frame, if known
+    ///
+    /// The image path is used as a relative (or absolute) URI reference,
+    /// percent-encoding any characters which are not allowed in URIs.
+    fn link(&self) -> Option<String> {
+        self.image.map(|path| {
+            let path = path.to_string_lossy().replace('\\', "/");
+            path.bytes()
+                .map(|byte| match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => (byte as char).to_string(),
+                    b'-' | b'.' | b'_' | b'~' | b'/' | b'+' | b':' => (byte as char).to_string(),
+                    _ => format!("%{byte:02X}"),
+                })
+                .collect()
+        })
+    }
+}
+
+/// Write the given frames as a GPX file
+///
+/// Each frame is written as a waypoint, and the frames of each roll are
+/// additionally written as a track.
+pub fn to_gpx<'a, I, W>(waypoints: I, writer: W) -> Result<(), MapError>
+where
+    I: IntoIterator<Item = Waypoint<'a>>,
+    W: std::io::Write,
+{
+    let waypoints = waypoints.into_iter().collect_vec();
+    let trk = waypoints
+        .iter()
+        .chunk_by(|waypoint| &waypoint.roll.id)
+        .into_iter()
+        .map(|(id, waypoints)| gpx::Track {
+            name: id.to_owned(),
+            trkseg: gpx::Segment {
+                trkpt: waypoints.map(Into::into).collect(),
+            },
+        })
+        .collect();
+    let gpx = gpx::Gpx {
+        version: "1.1",
+        creator: "filmrolls",
+        xmlns: "http://www.topografix.com/GPX/1/1",
+        wpt: waypoints.iter().map(Into::into).collect(),
+        trk,
+    };
+    write_xml(&gpx, writer)
+}
+
+/// Write the given frames as a KML file
+///
+/// Each frame is written as a placemark, in one folder per roll.
+pub fn to_kml<'a, I, W>(waypoints: I, writer: W) -> Result<(), MapError>
+where
+    I: IntoIterator<Item = Waypoint<'a>>,
+    W: std::io::Write,
+{
+    let folder = waypoints
+        .into_iter()
+        .chunk_by(|waypoint| waypoint.roll.id.clone())
+        .into_iter()
+        .map(|(name, waypoints)| kml::Folder {
+            name,
+            placemark: waypoints.map(|waypoint| (&waypoint).into()).collect(),
+        })
+        .collect();
+    let kml = kml::Kml {
+        xmlns: "http://www.opengis.net/kml/2.2",
+        document: kml::Document {
+            name: "Film rolls".to_owned(),
+            folder,
+        },
+    };
+    write_xml(&kml, writer)
+}
+
+/// Write the given frames as a GeoJSON feature collection
+///
+/// Each frame is written as a point feature, with the frame data as its
+/// properties (using the same fields as machine-readable frame listings).
+pub fn to_geojson<'a, I, W>(waypoints: I, mut writer: W) -> Result<(), MapError>
+where
+    I: IntoIterator<Item = Waypoint<'a>>,
+    W: std::io::Write,
+{
+    let collection = geojson::FeatureCollection {
+        kind: "FeatureCollection",
+        features: waypoints
+            .into_iter()
+            .map(|waypoint| (&waypoint).into())
+            .collect(),
+    };
+    serde_json::to_writer_pretty(&mut writer, &collection)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write the given document as indented XML, including an XML declaration
+fn write_xml<T: Serialize, W: std::io::Write>(document: &T, mut writer: W) -> Result<(), MapError> {
+    let mut buf = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut buf);
+    serializer.indent(' ', 2);
+    document.serialize(serializer)?;
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "{buf}")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rolls::fixtures;
+    use crate::types::{Aperture, Position, ShutterSpeed};
+    use pretty_assertions::assert_eq;
+
+    fn get_test_roll() -> Roll {
+        let frame = Frame {
+            lens: "Color Skopar 35/2.5".try_into().ok(),
+            aperture: Some(Aperture::from(rust_decimal::Decimal::new(56, 1))),
+            shutter_speed: Some(ShutterSpeed::from(num_rational::Ratio::new(1, 500))),
+            position: Position {
+                lat: 57.700767,
+                lon: 11.953715,
+            },
+            note: Some("Harbour & pier".into()),
+            ..fixtures::frame("2016-05-13T14:12:40")
+        };
+        Roll {
+            camera: None,
+            ..fixtures::roll("A0012", vec![Some(frame)])
+        }
+    }
+
+    fn waypoints(roll: &Roll) -> impl Iterator<Item = Waypoint<'_>> {
+        roll.frames.iter().flatten().map(move |frame| Waypoint {
+            roll,
+            number: 1,
+            frame,
+            image: Some(Path::new("A0012/001 crop.tiff")),
+        })
+    }
+
+    #[test]
+    fn describe_waypoint() {
+        let roll = get_test_roll();
+        let waypoint = waypoints(&roll).next().unwrap();
+        assert_eq!(waypoint.name(), "A0012 #1");
+        assert_eq!(waypoint.date(), "2016-05-13T14:12:40Z");
+        assert_eq!(
+            waypoint.description(),
+            "Ilford Delta 100 @ 100/21°, Color Skopar 35/2.5, ƒ/5.6, 1/500 s"
+        );
+        assert_eq!(waypoint.link().as_deref(), Some("A0012/001%20crop.tiff"));
+    }
+
+    #[test]
+    fn write_gpx() -> Result<(), MapError> {
+        let roll = get_test_roll();
+        let mut buf = Vec::new();
+        to_gpx(waypoints(&roll), &mut buf)?;
+        let output = String::from_utf8(buf).unwrap();
+        assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx "));
+        assert!(output.contains(r#"<wpt lat="57.700767" lon="11.953715">"#));
+        assert!(output.contains("<time>2016-05-13T14:12:40Z</time>"));
+        assert!(output.contains(r#"<link href="A0012/001%20crop.tiff">"#));
+        assert!(output.contains("<trk>\n    <name>A0012</name>"));
+        Ok(())
+    }
+
+    #[test]
+    fn write_kml() -> Result<(), MapError> {
+        let roll = get_test_roll();
+        let mut buf = Vec::new();
+        to_kml(waypoints(&roll), &mut buf)?;
+        let output = String::from_utf8(buf).unwrap();
+        assert!(output.contains("<name>A0012 #1</name>"));
+        assert!(output.contains("<coordinates>11.953715,57.700767</coordinates>"));
+        assert!(
+            output.contains("<Data name=\"note\">\n            <value>Harbour &amp; pier</value>")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_geojson() -> Result<(), MapError> {
+        let roll = get_test_roll();
+        let mut buf = Vec::new();
+        to_geojson(waypoints(&roll), &mut buf)?;
+        let output: serde_json::Value = serde_json::from_slice(&buf)?;
+        let feature = &output["features"][0];
+        assert_eq!(output["type"], "FeatureCollection");
+        assert_eq!(feature["geometry"]["coordinates"][0], 11.953715);
+        assert_eq!(feature["properties"]["roll"], "A0012");
+        assert_eq!(feature["properties"]["speed"], 100);
+        assert_eq!(feature["properties"]["aperture"], 5.6);
+        assert_eq!(feature["properties"]["shutter_speed"], "1/500");
+        assert_eq!(feature["properties"]["image"], "A0012/001 crop.tiff");
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! Serialization for GeoJSON data
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::Waypoint;
+use crate::rolls::{Film, Lens};
+use crate::types::{Aperture, ExposureBias, FilmSpeed, ShutterSpeed};
+
+/// Feature collection object
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct FeatureCollection<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub features: Vec<Feature<'a>>,
+}
+
+/// Feature object, one per frame
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Feature<'a> {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub geometry: Point,
+    pub properties: Properties<'a>,
+}
+
+/// Point geometry object
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Point {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub coordinates: [f64; 2],
+}
+
+/// Feature properties, i.e. the frame data
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Properties<'a> {
+    pub roll: &'a str,
+    pub frame: usize,
+    pub date: NaiveDateTime,
+    pub film: Option<&'a Film>,
+    pub speed: FilmSpeed,
+    pub lens: Option<&'a Lens>,
+    #[serde(serialize_with = "rust_decimal::serde::float_option::serialize")]
+    pub focal_length: Option<Decimal>,
+    pub aperture: Option<Aperture>,
+    pub shutter_speed: Option<ShutterSpeed>,
+    pub compensation: Option<ExposureBias>,
+    pub note: Option<&'a str>,
+    pub image: Option<&'a Path>,
+}
+
+impl<'a> From<&Waypoint<'a>> for Feature<'a> {
+    fn from(value: &Waypoint<'a>) -> Self {
+        let frame = value.frame;
+        let (film, speed) = value.film();
+        Self {
+            kind: "Feature",
+            geometry: Point {
+                kind: "Point",
+                coordinates: [frame.position.lon, frame.position.lat],
+            },
+            properties: Properties {
+                roll: &value.roll.id,
+                frame: value.number,
+                date: frame.datetime,
+                film,
+                speed,
+                lens: frame.lens.as_ref(),
+                focal_length: frame.focal_length.map(|focal_length| focal_length.real),
+                aperture: frame.aperture,
+                shutter_speed: frame.shutter_speed,
+                compensation: frame.compensation,
+                note: frame.note.as_deref(),
+                image: value.image,
+            },
+        }
+    }
+}
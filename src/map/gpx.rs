@@ -0,0 +1,98 @@
+//! Serialization for GPX data
+use serde::Serialize;
+
+use super::Waypoint;
+
+/// Outer `<gpx>` element
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+#[serde(rename = "gpx")]
+pub(super) struct Gpx {
+    #[serde(rename = "@version")]
+    pub version: &'static str,
+    #[serde(rename = "@creator")]
+    pub creator: &'static str,
+    #[serde(rename = "@xmlns")]
+    pub xmlns: &'static str,
+    pub wpt: Vec<Wpt>,
+    pub trk: Vec<Track>,
+}
+
+/// Waypoint element (`<wpt>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Wpt {
+    #[serde(rename = "@lat")]
+    pub lat: f64,
+    #[serde(rename = "@lon")]
+    pub lon: f64,
+    pub time: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cmt: Option<String>,
+    pub desc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<Link>,
+}
+
+/// Link element (`<link>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Link {
+    #[serde(rename = "@href")]
+    pub href: String,
+    pub text: String,
+}
+
+/// Track element (`<trk>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Track {
+    pub name: String,
+    pub trkseg: Segment,
+}
+
+/// Track segment element (`<trkseg>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Segment {
+    pub trkpt: Vec<TrackPoint>,
+}
+
+/// Track point element (`<trkpt>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct TrackPoint {
+    #[serde(rename = "@lat")]
+    pub lat: f64,
+    #[serde(rename = "@lon")]
+    pub lon: f64,
+    pub time: String,
+}
+
+impl From<&Waypoint<'_>> for Wpt {
+    fn from(value: &Waypoint<'_>) -> Self {
+        Self {
+            lat: value.frame.position.lat,
+            lon: value.frame.position.lon,
+            time: value.date(),
+            name: value.name(),
+            cmt: value.frame.note.clone(),
+            desc: value.description(),
+            link: value.link().map(|href| Link {
+                href,
+                text: value.name(),
+            }),
+        }
+    }
+}
+
+impl From<&Waypoint<'_>> for TrackPoint {
+    fn from(value: &Waypoint<'_>) -> Self {
+        Self {
+            lat: value.frame.position.lat,
+            lon: value.frame.position.lon,
+            time: value.date(),
+        }
+    }
+}
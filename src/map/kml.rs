@@ -0,0 +1,127 @@
+//! Serialization for KML data
+use serde::Serialize;
+
+use super::Waypoint;
+
+/// Outer `<kml>` element
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+#[serde(rename = "kml")]
+pub(super) struct Kml {
+    #[serde(rename = "@xmlns")]
+    pub xmlns: &'static str,
+    #[serde(rename = "Document")]
+    pub document: Document,
+}
+
+/// Document element (`<Document>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Document {
+    pub name: String,
+    #[serde(rename = "Folder")]
+    pub folder: Vec<Folder>,
+}
+
+/// Folder element (`<Folder>`), one per roll
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Folder {
+    pub name: String,
+    #[serde(rename = "Placemark")]
+    pub placemark: Vec<Placemark>,
+}
+
+/// Placemark element (`<Placemark>`), one per frame
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Placemark {
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "TimeStamp")]
+    pub timestamp: TimeStamp,
+    #[serde(rename = "ExtendedData")]
+    pub extended_data: ExtendedData,
+    #[serde(rename = "Point")]
+    pub point: Point,
+}
+
+/// Time stamp element (`<TimeStamp>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct TimeStamp {
+    pub when: String,
+}
+
+/// Extended data element (`<ExtendedData>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct ExtendedData {
+    #[serde(rename = "Data")]
+    pub data: Vec<Data>,
+}
+
+/// Data element (`<Data>`), holding a single named value
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Data {
+    #[serde(rename = "@name")]
+    pub name: &'static str,
+    pub value: String,
+}
+
+/// Point element (`<Point>`)
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Point {
+    pub coordinates: String,
+}
+
+impl From<&Waypoint<'_>> for Placemark {
+    fn from(value: &Waypoint<'_>) -> Self {
+        let frame = value.frame;
+        let (film, speed) = value.film();
+        let data = [
+            ("roll", Some(value.roll.id.clone())),
+            ("frame", Some(value.number.to_string())),
+            ("date", Some(value.date())),
+            ("film", film.map(ToString::to_string)),
+            ("speed", Some(speed.to_string())),
+            ("lens", frame.lens.as_ref().map(ToString::to_string)),
+            (
+                "focal_length",
+                frame.focal_length.as_ref().map(ToString::to_string),
+            ),
+            ("aperture", frame.aperture.as_ref().map(ToString::to_string)),
+            (
+                "shutter_speed",
+                frame.shutter_speed.as_ref().map(ToString::to_string),
+            ),
+            (
+                "compensation",
+                frame.compensation.as_ref().map(ToString::to_string),
+            ),
+            ("note", frame.note.clone()),
+            ("image", value.link()),
+        ];
+        Self {
+            name: value.name(),
+            description: value.description(),
+            timestamp: TimeStamp { when: value.date() },
+            extended_data: ExtendedData {
+                data: data
+                    .into_iter()
+                    .filter_map(|(name, value)| {
+                        Some(Data {
+                            name,
+                            value: value?,
+                        })
+                    })
+                    .collect(),
+            },
+            point: Point {
+                coordinates: format!("{},{}", frame.position.lon, frame.position.lat),
+            },
+        }
+    }
+}
@@ -0,0 +1,561 @@
+//! File name based frame matching
+//!
+//! This module provides file name patterns, which are used to extract the
+//! roll ID and frame number from the file name of a scanned negative. This
+//! allows matching each image to the exact frame it depicts, rather than
+//! relying on the order in which the images are given. It also provides
+//! frame selections and explicit frame-to-file mappings, for cases where
+//! images are matched by order but some frames were not logged, or where
+//! neither order nor file names are of any use.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use lazy_regex::Regex;
+use serde::Deserialize;
+
+use crate::rolls::Frame;
+
+/// File name pattern parsing errors
+#[derive(Clone, PartialEq, Debug)]
+#[derive(thiserror::Error)]
+pub enum PatternError {
+    /// Invalid regular expression
+    #[error(transparent)]
+    InvalidRegex(#[from] lazy_regex::regex::Error),
+
+    /// Unknown template placeholder
+    #[error("unknown placeholder `{{{0}}}`")]
+    UnknownPlaceholder(String),
+
+    /// Unterminated template placeholder
+    #[error("unterminated placeholder")]
+    Unterminated,
+
+    /// Pattern does not capture a frame number
+    #[error("pattern must capture a frame number (`{{frame}}` or `(?P<frame>...)`)")]
+    MissingFrame,
+}
+
+/// Information extracted from the file name of a negative
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct NameMatch {
+    pub roll: Option<String>,
+    pub frame: usize,
+    pub variant: Option<String>,
+}
+
+/// A file name pattern
+///
+/// Patterns are given either as a template such as `{date}-{roll}+{frame:03}`,
+/// which must match the entire file name (excluding the extension and any
+/// variant suffix), or as a regular expression with named `roll`, `frame` and
+/// (optionally) `variant` capture groups, such as
+/// `(?P<roll>[A-Z]\d{4})\+(?P<frame>\d+)`, which may match any part of it.
+/// Patterns containing named capture groups are treated as regular
+/// expressions; anything else is treated as a template.
+#[derive(Clone, Debug)]
+pub struct FilenamePattern(Regex);
+
+impl FilenamePattern {
+    /// Extract the roll ID and frame number from the given path
+    ///
+    /// Returns `None` if the file name does not match the pattern, or if
+    /// the captured frame number is not a valid number.
+    pub fn parse_path(&self, path: &Path) -> Option<NameMatch> {
+        let stem = path.file_stem()?.to_str()?;
+        let captures = self.0.captures(stem)?;
+        Some(NameMatch {
+            roll: captures.name("roll").map(|m| m.as_str().to_owned()),
+            frame: captures.name("frame")?.as_str().parse().ok()?,
+            variant: captures
+                .name("variant")
+                .map(|m| m.as_str().to_owned())
+                .filter(|variant| !variant.is_empty()),
+        })
+    }
+
+    /// Convert a template into an (anchored) regular expression
+    ///
+    /// The file name may carry a variant suffix not starting with a digit
+    /// (e.g. `001a` or `001-crop`), which is captured as the `variant`.
+    fn template_to_regex(template: &str) -> Result<String, PatternError> {
+        let mut regex = String::from("^");
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            regex.push_str(&lazy_regex::regex::escape(&rest[..start]));
+            let end = rest[start..].find('}').ok_or(PatternError::Unterminated)?;
+            let placeholder = &rest[start + 1..start + end];
+            regex.push_str(&match placeholder.split_once(':') {
+                None if placeholder == "date" => r"(?P<date>.+?)".to_owned(),
+                None if placeholder == "roll" => r"(?P<roll>.+?)".to_owned(),
+                None if placeholder == "frame" => r"(?P<frame>\d+)".to_owned(),
+                Some(("frame", width)) if width.starts_with('0') => width[1..]
+                    .parse::<usize>()
+                    .map(|width| format!(r"(?P<frame>\d{{{width}}})"))
+                    .map_err(|_| PatternError::UnknownPlaceholder(placeholder.to_owned()))?,
+                _ => Err(PatternError::UnknownPlaceholder(placeholder.to_owned()))?,
+            });
+            rest = &rest[start + end + 1..];
+        }
+        regex.push_str(&lazy_regex::regex::escape(rest));
+        regex.push_str(r"(?P<variant>\D.*)?$");
+        Ok(regex)
+    }
+}
+
+/// Invalid frame selection error
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(thiserror::Error)]
+#[error("invalid frame selection `{0}`")]
+pub struct SelectionError(String);
+
+/// A selection of frame numbers, e.g. `1-4,6-36`
+///
+/// The selection is an ordered list of non-overlapping (inclusive) frame
+/// number ranges, used to match images to frames by order while skipping
+/// unlogged frames.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FrameSelection(Vec<std::ops::RangeInclusive<usize>>);
+
+impl FrameSelection {
+    /// Iterate over all selected frame numbers, in order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().cloned().flatten()
+    }
+}
+
+impl std::str::FromStr for FrameSelection {
+    type Err = SelectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || SelectionError(s.to_owned());
+        let ranges = s
+            .split(',')
+            .map(|range| {
+                let (start, end) = range.split_once('-').unwrap_or((range, range));
+                let start: usize = start.trim().parse().map_err(|_| error())?;
+                let end: usize = end.trim().parse().map_err(|_| error())?;
+                match start > 0 && start <= end {
+                    true => Ok(start..=end),
+                    false => Err(error()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Reject selections matching any frame more than once
+        let mut sorted = ranges.clone();
+        sorted.sort_by_key(|range| *range.start());
+        match sorted
+            .windows(2)
+            .any(|pair| pair[1].start() <= pair[0].end())
+        {
+            true => Err(error()),
+            false => Ok(Self(ranges)),
+        }
+    }
+}
+
+/// Frame-to-file mapping errors
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(thiserror::Error)]
+pub enum MappingError {
+    /// Invalid frame number
+    #[error("invalid frame number `{0}`")]
+    InvalidFrame(String),
+
+    /// Malformed CSV line
+    #[error("malformed line {0}, expected `frame,path`")]
+    MalformedLine(usize),
+
+    /// Frame mapped to several files
+    #[error("frame {0} is mapped more than once")]
+    DuplicateFrame(usize),
+
+    /// File mapped to several frames
+    #[error("file {} is mapped more than once", .0.display())]
+    DuplicatePath(PathBuf),
+
+    /// Frame with no log entry
+    #[error("frame {0} has no log entry")]
+    UnknownFrame(usize),
+
+    /// Non-existent file
+    #[error("file {} does not exist", .0.display())]
+    MissingFile(PathBuf),
+}
+
+/// An explicit frame-to-file mapping
+///
+/// The mapping is read either from a CSV file with `frame,path` records (with
+/// an optional header, and quoted paths if they contain commas), or from a
+/// TOML file with frame numbers as keys, e.g.
+/// `12 = "strip-3/scan-0002.tiff"`. Entries are kept in frame order.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(try_from = "BTreeMap<String, PathBuf>")]
+pub struct Mapping(Vec<(usize, PathBuf)>);
+
+impl Mapping {
+    /// Parse a CSV frame-to-file mapping
+    pub fn from_csv(s: &str) -> Result<Self, MappingError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(s.as_bytes());
+        let mut entries = Vec::new();
+        for (idx, record) in reader.records().enumerate() {
+            let line = |position: Option<&csv::Position>| {
+                MappingError::MalformedLine(position.map_or(idx + 1, |p| p.line() as usize))
+            };
+            let record = record.map_err(|error| line(error.position()))?;
+            match (record.get(0), record.get(1), record.len()) {
+                (Some("frame"), Some(_), 2) if idx == 0 => {}
+                (Some(frame), Some(path), 2) => {
+                    entries.push((Self::parse_frame(frame)?, PathBuf::from(path)))
+                }
+                _ => Err(line(record.position()))?,
+            }
+        }
+        Self::from_entries(entries)
+    }
+
+    /// Resolve relative paths against the given base directory
+    pub fn relative_to(self, base: &Path) -> Self {
+        Self(
+            self.0
+                .into_iter()
+                .map(|(frame, path)| (frame, base.join(path)))
+                .collect(),
+        )
+    }
+
+    /// Iterate over all frame number & path pairs, in frame order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Path)> {
+        self.0.iter().map(|(frame, path)| (*frame, path.as_path()))
+    }
+
+    /// Validate the mapping against the given frames
+    ///
+    /// Every mapped frame must have a log entry, and every mapped file must
+    /// exist and be mapped to a single frame.
+    pub fn validate(&self, frames: &[Option<Frame>]) -> Result<(), MappingError> {
+        let mut paths = std::collections::HashSet::new();
+        for (frame, path) in self.iter() {
+            if !matches!(
+                frame.checked_sub(1).and_then(|i| frames.get(i)),
+                Some(Some(_))
+            ) {
+                Err(MappingError::UnknownFrame(frame))?;
+            }
+            if !paths.insert(path) {
+                Err(MappingError::DuplicatePath(path.to_owned()))?;
+            }
+            if !path.is_file() {
+                Err(MappingError::MissingFile(path.to_owned()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sort the given entries by frame number, rejecting duplicate frames
+    fn from_entries(mut entries: Vec<(usize, PathBuf)>) -> Result<Self, MappingError> {
+        entries.sort_by_key(|(frame, _)| *frame);
+        match entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            Some(pair) => Err(MappingError::DuplicateFrame(pair[0].0)),
+            None => Ok(Self(entries)),
+        }
+    }
+
+    /// Parse a frame number
+    fn parse_frame(frame: &str) -> Result<usize, MappingError> {
+        frame
+            .parse()
+            .ok()
+            .filter(|frame| *frame > 0)
+            .ok_or_else(|| MappingError::InvalidFrame(frame.to_owned()))
+    }
+}
+
+impl TryFrom<BTreeMap<String, PathBuf>> for Mapping {
+    type Error = MappingError;
+
+    fn try_from(value: BTreeMap<String, PathBuf>) -> Result<Self, Self::Error> {
+        let entries = value
+            .into_iter()
+            .map(|(frame, path)| Ok((Self::parse_frame(frame.trim())?, path)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_entries(entries)
+    }
+}
+
+/// Check whether `candidate` is a variant of the image at `original`
+///
+/// A variant (e.g. a crop or rescan) has a file name consisting of the
+/// file name of the original followed by a suffix which does not start
+/// with a digit, ignoring extensions: `001a.tiff` and `001-crop.tif` are
+/// both variants of `001.tiff`, but `0010.tiff` is not.
+pub fn is_variant_of(original: &Path, candidate: &Path) -> bool {
+    let stems = original.file_stem().zip(candidate.file_stem());
+    stems
+        .and_then(|(original, candidate)| {
+            let suffix = candidate.to_str()?.strip_prefix(original.to_str()?)?;
+            suffix.chars().next().filter(|c| !c.is_ascii_digit())
+        })
+        .is_some()
+}
+
+/// Infer the roll ID of the negative at the given path
+///
+/// The roll ID is taken from the file name if the given pattern captures
+/// it, falling back to the name of the parent directory (e.g. `A0012/`).
+pub fn infer_roll(path: &Path, pattern: Option<&FilenamePattern>) -> Option<String> {
+    pattern
+        .and_then(|pattern| pattern.parse_path(path))
+        .and_then(|name| name.roll)
+        .or_else(|| Some(path.parent()?.file_name()?.to_str()?.to_owned()))
+}
+
+impl std::str::FromStr for FilenamePattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let regex = if s.contains("(?P<") || s.contains("(?<") {
+            Regex::new(s)?
+        } else {
+            Regex::new(&Self::template_to_regex(s)?)?
+        };
+        match regex.capture_names().flatten().any(|name| name == "frame") {
+            true => Ok(Self(regex)),
+            false => Err(PatternError::MissingFrame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_template() -> Result<(), PatternError> {
+        let pattern: FilenamePattern = "{date}-{roll}+{frame:03}".parse()?;
+        assert_eq!(
+            pattern.parse_path(Path::new("tests/data/20160513-A0012+001.tiff")),
+            Some(NameMatch {
+                roll: Some("A0012".into()),
+                frame: 1,
+                variant: None
+            })
+        );
+        assert_eq!(
+            pattern.parse_path(Path::new("20160513-A0012+0001.tiff")),
+            None
+        );
+        assert_eq!(pattern.parse_path(Path::new("A0012+001.tiff")), None);
+
+        let pattern: FilenamePattern = "{frame}".parse()?;
+        assert_eq!(
+            pattern.parse_path(Path::new("A0012/12.tif")),
+            Some(NameMatch {
+                roll: None,
+                frame: 12,
+                variant: None
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_regex() -> Result<(), PatternError> {
+        let pattern: FilenamePattern = r"(?P<roll>[A-Z]\d{4})\+(?P<frame>\d+)".parse()?;
+        assert_eq!(
+            pattern.parse_path(Path::new("20160513-A0012+001.tiff")),
+            Some(NameMatch {
+                roll: Some("A0012".into()),
+                frame: 1,
+                variant: None
+            })
+        );
+        assert_eq!(pattern.parse_path(Path::new("scan-001.tiff")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_variants() -> Result<(), PatternError> {
+        let pattern: FilenamePattern = "{roll}+{frame:03}".parse()?;
+        assert_eq!(
+            pattern.parse_path(Path::new("A0012+001-crop.tiff")),
+            Some(NameMatch {
+                roll: Some("A0012".into()),
+                frame: 1,
+                variant: Some("-crop".into())
+            })
+        );
+        assert!(is_variant_of(Path::new("001.tiff"), Path::new("001a.tiff")));
+        assert!(is_variant_of(
+            Path::new("001.tiff"),
+            Path::new("001-crop.tif")
+        ));
+        assert!(!is_variant_of(
+            Path::new("001.tiff"),
+            Path::new("0010.tiff")
+        ));
+        assert!(!is_variant_of(Path::new("001.tiff"), Path::new("001.tif")));
+        assert!(!is_variant_of(
+            Path::new("001.tiff"),
+            Path::new("002a.tiff")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn infer_roll_id() -> Result<(), PatternError> {
+        let pattern: FilenamePattern = "{date}-{roll}+{frame:03}".parse()?;
+        let path = Path::new("A0013/20160513-A0012+001.tiff");
+        assert_eq!(infer_roll(path, Some(&pattern)), Some("A0012".into()));
+        assert_eq!(infer_roll(path, None), Some("A0013".into()));
+        assert_eq!(
+            infer_roll(Path::new("A0013/001.tiff"), Some(&pattern)),
+            Some("A0013".into())
+        );
+        assert_eq!(infer_roll(Path::new("001.tiff"), None), None);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_frame_selection() -> Result<(), SelectionError> {
+        let selection: FrameSelection = "1-4, 6-8,10".parse()?;
+        assert_eq!(
+            selection.iter().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 6, 7, 8, 10]
+        );
+        assert!("".parse::<FrameSelection>().is_err());
+        assert!("0-4".parse::<FrameSelection>().is_err());
+        assert!("4-1".parse::<FrameSelection>().is_err());
+        assert!("1-4,a".parse::<FrameSelection>().is_err());
+        assert!("1,1".parse::<FrameSelection>().is_err());
+        assert!("1-3,2".parse::<FrameSelection>().is_err());
+        assert!("6-8,1-6".parse::<FrameSelection>().is_err());
+        assert_eq!(
+            "6-8,1-4"
+                .parse::<FrameSelection>()?
+                .iter()
+                .collect::<Vec<_>>(),
+            [6, 7, 8, 1, 2, 3, 4]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_csv_mapping() -> Result<(), MappingError> {
+        let mapping = Mapping::from_csv("frame,path\n2, b.tiff\n\n1,a.tiff\n")?;
+        assert_eq!(
+            mapping.iter().collect::<Vec<_>>(),
+            [(1, Path::new("a.tiff")), (2, Path::new("b.tiff"))]
+        );
+        assert_eq!(
+            Mapping::from_csv("1,a.tiff\n1,b.tiff"),
+            Err(MappingError::DuplicateFrame(1))
+        );
+        assert_eq!(
+            Mapping::from_csv("1,a.tiff\nb.tiff"),
+            Err(MappingError::MalformedLine(2))
+        );
+        assert_eq!(
+            Mapping::from_csv("0,a.tiff"),
+            Err(MappingError::InvalidFrame("0".into()))
+        );
+        assert_eq!(
+            Mapping::from_csv("1,\"strip 1, scan 2.tiff\"\n2,b.tiff,c.tiff"),
+            Err(MappingError::MalformedLine(2))
+        );
+        let mapping = Mapping::from_csv("1,\"strip 1, scan 2.tiff\"")?;
+        assert_eq!(
+            mapping.iter().collect::<Vec<_>>(),
+            [(1, Path::new("strip 1, scan 2.tiff"))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_toml_mapping() -> Result<(), toml::de::Error> {
+        let mapping = toml::from_str::<Mapping>(
+            r#"
+            12 = "strip-3/scan-0002.tiff"
+            3 = "strip-1/scan-0003.tiff"
+            "#,
+        )?
+        .relative_to(Path::new("scans"));
+        assert_eq!(
+            mapping.iter().collect::<Vec<_>>(),
+            [
+                (3, Path::new("scans/strip-1/scan-0003.tiff")),
+                (12, Path::new("scans/strip-3/scan-0002.tiff"))
+            ]
+        );
+        assert!(toml::from_str::<Mapping>("first = \"a.tiff\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_mapping() -> Result<(), MappingError> {
+        let frame = Frame {
+            film: None,
+            speed: None,
+            development: None,
+            lens: None,
+            lens_serial: None,
+            aperture: None,
+            shutter_speed: None,
+            focal_length: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: Default::default(),
+            position: crate::types::Position { lat: 0.0, lon: 0.0 },
+            note: None,
+        };
+        let frames = [Some(frame.clone()), None, Some(frame)];
+        let image = "tests/data/20160513-A0012+001.tiff";
+        Mapping::from_csv(&format!("1,{image}"))?.validate(&frames)?;
+        assert_eq!(
+            Mapping::from_csv(&format!("2,{image}"))?.validate(&frames),
+            Err(MappingError::UnknownFrame(2))
+        );
+        assert_eq!(
+            Mapping::from_csv(&format!("1,{image}\n3,{image}"))?.validate(&frames),
+            Err(MappingError::DuplicatePath(image.into()))
+        );
+        assert_eq!(
+            Mapping::from_csv("3,tests/data/missing.tiff")?.validate(&frames),
+            Err(MappingError::MissingFile("tests/data/missing.tiff".into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        assert_eq!(
+            "{roll}".parse::<FilenamePattern>().err(),
+            Some(PatternError::MissingFrame)
+        );
+        assert_eq!(
+            "{roll}-{frame:3}".parse::<FilenamePattern>().err(),
+            Some(PatternError::UnknownPlaceholder("frame:3".into()))
+        );
+        assert_eq!(
+            "{roll}-{number}".parse::<FilenamePattern>().err(),
+            Some(PatternError::UnknownPlaceholder("number".into()))
+        );
+        assert_eq!(
+            "{roll}-{frame".parse::<FilenamePattern>().err(),
+            Some(PatternError::Unterminated)
+        );
+        assert!(matches!(
+            "(?P<frame>\\d+".parse::<FilenamePattern>(),
+            Err(PatternError::InvalidRegex(_))
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+//! Merge policies for existing metadata
+//!
+//! By default, tagging overwrites any metadata already held by an image.
+//! Since some images already carry better data (e.g. corrected GPS positions
+//! or additional co-authors), each group of related fields can instead be
+//! given a different merge mode, either on the command line (`--merge`) or
+//! in the `[merge]` table of the configuration file:
+//!
+//! ```toml
+//! [merge]
+//! location = "keep"
+//! author = "append"
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Deserialize;
+
+/// How new values of a field group are merged with existing values
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[derive(clap::ValueEnum)]
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeMode {
+    /// Replace existing values
+    #[default]
+    Overwrite,
+    /// Keep existing values, never writing any field of the group
+    Keep,
+    /// Only write fields which are not already set
+    FillMissing,
+    /// Add new items to list fields (e.g. authors), and replace other values
+    Append,
+}
+
+/// Group of related metadata fields sharing a merge mode
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(clap::ValueEnum)]
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldGroup {
+    /// Camera make, model, serial number & owner
+    Camera,
+    /// Film name & ISO speed
+    Film,
+    /// Lens make, model & serial number, and focal length
+    Lens,
+    /// Shutter speed, aperture, exposure program, flash, metering & compensation
+    Exposure,
+    /// Original date & time
+    Date,
+    /// GPS position
+    Location,
+    /// Development record
+    Development,
+    /// Author, copyright & license
+    Author,
+    /// Scanning profile
+    Scan,
+}
+
+/// Invalid merge rule error
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(thiserror::Error)]
+#[error("invalid merge rule `{0}`, expected `GROUP=MODE` or `MODE`")]
+pub struct MergeRuleError(String);
+
+/// A merge rule given on the command line, e.g. `location=keep`
+///
+/// Rules without a field group apply to all groups.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MergeRule {
+    group: Option<FieldGroup>,
+    mode: MergeMode,
+}
+
+impl std::str::FromStr for MergeRule {
+    type Err = MergeRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use clap::ValueEnum;
+        let error = |_| MergeRuleError(s.to_owned());
+        let (group, mode) = match s.split_once('=') {
+            Some((group, mode)) => (Some(group), mode),
+            None => (None, s),
+        };
+        Ok(Self {
+            group: group
+                .map(|group| FieldGroup::from_str(group.trim(), true))
+                .transpose()
+                .map_err(error)?,
+            mode: MergeMode::from_str(mode.trim(), true).map_err(error)?,
+        })
+    }
+}
+
+/// Merge mode of each field group
+///
+/// Groups without an explicit mode use [`MergeMode::Overwrite`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct MergePolicy(BTreeMap<FieldGroup, MergeMode>);
+
+impl MergePolicy {
+    /// Get the merge mode of the given field group
+    pub fn mode(&self, group: FieldGroup) -> MergeMode {
+        self.0.get(&group).copied().unwrap_or_default()
+    }
+
+    /// Override this policy using the given rules, in order
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = MergeRule>) -> Self {
+        use clap::ValueEnum;
+        for rule in rules {
+            match rule.group {
+                Some(group) => {
+                    self.0.insert(group, rule.mode);
+                }
+                None => self.0.extend(
+                    FieldGroup::value_variants()
+                        .iter()
+                        .map(|group| (*group, rule.mode)),
+                ),
+            }
+        }
+        self
+    }
+}
+
+/// Merge state of a single image
+///
+/// Besides the policy, this tracks the fields written so far, such that
+/// values written earlier in the same run (e.g. the film of a roll) are not
+/// mistaken for existing values when overridden (e.g. by the film of a sheet).
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Merger {
+    policy: MergePolicy,
+    written: BTreeSet<String>,
+}
+
+impl Merger {
+    /// Start merging metadata into an image using the given policy
+    pub fn new(policy: &MergePolicy) -> Self {
+        Self {
+            policy: policy.clone(),
+            written: BTreeSet::new(),
+        }
+    }
+
+    /// Get the merge mode of the given field group
+    pub fn mode(&self, group: FieldGroup) -> MergeMode {
+        self.policy.mode(group)
+    }
+
+    /// Decide whether to write a field, given whether it is currently set
+    ///
+    /// The `field` name must uniquely identify the field within the image,
+    /// and is recorded if the field is to be written.
+    pub fn write(&mut self, group: FieldGroup, field: &str, present: bool) -> bool {
+        let write = match self.mode(group) {
+            MergeMode::Overwrite | MergeMode::Append => true,
+            MergeMode::Keep => false,
+            MergeMode::FillMissing => !present || self.written.contains(field),
+        };
+        if write {
+            self.written.insert(field.to_owned());
+        }
+        write
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_merge_rules() {
+        let rule: MergeRule = "location=keep".parse().expect("rule should be valid");
+        assert_eq!(rule.group, Some(FieldGroup::Location));
+        assert_eq!(rule.mode, MergeMode::Keep);
+        let rule: MergeRule = "fill-missing".parse().expect("rule should be valid");
+        assert_eq!(rule.group, None);
+        assert_eq!(rule.mode, MergeMode::FillMissing);
+        assert!("location=ignore".parse::<MergeRule>().is_err());
+        assert!("position=keep".parse::<MergeRule>().is_err());
+    }
+
+    #[test]
+    fn merge_policy_rules() {
+        let policy: MergePolicy = toml::from_str("author = \"append\"\nlocation = \"keep\"")
+            .expect("policy should be valid");
+        assert_eq!(policy.mode(FieldGroup::Author), MergeMode::Append);
+        assert_eq!(policy.mode(FieldGroup::Camera), MergeMode::Overwrite);
+        assert!(toml::from_str::<MergePolicy>("position = \"keep\"").is_err());
+
+        let rules = ["fill-missing", "location=overwrite"].map(|rule| rule.parse().unwrap());
+        let policy = policy.with_rules(rules);
+        assert_eq!(policy.mode(FieldGroup::Author), MergeMode::FillMissing);
+        assert_eq!(policy.mode(FieldGroup::Location), MergeMode::Overwrite);
+    }
+
+    #[test]
+    fn merge_fields() {
+        let policy = MergePolicy::default().with_rules(["film=fill-missing".parse().unwrap()]);
+        let mut merger = Merger::new(&policy.with_rules(["location=keep".parse().unwrap()]));
+        assert!(merger.write(FieldGroup::Camera, "Model", true));
+        assert!(!merger.write(FieldGroup::Location, "GPSLatitude", false));
+        assert!(!merger.write(FieldGroup::Film, "ISO", true));
+        assert!(merger.write(FieldGroup::Film, "UserComment", false));
+        assert!(merger.write(FieldGroup::Film, "UserComment", true));
+    }
+}
@@ -1,9 +1,14 @@
 //! Author metadata definitions
 //!
 //! This module provides definitions for author and license
-//! metadata, including basic Creative Commons licenses.
-use chrono::Datelike;
+//! metadata, including basic Creative Commons licenses, as
+//! well as a scanning profile describing how the negatives
+//! were digitised.
+use chrono::{Datelike, NaiveDateTime};
 use serde::Deserialize;
+use serde_with::{serde_as, FromInto};
+
+use crate::types::TomlDateTime;
 
 /// A Creative Commons license
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -65,12 +70,32 @@ pub struct Author {
     pub url: Option<String>,
 }
 
+/// A scanning profile
+///
+/// Describes how a negative was digitised, i.e. the scanner (or camera
+/// scanning rig) make & model, the scanning software, the resolution in
+/// pixels per inch, the scan date, and the film holder used.
+#[serde_as]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scan {
+    pub scanner: Option<String>,
+    pub software: Option<String>,
+    pub resolution: Option<u32>,
+    #[serde_as(as = "Option<FromInto<TomlDateTime>>")]
+    #[serde(default)]
+    pub date: Option<NaiveDateTime>,
+    pub holder: Option<String>,
+}
+
 /// A full set of author metadata
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
 #[derive(Deserialize)]
 pub struct Metadata {
     pub author: Author,
     pub license: Option<License>,
+    pub scan: Option<Scan>,
 }
 
 impl Metadata {
@@ -121,6 +146,7 @@ mod tests {
                 url: None,
             },
             license: None,
+            scan: None,
         };
         assert_eq!(input, expected);
         Ok(())
@@ -133,6 +159,13 @@ mod tests {
             author.name = "Simon Sigurdhsson"
             author.url = "http://photography.sigurdhsson.org/"
             license = "cc-by-nc"
+
+            [scan]
+            scanner = "Epson Perfection V850 Pro"
+            software = "SilverFast 9"
+            resolution = 3200
+            date = 2025-06-01
+            holder = "Betterscanning 35mm"
             "#,
         )?;
         let expected = Metadata {
@@ -141,6 +174,13 @@ mod tests {
                 url: Some("http://photography.sigurdhsson.org/".into()),
             },
             license: Some(License::AttributionNc),
+            scan: Some(Scan {
+                scanner: Some("Epson Perfection V850 Pro".into()),
+                software: Some("SilverFast 9".into()),
+                resolution: Some(3200),
+                date: NaiveDate::from_ymd_opt(2025, 6, 1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+                holder: Some("Betterscanning 35mm".into()),
+            }),
         };
         assert_eq!(input, expected);
         Ok(())
@@ -154,6 +194,7 @@ mod tests {
                 url: None,
             },
             license: None,
+            scan: None,
         };
         assert_eq!(no_license.license.as_ref().map(|l| l.url()), None);
         assert_eq!(
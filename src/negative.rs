@@ -8,10 +8,13 @@ use little_exif::{exif_tag::ExifTag, ifd::ExifTagGroup};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::metadata::Metadata;
+use crate::merge::Merger;
+use crate::metadata::{Metadata, Scan};
 use crate::rolls::{Frame, Roll};
+use crate::types::{Aperture, FocalLength, Position, ShutterSpeed};
 
 mod exif;
+mod file;
 mod xmp;
 
 /// Metadata application errors
@@ -30,6 +33,96 @@ pub enum NegativeError {
     /// UTF8 conversion error
     #[error(transparent)]
     Utf8Error(#[from] std::string::FromUtf8Error),
+
+    /// Source file could not be atomically replaced
+    #[error("Unable to safely replace {}", .0.display())]
+    ReplaceError(PathBuf, #[source] std::io::Error),
+}
+
+/// Film-related metadata read back from a negative
+#[serde_with::serde_as]
+#[derive(Clone, PartialEq, Debug, Default)]
+#[derive(serde::Serialize)]
+pub struct Details {
+    pub path: PathBuf,
+    pub roll: Option<String>,
+    pub frame: Option<usize>,
+    pub camera: Option<String>,
+    pub lens: Option<String>,
+    pub film: Option<String>,
+    pub iso: Option<u32>,
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub shutter_speed: Option<ShutterSpeed>,
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub aperture: Option<Aperture>,
+    #[serde_as(as = "Option<serde_with::DisplayFromStr>")]
+    pub focal_length: Option<FocalLength>,
+    #[serde_as(as = "Option<crate::output::Rfc3339>")]
+    pub date: Option<chrono::NaiveDateTime>,
+    #[serde(flatten, serialize_with = "serialize_position")]
+    pub position: Option<Position>,
+    pub author: Option<String>,
+    pub copyright: Option<String>,
+    pub license: Option<String>,
+}
+
+impl crate::output::Record for Details {
+    const FIELDS: &'static [&'static str] = &[
+        "path",
+        "roll",
+        "frame",
+        "camera",
+        "lens",
+        "film",
+        "iso",
+        "shutter_speed",
+        "aperture",
+        "focal_length",
+        "date",
+        "latitude",
+        "longitude",
+        "author",
+        "copyright",
+        "license",
+    ];
+}
+
+/// Serialize a position as separate `latitude` and `longitude` fields
+fn serialize_position<S>(position: &Option<Position>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    #[derive(serde::Serialize)]
+    struct Coordinates {
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+    }
+    serde::Serialize::serialize(
+        &Coordinates {
+            latitude: position.map(|position| position.lat),
+            longitude: position.map(|position| position.lon),
+        },
+        serializer,
+    )
+}
+
+/// A metadata field differing between two negatives
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Difference {
+    pub field: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+/// Provenance of the metadata written when tagging a negative
+#[derive(Clone, PartialEq, Debug)]
+pub struct Provenance {
+    pub roll: String,
+    pub frame: Option<usize>,
+    pub source: Option<PathBuf>,
+    pub load: chrono::NaiveDateTime,
+    pub unload: chrono::NaiveDateTime,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
 /// A "negative" (image with metadata)
@@ -39,6 +132,7 @@ pub struct Negative {
     xmp: xmp_toolkit::XmpMeta,
     path: PathBuf,
     roll: Option<String>,
+    frame: Option<usize>,
 }
 
 impl std::fmt::Debug for Negative {
@@ -48,6 +142,7 @@ impl std::fmt::Debug for Negative {
             .field("xmp", &self.xmp)
             .field("path", &self.path)
             .field("roll", &self.roll)
+            .field("frame", &self.frame)
             .finish()
     }
 }
@@ -58,34 +153,21 @@ impl Negative {
     /// This will open up the given path for reading, and extract both EXIF
     /// and XMP data if available. Only file formats supported by [little_exif]
     /// are supported; XMP data is extracted from the EXIF IFD and fed directly
-    /// to the XMP Toolkit to avoid the toolkit reconciling legacy tags.
+    /// to the XMP Toolkit to avoid the toolkit reconciling legacy tags. The
+    /// roll ID and frame number are read from any previously recorded
+    /// [`Provenance`].
     ///
     /// [little_exif]: https://docs.rs/little_exif/latest/little_exif/
     pub fn new_from_path(path: &Path) -> Result<Negative, NegativeError> {
         let exif_data = little_exif::metadata::Metadata::new_from_path(path)?;
-        let xmp_data = exif_data
-            .get_tag(&ExifTag::UnknownINT8U(
-                vec![],
-                0x02bc,
-                ExifTagGroup::GENERIC,
-            ))
-            .next()
-            .and_then(|tag| match tag {
-                ExifTag::UnknownUNDEF(value, _, _) => Some(value),
-                ExifTag::UnknownINT8U(value, _, _) => Some(value),
-                _ => None,
-            })
-            .map(|data| -> Result<xmp_toolkit::XmpMeta, NegativeError> {
-                String::from_utf8(data.to_vec())
-                    .map_err(Into::<NegativeError>::into)
-                    .and_then(|s| Ok(FromStr::from_str(&s)?))
-            })
-            .unwrap_or_else(|| Ok(xmp_toolkit::XmpMeta::new()?));
+        let xmp = read_xmp(&exif_data)?;
+        let (roll, frame) = xmp::read_provenance(&xmp);
         Ok(Self {
+            xmp,
             exif: exif_data,
-            xmp: xmp_data?,
             path: path.into(),
-            roll: None,
+            roll,
+            frame,
         })
     }
 
@@ -98,6 +180,16 @@ impl Negative {
                 .expect("it should be possible to create empty XMP metadata"),
             path: PathBuf::new(),
             roll: None,
+            frame: None,
+        }
+    }
+
+    #[cfg(test)]
+    /// Create a new, empty negative with the given path
+    pub(crate) fn new_with_path(path: &Path) -> Negative {
+        Self {
+            path: path.into(),
+            ..Self::new()
         }
     }
 
@@ -111,6 +203,11 @@ impl Negative {
         self.roll.as_deref()
     }
 
+    /// Get the frame number of this negative, if any
+    pub fn frame(&self) -> Option<usize> {
+        self.frame
+    }
+
     /// Get the original date/time of this negative, if any
     pub fn date(&self) -> Option<chrono::NaiveDateTime> {
         use little_exif::exif_tag::ExifTag;
@@ -132,10 +229,93 @@ impl Negative {
         })
     }
 
+    /// Read the film-related metadata held by this negative
+    ///
+    /// This reads back camera, lens, film, exposure, position and author
+    /// information from EXIF, and the license from XMP.
+    pub fn details(&self) -> Details {
+        Details {
+            path: self.path.clone(),
+            roll: self.roll.clone(),
+            frame: self.frame,
+            date: self.date(),
+            license: xmp::read_license(&self.xmp),
+            ..exif::read_details(&self.exif)
+        }
+    }
+
+    /// Compare the metadata of this negative with an `expected` negative
+    ///
+    /// Every EXIF tag and XMP property which is missing from, differs
+    /// between, or is only present in either negative is returned, with
+    /// EXIF tags named as e.g. `EXIF Model` and XMP properties by their
+    /// path, e.g. `XMP exif:DateTimeOriginal`.
+    pub fn diff(&self, expected: &Negative) -> Vec<Difference> {
+        let fields = |negative: &Negative| {
+            let exif = exif::read_fields(&negative.exif).into_iter();
+            let xmp = xmp::read_fields(&negative.xmp).into_iter();
+            exif.map(|(name, value)| (format!("EXIF {name}"), value))
+                .chain(xmp.map(|(name, value)| (format!("XMP {name}"), value)))
+                .collect::<std::collections::BTreeMap<_, _>>()
+        };
+        let (mut actual, expected) = (fields(self), fields(expected));
+        let mut differences = Vec::new();
+        for (field, expected) in expected {
+            match actual.remove(&field) {
+                Some(actual) if actual == expected => {}
+                actual => differences.push(Difference {
+                    field,
+                    expected: Some(expected),
+                    actual,
+                }),
+            }
+        }
+        differences.extend(actual.into_iter().map(|(field, actual)| Difference {
+            field,
+            expected: None,
+            actual: Some(actual),
+        }));
+        differences.sort();
+        differences
+    }
+
+    /// Get the XMP document ID of this negative, if any
+    pub fn document_id(&self) -> Option<String> {
+        self.xmp
+            .property(xmp_toolkit::xmp_ns::XMP_MM, "DocumentID")
+            .map(|value| value.value)
+    }
+
+    /// Link this negative to the other scans of the same frame
+    ///
+    /// All scans of a frame share the given `document_id`; scans derived
+    /// from an `original` scan (e.g. crops or rescans) also refer to it.
+    pub fn link_variant(
+        &mut self,
+        document_id: &str,
+        original: Option<&Path>,
+    ) -> Result<(), NegativeError> {
+        xmp::set_variant_link(&mut self.xmp, document_id, original)?;
+        Ok(())
+    }
+
+    /// Record the provenance of the metadata applied to this negative
+    ///
+    /// The provenance is written to a dedicated XMP namespace, from which
+    /// the roll ID and frame number are read back by [`Negative::new_from_path`].
+    pub fn record_provenance(&mut self, provenance: &Provenance) -> Result<(), NegativeError> {
+        xmp::set_provenance(&mut self.xmp, provenance)?;
+        self.roll = Some(provenance.roll.clone());
+        self.frame = provenance.frame;
+        Ok(())
+    }
+
     /// Save the metadata back to the source file
     ///
     /// As with [`Negative::new_from_path`], this will use [little_exif] to write
     /// EXIF tags to the source file, bypassing the XMP Toolkit reconciliation.
+    /// The source file is replaced atomically, such that it is left untouched
+    /// if writing fails part-way.
     ///
     /// [little_exif]: https://docs.rs/little_exif/latest/little_exif/
     pub fn save(&mut self) -> Result<(), NegativeError> {
@@ -147,22 +327,85 @@ impl Negative {
             0x02bc,
             ExifTagGroup::GENERIC,
         ));
-        self.exif.write_to_file(&self.path)?;
-        Ok(())
+        self.write()
+    }
+
+    /// Take a snapshot of the metadata currently stored in the source file
+    ///
+    /// The snapshot is an encoded EXIF structure holding the descriptive tags
+    /// of IFD0 and its EXIF and GPS sub-IFDs, including the embedded XMP
+    /// packet, as read from disk (ignoring any metadata applied to this
+    /// negative in memory). Tags describing the image data, and the image data
+    /// itself, are not included.
+    pub fn snapshot(&self) -> Result<Vec<u8>, NegativeError> {
+        let exif = little_exif::metadata::Metadata::new_from_path(&self.path)?;
+        Ok(exif::copy_metadata(&exif).encode()?)
+    }
+
+    /// Restore the metadata of the source file from a snapshot
+    ///
+    /// The metadata held by the snapshot, as taken by [`Negative::snapshot`],
+    /// replaces the EXIF and XMP metadata currently stored in the source file,
+    /// keeping its current image data.
+    pub fn restore(&mut self, snapshot: &[u8]) -> Result<(), NegativeError> {
+        use little_exif::filetype::FileExtension;
+        let snapshot =
+            little_exif::metadata::Metadata::new_from_vec(&snapshot.to_vec(), FileExtension::TIFF)?;
+        let mut exif = little_exif::metadata::Metadata::new_from_path(&self.path)?;
+        exif::replace_metadata(&mut exif, &snapshot);
+        self.xmp = read_xmp(&exif)?;
+        (self.roll, self.frame) = xmp::read_provenance(&self.xmp);
+        self.exif = exif;
+        self.write()
     }
+
+    /// Write the EXIF metadata to a copy of the source file, and replace it
+    fn write(&self) -> Result<(), NegativeError> {
+        let file_type = little_exif::filetype::get_file_type(&self.path)?;
+        let mut data = std::fs::read(&self.path)?;
+        self.exif.write_to_vec(&mut data, file_type)?;
+        file::replace(&self.path, &data)
+            .map_err(|error| NegativeError::ReplaceError(self.path.clone(), error))
+    }
+}
+
+/// Read the XMP packet embedded in the given EXIF data, if any
+///
+/// The packet is fed directly to the XMP Toolkit; if there is none, empty
+/// XMP metadata is returned instead.
+fn read_xmp(exif: &little_exif::metadata::Metadata) -> Result<xmp_toolkit::XmpMeta, NegativeError> {
+    exif.get_tag(&ExifTag::UnknownINT8U(
+        vec![],
+        0x02bc,
+        ExifTagGroup::GENERIC,
+    ))
+    .next()
+    .and_then(|tag| match tag {
+        ExifTag::UnknownUNDEF(value, _, _) => Some(value),
+        ExifTag::UnknownINT8U(value, _, _) => Some(value),
+        _ => None,
+    })
+    .map(|data| -> Result<xmp_toolkit::XmpMeta, NegativeError> {
+        String::from_utf8(data.to_vec())
+            .map_err(Into::<NegativeError>::into)
+            .and_then(|s| Ok(FromStr::from_str(&s)?))
+    })
+    .unwrap_or_else(|| Ok(xmp_toolkit::XmpMeta::new()?))
 }
 
 /// Apply film roll and author metadata to a negative
 ///
-/// This trait is used to apply [`Roll`], [`Frame`], and [`Metadata`] to `Self`, which
-/// when applied to [`Negative`] will simply forward the application to the underlying
-/// EXIF and XMP representations held in memory.
+/// This trait is used to apply [`Roll`], [`Frame`], [`Metadata`], and [`Scan`] to `Self`,
+/// which when applied to [`Negative`] will simply forward the application to the
+/// underlying EXIF and XMP representations held in memory. Existing metadata is
+/// merged with the applied metadata according to the given [`Merger`], which
+/// should be used for all metadata applied to the same negative.
 pub trait ApplyMetadata {
     /// Apply [`Roll`] metadata to `self`
-    fn apply_roll_data(&mut self, data: &Roll) -> Result<(), NegativeError>;
+    fn apply_roll_data(&mut self, data: &Roll, merge: &mut Merger) -> Result<(), NegativeError>;
 
     /// Apply [`Frame`] metadata to `self`
-    fn apply_frame_data(&mut self, data: &Frame) -> Result<(), NegativeError>;
+    fn apply_frame_data(&mut self, data: &Frame, merge: &mut Merger) -> Result<(), NegativeError>;
 
     /// Apply author metadata to `self`
     ///
@@ -175,20 +418,27 @@ pub trait ApplyMetadata {
         &mut self,
         data: &Metadata,
         date: &Option<chrono::NaiveDate>,
+        merge: &mut Merger,
     ) -> Result<(), NegativeError>;
+
+    /// Apply [`Scan`] metadata to `self`
+    ///
+    /// This describes how the negative was digitised, and must not touch
+    /// the camera make & model (which describe the exposure, not the scan).
+    fn apply_scan_data(&mut self, data: &Scan, merge: &mut Merger) -> Result<(), NegativeError>;
 }
 
 impl ApplyMetadata for Negative {
-    fn apply_roll_data(&mut self, data: &Roll) -> Result<(), NegativeError> {
-        self.exif.apply_roll_data(data)?;
-        self.xmp.apply_roll_data(data)?;
+    fn apply_roll_data(&mut self, data: &Roll, merge: &mut Merger) -> Result<(), NegativeError> {
+        self.exif.apply_roll_data(data, merge)?;
+        self.xmp.apply_roll_data(data, merge)?;
         self.roll = Some(data.id.clone());
         Ok(())
     }
 
-    fn apply_frame_data(&mut self, data: &Frame) -> Result<(), NegativeError> {
-        self.exif.apply_frame_data(data)?;
-        self.xmp.apply_frame_data(data)?;
+    fn apply_frame_data(&mut self, data: &Frame, merge: &mut Merger) -> Result<(), NegativeError> {
+        self.exif.apply_frame_data(data, merge)?;
+        self.xmp.apply_frame_data(data, merge)?;
         Ok(())
     }
 
@@ -196,10 +446,17 @@ impl ApplyMetadata for Negative {
         &mut self,
         data: &Metadata,
         date: &Option<chrono::NaiveDate>,
+        merge: &mut Merger,
     ) -> Result<(), NegativeError> {
         let date = date.or_else(|| self.date().map(|d| d.date()));
-        self.exif.apply_author_data(data, &date)?;
-        self.xmp.apply_author_data(data, &date)?;
+        self.exif.apply_author_data(data, &date, merge)?;
+        self.xmp.apply_author_data(data, &date, merge)?;
+        Ok(())
+    }
+
+    fn apply_scan_data(&mut self, data: &Scan, merge: &mut Merger) -> Result<(), NegativeError> {
+        self.exif.apply_scan_data(data, merge)?;
+        self.xmp.apply_scan_data(data, merge)?;
         Ok(())
     }
 }
@@ -207,6 +464,7 @@ impl ApplyMetadata for Negative {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merge::Merger;
     use crate::rolls::*;
     use crate::types::*;
     use chrono::Timelike;
@@ -217,6 +475,7 @@ mod tests {
         let negative = Negative::new();
         assert_eq!(negative.path(), PathBuf::new());
         assert_eq!(negative.roll(), None);
+        assert_eq!(negative.frame(), None);
         assert_eq!(negative.date(), None);
     }
 
@@ -224,32 +483,167 @@ mod tests {
     fn updated_frame_details() {
         let mut negative = Negative::new();
         let datetime = chrono::Utc::now().naive_local();
+        let mut merger = Merger::default();
         negative
-            .apply_roll_data(&Roll {
-                id: "A1234".into(),
-                film: None,
-                speed: FilmSpeed::from_din(21),
-                camera: None,
-                load: chrono::NaiveDateTime::MIN,
-                unload: chrono::NaiveDateTime::MAX,
-                frames: vec![],
-            })
+            .apply_roll_data(
+                &Roll {
+                    id: "A1234".into(),
+                    sheet_film: false,
+                    film: None,
+                    speed: FilmSpeed::from_din(21),
+                    camera: None,
+                    camera_serial: None,
+                    camera_owner: None,
+                    load: chrono::NaiveDateTime::MIN,
+                    unload: chrono::NaiveDateTime::MAX,
+                    development: None,
+                    frames: vec![],
+                },
+                &mut merger,
+            )
             .expect("roll data should be applicable to negative");
         negative
-            .apply_frame_data(&Frame {
-                lens: None,
-                aperture: None,
-                shutter_speed: None,
-                focal_length: None,
-                compensation: None,
-                datetime,
-                position: Default::default(),
-                note: None,
-            })
+            .apply_frame_data(
+                &Frame {
+                    film: None,
+                    speed: None,
+                    development: None,
+                    lens: None,
+                    lens_serial: None,
+                    aperture: None,
+                    shutter_speed: None,
+                    focal_length: None,
+                    compensation: None,
+                    flash: None,
+                    metering: None,
+                    light_source: None,
+                    datetime,
+                    position: Default::default(),
+                    note: None,
+                },
+                &mut merger,
+            )
             .expect("frame data should be applicable to negative");
 
         assert_eq!(negative.path(), PathBuf::new());
         assert_eq!(negative.roll(), Some("A1234"));
         assert_eq!(negative.date(), datetime.with_nanosecond(0));
     }
+
+    #[test]
+    fn recorded_provenance() -> Result<(), NegativeError> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("20160513-A0012+001.tiff");
+        std::fs::copy("tests/data/20160513-A0012+001.tiff", &path)?;
+        let mut negative = Negative::new_from_path(&path)?;
+        assert_eq!(negative.roll(), None);
+        assert_eq!(negative.frame(), None);
+
+        // The roll ID & frame number are read back after saving
+        negative.record_provenance(&Provenance {
+            roll: "A0012".into(),
+            frame: Some(1),
+            source: Some("tests/data/filmrolls.xml".into()),
+            load: chrono::DateTime::UNIX_EPOCH.naive_utc(),
+            unload: chrono::DateTime::UNIX_EPOCH.naive_utc(),
+            timestamp: chrono::Utc::now(),
+        })?;
+        assert_eq!(negative.roll(), Some("A0012"));
+        assert_eq!(negative.frame(), Some(1));
+        negative.save()?;
+        let negative = Negative::new_from_path(&path)?;
+        assert_eq!(negative.roll(), Some("A0012"));
+        assert_eq!(negative.frame(), Some(1));
+        assert_eq!(negative.details().frame, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn diff_negatives() {
+        let negative = Negative::new();
+        assert_eq!(negative.diff(&negative), vec![]);
+
+        let mut expected = negative.clone();
+        expected
+            .link_variant("xmp.did:A1234/001", None)
+            .expect("variant link should be applicable to negative");
+        expected.exif.set_tag(ExifTag::Model("Bessa R2M".into()));
+        assert_eq!(
+            negative.diff(&expected),
+            vec![
+                Difference {
+                    field: "EXIF Model".into(),
+                    expected: Some("Bessa R2M".into()),
+                    actual: None,
+                },
+                Difference {
+                    field: "XMP xmpMM:DocumentID".into(),
+                    expected: Some("xmp.did:A1234/001".into()),
+                    actual: None,
+                },
+            ]
+        );
+        assert_eq!(
+            expected.diff(&negative),
+            vec![
+                Difference {
+                    field: "EXIF Model".into(),
+                    expected: None,
+                    actual: Some("Bessa R2M".into()),
+                },
+                Difference {
+                    field: "XMP xmpMM:DocumentID".into(),
+                    expected: None,
+                    actual: Some("xmp.did:A1234/001".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn snapshot_and_restore() -> Result<(), NegativeError> {
+        use little_exif::filetype::FileExtension;
+        use little_exif::metadata::Metadata;
+        let strips = |exif: &Metadata| {
+            exif.get_tag(&ExifTag::StripOffsets(vec![], vec![]))
+                .next()
+                .and_then(|tag| match tag {
+                    ExifTag::StripOffsets(_, data) => Some(data.clone()),
+                    _ => None,
+                })
+        };
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("20160513-A0012+001.tiff");
+        std::fs::copy("tests/data/20160513-A0012+001.tiff", &path)?;
+
+        // Snapshots hold the metadata, but not the image data
+        let original = Negative::new_from_path(&path)?;
+        let snapshot = original.snapshot()?;
+        assert!(strips(&original.exif).is_some());
+        assert!(strips(&Metadata::new_from_vec(&snapshot, FileExtension::TIFF)?).is_none());
+
+        // Modify the metadata, and then the image data
+        let mut negative = original.clone();
+        negative.link_variant("xmp.did:A0012/001", None)?;
+        negative.exif.set_tag(ExifTag::Model("Bessa R2M".into()));
+        negative.save()?;
+        assert_ne!(Negative::new_from_path(&path)?.diff(&original), vec![]);
+        let mut exif = Metadata::new_from_path(&path)?;
+        let mut pixels = strips(&exif).expect("image data should be present");
+        pixels[0].iter_mut().for_each(|byte| *byte = !*byte);
+        exif.set_tag(ExifTag::StripOffsets(vec![], pixels.clone()));
+        exif.write_to_file(&path)?;
+        assert_eq!(
+            strips(&Metadata::new_from_path(&path)?),
+            Some(pixels.clone())
+        );
+        assert_ne!(strips(&original.exif), Some(pixels.clone()));
+
+        // Restoring reverts the metadata, keeping the current image data
+        negative.restore(&snapshot)?;
+        assert_eq!(negative.diff(&original), vec![]);
+        assert_eq!(Negative::new_from_path(&path)?.diff(&original), vec![]);
+        assert_eq!(strips(&Metadata::new_from_path(&path)?), Some(pixels));
+        Ok(())
+    }
 }
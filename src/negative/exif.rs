@@ -1,135 +1,188 @@
 //! Implements [`super::ApplyMetadata`] for [little_exif]
 //!
+//! Also provides [`read_details`] and [`read_fields`], reading the applied
+//! metadata back, and [`copy_metadata`] and [`replace_metadata`], used for
+//! snapshots of the metadata stored in a file.
+//!
 //! [little_exif]: https://docs.rs/little_exif/latest/little_exif/
+use std::collections::BTreeMap;
+
 use little_exif::exif_tag::ExifTag;
 use little_exif::ifd::ExifTagGroup;
 use little_exif::rational::{iR64, uR64};
 
-use crate::metadata::Metadata;
-use crate::rolls::{Frame, Roll};
+use crate::merge::{FieldGroup, MergeMode, Merger};
+use crate::metadata::{Metadata, Scan};
+use crate::rolls::{Film, Frame, Roll};
 use crate::types::*;
 
 impl super::ApplyMetadata for little_exif::metadata::Metadata {
-    fn apply_roll_data(&mut self, data: &Roll) -> Result<(), super::NegativeError> {
+    fn apply_roll_data(
+        &mut self,
+        data: &Roll,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
         // Set EXIF version
         self.set_tag(ExifTag::ExifVersion(b"0231".into()));
 
         // Set camera make & model, if available
+        let mut tags = Tags::new(self, merge, FieldGroup::Camera);
         if let Some(camera) = &data.camera {
-            self.set_tag(ExifTag::UnknownSTRING(
+            tags.set(ExifTag::UnknownSTRING(
                 camera.to_string(),
                 0xc615,
                 ExifTagGroup::GENERIC,
             ));
             if let Some(make) = camera.make() {
-                self.set_tag(ExifTag::Make(make.to_owned()));
+                tags.set(ExifTag::Make(make.to_owned()));
             }
-            self.set_tag(ExifTag::Model(camera.model().to_owned()));
+            tags.set(ExifTag::Model(camera.model().to_owned()));
+        }
+
+        // Set camera serial number & owner, if available
+        if let Some(serial) = &data.camera_serial {
+            tags.set(ExifTag::SerialNumber(serial.to_owned()));
+        }
+        if let Some(owner) = &data.camera_owner {
+            tags.set(ExifTag::OwnerName(owner.to_owned()));
         }
 
         // Set film name in user comment, if available
+        let mut tags = Tags::new(self, merge, FieldGroup::Film);
         if let Some(film) = &data.film {
-            self.set_tag(ExifTag::UserComment(to_exif_undef(
-                &film.to_string(),
-                self.get_endian(),
-            )));
+            tags.set_film(film);
         }
 
         // Set film ISO speed
-        let iso: i64 = data.speed.iso().as_rational().to_integer();
-        self.set_tag(ExifTag::ISO(vec![
-            iso.clamp(u16::MIN as i64, u16::MAX as i64) as u16,
-        ]));
-        self.set_tag(ExifTag::ISOSpeed(vec![
-            iso.clamp(u32::MIN as i64, u32::MAX as i64) as u32,
-        ]));
-        self.set_tag(ExifTag::SensitivityType(vec![3u16])); // "ISO Speed"
+        tags.set_film_speed(&data.speed);
 
         // Success!
         Ok(())
     }
 
-    fn apply_frame_data(&mut self, data: &Frame) -> Result<(), super::NegativeError> {
+    fn apply_frame_data(
+        &mut self,
+        data: &Frame,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
         // Set original date/time
-        self.set_tag(ExifTag::DateTimeOriginal(
+        Tags::new(self, merge, FieldGroup::Date).set(ExifTag::DateTimeOriginal(
             data.datetime.format("%Y:%m:%d %H:%M:%S").to_string(),
         ));
 
+        // Override film name and ISO speed, if available (e.g. for sheet film)
+        let mut tags = Tags::new(self, merge, FieldGroup::Film);
+        if let Some(film) = &data.film {
+            tags.set_film(film);
+        }
+        if let Some(speed) = &data.speed {
+            tags.set_film_speed(speed);
+        }
+
         // Set lens make & model, if available
+        let mut tags = Tags::new(self, merge, FieldGroup::Lens);
         if let Some(lens) = &data.lens {
-            self.set_tag(ExifTag::UnknownSTRING(
+            tags.set(ExifTag::UnknownSTRING(
                 lens.to_string(),
                 0xfdea,
                 ExifTagGroup::EXIF,
             ));
             if let Some(make) = lens.make() {
-                self.set_tag(ExifTag::LensMake(make.to_owned()));
+                tags.set(ExifTag::LensMake(make.to_owned()));
             }
-            self.set_tag(ExifTag::LensModel(lens.model().to_owned()));
+            tags.set(ExifTag::LensModel(lens.model().to_owned()));
+        }
+
+        // Set lens serial number, if available
+        if let Some(serial) = &data.lens_serial {
+            tags.set(ExifTag::LensSerialNumber(serial.to_owned()));
         }
 
         // Set focal length and optionally 35mm equivalent focal length
         if let Some(focal_length) = data.focal_length {
             let ratio: num_rational::Ratio<i64> = focal_length.real.as_rational();
-            self.set_tag(ExifTag::FocalLength(vec![uR64::from_rational(ratio)]));
+            tags.set(ExifTag::FocalLength(vec![uR64::from_rational(ratio)]));
             if let Some(equiv) = focal_length.equiv {
                 let equiv = equiv
                     .round()
                     .normalize()
                     .mantissa()
                     .clamp(u16::MIN as i128, u16::MAX as i128) as u16;
-                self.set_tag(ExifTag::FocalLengthIn35mmFormat(vec![equiv]));
+                tags.set(ExifTag::FocalLengthIn35mmFormat(vec![equiv]));
             }
         }
 
-        // Set shutter speed, aperture, and exposure program
+        // Set shutter speed, aperture, exposure program and exposure mode
+        let mut tags = Tags::new(self, merge, FieldGroup::Exposure);
         if let Some(ShutterSpeed::Manual(value)) = data.shutter_speed {
-            self.set_tag(ExifTag::ExposureTime(vec![
+            tags.set(ExifTag::ExposureTime(vec![
                 uR64::from_rational(value), //
             ]));
-            self.set_tag(ExifTag::ShutterSpeedValue(vec![
+            tags.set(ExifTag::ShutterSpeedValue(vec![
                 iR64::from_rational(log2(value.recip())), // APEX value
             ]));
         }
 
         if let Some(Aperture::Manual(value)) = data.aperture {
             let ratio: num_rational::Ratio<i64> = value.as_rational();
-            self.set_tag(ExifTag::FNumber(vec![
+            tags.set(ExifTag::FNumber(vec![
                 uR64::from_rational(ratio), //
             ]));
-            self.set_tag(ExifTag::ApertureValue(vec![
+            tags.set(ExifTag::ApertureValue(vec![
                 uR64::from_rational(log2(ratio.pow(2))), // APEX value
             ]));
         }
 
         match (data.shutter_speed, data.aperture) {
             (Some(ShutterSpeed::AperturePriority), Some(Aperture::ShutterPriority)) => {
-                self.set_tag(ExifTag::ExposureProgram(vec![2u16])) // "Program AE"
+                tags.set(ExifTag::ExposureProgram(vec![2u16])) // "Program AE"
             }
             (Some(ShutterSpeed::AperturePriority), Some(Aperture::Manual(_))) => {
-                self.set_tag(ExifTag::ExposureProgram(vec![3u16])) // "Aperture Priority AE"
+                tags.set(ExifTag::ExposureProgram(vec![3u16])) // "Aperture Priority AE"
             }
             (Some(ShutterSpeed::Manual(_)), Some(Aperture::ShutterPriority)) => {
-                self.set_tag(ExifTag::ExposureProgram(vec![4u16])) // "Shutter Priority AE"
+                tags.set(ExifTag::ExposureProgram(vec![4u16])) // "Shutter Priority AE"
             }
             (Some(ShutterSpeed::Manual(_)), Some(Aperture::Manual(_))) => {
-                self.set_tag(ExifTag::ExposureProgram(vec![1u16])) // "Manual"
+                tags.set(ExifTag::ExposureProgram(vec![1u16])) // "Manual"
             }
             (_, _) => {
-                self.set_tag(ExifTag::ExposureProgram(vec![0u16])) // "Not Defined"
+                tags.set(ExifTag::ExposureProgram(vec![0u16])) // "Not Defined"
+            }
+        }
+
+        match (data.shutter_speed, data.aperture) {
+            (Some(ShutterSpeed::AperturePriority), _) | (_, Some(Aperture::ShutterPriority)) => {
+                tags.set(ExifTag::ExposureMode(vec![0u16])) // "Auto"
+            }
+            (Some(ShutterSpeed::Manual(_)), Some(Aperture::Manual(_))) => {
+                tags.set(ExifTag::ExposureMode(vec![1u16])) // "Manual"
             }
+            (_, _) => {}
+        }
+
+        // Set flash, metering mode & light source, if available
+        if let Some(flash) = data.flash {
+            tags.set(ExifTag::Flash(vec![flash.exif()]));
+        }
+        if let Some(metering) = data.metering {
+            tags.set(ExifTag::MeteringMode(vec![metering.exif()]));
+        }
+        if let Some(light_source) = data.light_source {
+            tags.set(ExifTag::LightSource(vec![light_source.exif()]));
         }
 
         // Set the EV compensation, if available
         if let Some(ExposureBias(bias)) = data.compensation {
-            self.set_tag(ExifTag::ExposureCompensation(vec![
+            tags.set(ExifTag::ExposureCompensation(vec![
                 iR64::from_rational(bias), //
             ]))
         }
 
         // Set the GPS position of this shot
-        set_longitude(self, data.position.lon);
-        set_latitude(self, data.position.lat);
+        let mut tags = Tags::new(self, merge, FieldGroup::Location);
+        tags.set_longitude(data.position.lon);
+        tags.set_latitude(data.position.lat);
 
         // Success!
         Ok(())
@@ -139,52 +192,805 @@ impl super::ApplyMetadata for little_exif::metadata::Metadata {
         &mut self,
         data: &Metadata,
         date: &Option<chrono::NaiveDate>,
+        merge: &mut Merger,
     ) -> Result<(), super::NegativeError> {
         // Figure out what year this negative was shot, for the copyright
         let date = date.unwrap_or_else(|| chrono::Utc::now().date_naive());
 
-        // Set the Artist & Copyright EXIF tags
-        self.set_tag(ExifTag::Artist(data.author.name.to_owned()));
-        self.set_tag(ExifTag::Copyright(data.copyright(date)));
+        // Set the Artist & Copyright EXIF tags (optionally adding to the existing artists)
+        let mut tags = Tags::new(self, merge, FieldGroup::Author);
+        tags.set_artist(&data.author.name);
+        tags.set(ExifTag::Copyright(data.copyright(date)));
 
         // Success!
         Ok(())
     }
+
+    fn apply_scan_data(
+        &mut self,
+        data: &Scan,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
+        // Mark the image as originating from a film scanner
+        let mut tags = Tags::new(self, merge, FieldGroup::Scan);
+        tags.set(ExifTag::FileSource(vec![1u8]));
+
+        // Set digitization date/time, if available
+        if let Some(date) = data.date {
+            tags.set(ExifTag::CreateDate(
+                date.format("%Y:%m:%d %H:%M:%S").to_string(),
+            ));
+        }
+
+        // Set scanning software, if available
+        if let Some(software) = &data.software {
+            tags.set(ExifTag::Software(software.to_owned()));
+        }
+
+        // Success!
+        Ok(())
+    }
+}
+
+/// Read film-related metadata back from EXIF tags
+///
+/// This is the inverse of [`super::ApplyMetadata`], reading back the camera,
+/// lens, film, exposure, position and author tags where present. Fields not
+/// stored in EXIF (such as the path and license) are left empty.
+pub(super) fn read_details(exif: &little_exif::metadata::Metadata) -> super::Details {
+    use num_rational::Rational32;
+    use rust_decimal::Decimal;
+
+    let string = |tag: ExifTag| {
+        get_tag(exif, tag, |tag| match tag {
+            ExifTag::Make(s)
+            | ExifTag::Model(s)
+            | ExifTag::LensMake(s)
+            | ExifTag::LensModel(s)
+            | ExifTag::Artist(s)
+            | ExifTag::Copyright(s)
+            | ExifTag::UnknownSTRING(s, _, _) => Some(s.trim_end_matches('\0').to_owned()),
+            _ => None,
+        })
+        .filter(|s| !s.is_empty())
+    };
+    let rational = |tag: ExifTag| {
+        get_tag(exif, tag, |tag| match tag {
+            ExifTag::ExposureTime(v) | ExifTag::FNumber(v) | ExifTag::FocalLength(v) => {
+                v.first().cloned()
+            }
+            _ => None,
+        })
+        .filter(|v| v.denominator != 0)
+    };
+    let make_model = |make: Option<String>, model: Option<String>| match (make, model) {
+        (Some(make), Some(model)) if !model.starts_with(&make) => Some(format!("{make} {model}")),
+        (make, model) => model.or(make),
+    };
+
+    super::Details {
+        camera: string(ExifTag::UnknownSTRING(
+            String::new(),
+            0xc615,
+            ExifTagGroup::GENERIC,
+        ))
+        .or_else(|| {
+            make_model(
+                string(ExifTag::Make(String::new())),
+                string(ExifTag::Model(String::new())),
+            )
+        }),
+        lens: string(ExifTag::UnknownSTRING(
+            String::new(),
+            0xfdea,
+            ExifTagGroup::EXIF,
+        ))
+        .or_else(|| {
+            make_model(
+                string(ExifTag::LensMake(String::new())),
+                string(ExifTag::LensModel(String::new())),
+            )
+        }),
+        film: get_tag(exif, ExifTag::UserComment(vec![]), |tag| match tag {
+            ExifTag::UserComment(value) => from_exif_undef(value, exif.get_endian()),
+            _ => None,
+        }),
+        iso: get_tag(exif, ExifTag::ISOSpeed(vec![]), |tag| match tag {
+            ExifTag::ISOSpeed(v) => v.first().copied(),
+            _ => None,
+        })
+        .or_else(|| {
+            get_tag(exif, ExifTag::ISO(vec![]), |tag| match tag {
+                ExifTag::ISO(v) => v.first().map(|&iso| iso.into()),
+                _ => None,
+            })
+        }),
+        shutter_speed: rational(ExifTag::ExposureTime(vec![])).and_then(|v| {
+            let numer = i32::try_from(v.nominator).ok()?;
+            let denom = i32::try_from(v.denominator).ok()?;
+            Some(Rational32::new(numer, denom).into())
+        }),
+        aperture: rational(ExifTag::FNumber(vec![]))
+            .map(|v| (Decimal::from(v.nominator) / Decimal::from(v.denominator)).into()),
+        focal_length: rational(ExifTag::FocalLength(vec![])).map(|v| FocalLength {
+            real: Decimal::from(v.nominator) / Decimal::from(v.denominator),
+            equiv: get_tag(
+                exif,
+                ExifTag::FocalLengthIn35mmFormat(vec![]),
+                |tag| match tag {
+                    ExifTag::FocalLengthIn35mmFormat(v) => v.first().map(|&v| v.into()),
+                    _ => None,
+                },
+            ),
+        }),
+        position: get_latitude(exif)
+            .zip(get_longitude(exif))
+            .map(|(lat, lon)| Position { lat, lon }),
+        author: string(ExifTag::Artist(String::new())),
+        copyright: string(ExifTag::Copyright(String::new())),
+        ..Default::default()
+    }
+}
+
+/// Read all EXIF tags as a map from tag names to formatted values
+///
+/// The XMP packet is skipped, since it is compared property by property
+/// instead. Tags are named using [`tag_name`], and their values are formatted
+/// using [`format_value`].
+pub(super) fn read_fields(exif: &little_exif::metadata::Metadata) -> BTreeMap<String, String> {
+    let endian = exif.get_endian();
+    let mut fields = BTreeMap::new();
+    for tag in exif {
+        if (tag.get_group(), tag.as_u16()) == (ExifTagGroup::GENERIC, 0x02bc) {
+            continue;
+        }
+        let value = match tag {
+            ExifTag::UserComment(value) => {
+                from_exif_undef(value, exif.get_endian()).unwrap_or_default()
+            }
+            _ => format_value(tag, &endian),
+        };
+        fields.entry(tag_name(tag)).or_insert(value);
+    }
+    fields
+}
+
+/// Helper function for naming an EXIF tag
+///
+/// Tags are named after their hex value and group, using the names of the
+/// EXIF specification for known tags. Other tags are named by their group
+/// and hex value, e.g. `GPS 0x001B`.
+fn tag_name(tag: &ExifTag) -> String {
+    let name = match (tag.get_group(), tag.as_u16()) {
+        (ExifTagGroup::GENERIC, 0x0100) => "ImageWidth",
+        (ExifTagGroup::GENERIC, 0x0101) => "ImageHeight",
+        (ExifTagGroup::GENERIC, 0x0102) => "BitsPerSample",
+        (ExifTagGroup::GENERIC, 0x0103) => "Compression",
+        (ExifTagGroup::GENERIC, 0x0106) => "PhotometricInterpretation",
+        (ExifTagGroup::GENERIC, 0x010E) => "ImageDescription",
+        (ExifTagGroup::GENERIC, 0x010F) => "Make",
+        (ExifTagGroup::GENERIC, 0x0110) => "Model",
+        (ExifTagGroup::GENERIC, 0x0111) => "StripOffsets",
+        (ExifTagGroup::GENERIC, 0x0112) => "Orientation",
+        (ExifTagGroup::GENERIC, 0x0115) => "SamplesPerPixel",
+        (ExifTagGroup::GENERIC, 0x0116) => "RowsPerStrip",
+        (ExifTagGroup::GENERIC, 0x0117) => "StripByteCounts",
+        (ExifTagGroup::GENERIC, 0x011A) => "XResolution",
+        (ExifTagGroup::GENERIC, 0x011B) => "YResolution",
+        (ExifTagGroup::GENERIC, 0x011C) => "PlanarConfiguration",
+        (ExifTagGroup::GENERIC, 0x0128) => "ResolutionUnit",
+        (ExifTagGroup::GENERIC, 0x0131) => "Software",
+        (ExifTagGroup::GENERIC, 0x0132) => "ModifyDate",
+        (ExifTagGroup::GENERIC, 0x013B) => "Artist",
+        (ExifTagGroup::GENERIC, 0x8298) => "Copyright",
+        (ExifTagGroup::GENERIC, 0x8769) => "ExifOffset",
+        (ExifTagGroup::GENERIC, 0x8825) => "GPSInfo",
+        (ExifTagGroup::GENERIC, 0xC615) => "LocalizedCameraModel",
+        (ExifTagGroup::EXIF, 0x829A) => "ExposureTime",
+        (ExifTagGroup::EXIF, 0x829D) => "FNumber",
+        (ExifTagGroup::EXIF, 0x8822) => "ExposureProgram",
+        (ExifTagGroup::EXIF, 0x8827) => "ISO",
+        (ExifTagGroup::EXIF, 0x8830) => "SensitivityType",
+        (ExifTagGroup::EXIF, 0x8833) => "ISOSpeed",
+        (ExifTagGroup::EXIF, 0x9000) => "ExifVersion",
+        (ExifTagGroup::EXIF, 0x9003) => "DateTimeOriginal",
+        (ExifTagGroup::EXIF, 0x9004) => "CreateDate",
+        (ExifTagGroup::EXIF, 0x9201) => "ShutterSpeedValue",
+        (ExifTagGroup::EXIF, 0x9202) => "ApertureValue",
+        (ExifTagGroup::EXIF, 0x9204) => "ExposureCompensation",
+        (ExifTagGroup::EXIF, 0x9207) => "MeteringMode",
+        (ExifTagGroup::EXIF, 0x9208) => "LightSource",
+        (ExifTagGroup::EXIF, 0x9209) => "Flash",
+        (ExifTagGroup::EXIF, 0x920A) => "FocalLength",
+        (ExifTagGroup::EXIF, 0x9286) => "UserComment",
+        (ExifTagGroup::EXIF, 0xA300) => "FileSource",
+        (ExifTagGroup::EXIF, 0xA402) => "ExposureMode",
+        (ExifTagGroup::EXIF, 0xA405) => "FocalLengthIn35mmFormat",
+        (ExifTagGroup::EXIF, 0xA430) => "OwnerName",
+        (ExifTagGroup::EXIF, 0xA431) => "SerialNumber",
+        (ExifTagGroup::EXIF, 0xA433) => "LensMake",
+        (ExifTagGroup::EXIF, 0xA434) => "LensModel",
+        (ExifTagGroup::EXIF, 0xA435) => "LensSerialNumber",
+        (ExifTagGroup::EXIF, 0xFDEA) => "Lens",
+        (ExifTagGroup::GPS, 0x0001) => "GPSLatitudeRef",
+        (ExifTagGroup::GPS, 0x0002) => "GPSLatitude",
+        (ExifTagGroup::GPS, 0x0003) => "GPSLongitudeRef",
+        (ExifTagGroup::GPS, 0x0004) => "GPSLongitude",
+        (group, hex) => {
+            let group = match group {
+                ExifTagGroup::GENERIC => "IFD0",
+                ExifTagGroup::EXIF => "ExifIFD",
+                ExifTagGroup::INTEROP => "InteropIFD",
+                ExifTagGroup::GPS => "GPS",
+            };
+            return format!("{group} 0x{hex:04X}");
+        }
+    };
+    name.to_owned()
+}
+
+/// Helper function for formatting the value of an EXIF tag
+///
+/// Common tags are formatted readably: version strings as text, rational and
+/// APEX values as decimals (with the corresponding f-number or exposure time
+/// for APEX values), GPS coordinates as degrees, minutes & seconds, and
+/// enumerated values by name. Other tags, and values which can't be formatted
+/// readably, are formatted using [`format_raw`].
+fn format_value(tag: &ExifTag, endian: &little_exif::endian::Endian) -> String {
+    let unsigned = |v: &[uR64]| match v {
+        [v] => (v.denominator != 0).then(|| v.nominator as f64 / v.denominator as f64),
+        _ => None,
+    };
+    let signed = |v: &[iR64]| match v {
+        [v] => (v.denominator != 0).then(|| v.nominator as f64 / v.denominator as f64),
+        _ => None,
+    };
+    let readable = match tag {
+        ExifTag::ExifVersion(v) | ExifTag::FlashpixVersion(v) => (!v.is_empty()
+            && v.iter().all(u8::is_ascii_alphanumeric))
+        .then(|| String::from_utf8_lossy(v).into_owned()),
+        ExifTag::GPSVersionID(v) => {
+            (!v.is_empty()).then(|| v.iter().map(u8::to_string).collect::<Vec<_>>().join("."))
+        }
+        ExifTag::ExposureTime(v) => unsigned(v).map(format_seconds),
+        ExifTag::ShutterSpeedValue(v) => signed(v).map(|tv| {
+            let time = format_seconds((-tv).exp2());
+            format!("{} ({time})", format_decimal(tv, 4))
+        }),
+        ExifTag::FNumber(v) => unsigned(v).map(|n| format!("f/{}", format_decimal(n, 1))),
+        ExifTag::ApertureValue(v) | ExifTag::MaxApertureValue(v) => unsigned(v).map(|av| {
+            let n = format_decimal((av / 2.0).exp2(), 1);
+            format!("{} (f/{n})", format_decimal(av, 4))
+        }),
+        ExifTag::BrightnessValue(v) | ExifTag::ExposureCompensation(v) => {
+            signed(v).map(|value| format_decimal(value, 4))
+        }
+        ExifTag::FocalLength(v) => unsigned(v).map(|f| format!("{} mm", format_decimal(f, 4))),
+        ExifTag::SubjectDistance(v) | ExifTag::GPSAltitude(v) => {
+            unsigned(v).map(|d| format!("{} m", format_decimal(d, 4)))
+        }
+        ExifTag::XResolution(v) | ExifTag::YResolution(v) => {
+            unsigned(v).map(|value| format_decimal(value, 4))
+        }
+        ExifTag::GPSLatitude(v) | ExifTag::GPSLongitude(v) => match v.as_slice() {
+            [degrees, minutes, seconds] => unsigned(std::slice::from_ref(degrees))
+                .zip(unsigned(std::slice::from_ref(minutes)))
+                .zip(unsigned(std::slice::from_ref(seconds)))
+                .map(|((d, m), s)| {
+                    let (d, m, s) = (
+                        format_decimal(d, 4),
+                        format_decimal(m, 4),
+                        format_decimal(s, 2),
+                    );
+                    format!("{d}° {m}' {s}\"")
+                }),
+            _ => None,
+        },
+        ExifTag::Compression(v) => format_enum(
+            v,
+            &[
+                (1, "Uncompressed"),
+                (5, "LZW"),
+                (6, "JPEG (old-style)"),
+                (7, "JPEG"),
+                (8, "Adobe Deflate"),
+                (32773, "PackBits"),
+            ],
+        ),
+        ExifTag::PhotometricInterpretation(v) => format_enum(
+            v,
+            &[
+                (0, "WhiteIsZero"),
+                (1, "BlackIsZero"),
+                (2, "RGB"),
+                (3, "RGB Palette"),
+                (5, "CMYK"),
+                (6, "YCbCr"),
+                (8, "CIELab"),
+            ],
+        ),
+        ExifTag::Orientation(v) => format_enum(
+            v,
+            &[
+                (1, "Horizontal (normal)"),
+                (2, "Mirror horizontal"),
+                (3, "Rotate 180"),
+                (4, "Mirror vertical"),
+                (5, "Mirror horizontal and rotate 270 CW"),
+                (6, "Rotate 90 CW"),
+                (7, "Mirror horizontal and rotate 90 CW"),
+                (8, "Rotate 270 CW"),
+            ],
+        ),
+        ExifTag::PlanarConfiguration(v) => format_enum(v, &[(1, "Chunky"), (2, "Planar")]),
+        ExifTag::ResolutionUnit(v) => format_enum(v, &[(1, "None"), (2, "inches"), (3, "cm")]),
+        ExifTag::ExposureProgram(v) => format_enum(
+            v,
+            &[
+                (0, "Not defined"),
+                (1, "Manual"),
+                (2, "Program AE"),
+                (3, "Aperture-priority AE"),
+                (4, "Shutter speed priority AE"),
+                (5, "Creative (slow speed)"),
+                (6, "Action (high speed)"),
+                (7, "Portrait"),
+                (8, "Landscape"),
+            ],
+        ),
+        ExifTag::SensitivityType(v) => format_enum(
+            v,
+            &[
+                (0, "Unknown"),
+                (1, "Standard Output Sensitivity"),
+                (2, "Recommended Exposure Index"),
+                (3, "ISO Speed"),
+                (
+                    4,
+                    "Standard Output Sensitivity and Recommended Exposure Index",
+                ),
+                (5, "Standard Output Sensitivity and ISO Speed"),
+                (6, "Recommended Exposure Index and ISO Speed"),
+                (
+                    7,
+                    "Standard Output Sensitivity, Recommended Exposure Index and ISO Speed",
+                ),
+            ],
+        ),
+        ExifTag::MeteringMode(v) => format_enum(
+            v,
+            &[
+                (0, "Unknown"),
+                (1, "Average"),
+                (2, "Center-weighted average"),
+                (3, "Spot"),
+                (4, "Multi-spot"),
+                (5, "Multi-segment"),
+                (6, "Partial"),
+                (255, "Other"),
+            ],
+        ),
+        ExifTag::LightSource(v) => format_enum(
+            v,
+            &[
+                (0, "Unknown"),
+                (1, "Daylight"),
+                (2, "Fluorescent"),
+                (3, "Tungsten (Incandescent)"),
+                (4, "Flash"),
+                (9, "Fine Weather"),
+                (10, "Cloudy"),
+                (11, "Shade"),
+                (12, "Daylight Fluorescent"),
+                (13, "Day White Fluorescent"),
+                (14, "Cool White Fluorescent"),
+                (15, "White Fluorescent"),
+                (16, "Warm White Fluorescent"),
+                (17, "Standard Light A"),
+                (18, "Standard Light B"),
+                (19, "Standard Light C"),
+                (20, "D55"),
+                (21, "D65"),
+                (22, "D75"),
+                (23, "D50"),
+                (24, "ISO Studio Tungsten"),
+                (255, "Other"),
+            ],
+        ),
+        ExifTag::Flash(v) => match v.as_slice() {
+            [flash] => Some(format_flash(*flash)),
+            _ => None,
+        },
+        ExifTag::ColorSpace(v) => format_enum(
+            v,
+            &[(1, "sRGB"), (2, "Adobe RGB"), (0xffff, "Uncalibrated")],
+        ),
+        ExifTag::FileSource(v) => format_enum(
+            v,
+            &[
+                (0, "Others"),
+                (1, "Film Scanner"),
+                (2, "Reflection Print Scanner"),
+                (3, "Digital Camera"),
+            ],
+        ),
+        ExifTag::SceneType(v) => format_enum(v, &[(1, "Directly photographed")]),
+        ExifTag::ExposureMode(v) => {
+            format_enum(v, &[(0, "Auto"), (1, "Manual"), (2, "Auto bracket")])
+        }
+        ExifTag::WhiteBalance(v) => format_enum(v, &[(0, "Auto"), (1, "Manual")]),
+        _ => None,
+    };
+    readable.unwrap_or_else(|| format_raw(tag, endian))
+}
+
+/// Helper function for formatting a decimal value
+///
+/// The value is rounded to the given number of decimals, and printed without
+/// trailing zeros.
+fn format_decimal(value: f64, decimals: usize) -> String {
+    let value = format!("{value:.decimals$}");
+    let value = match value.contains('.') {
+        true => value.trim_end_matches('0').trim_end_matches('.'),
+        false => &value,
+    };
+    match value {
+        "-0" => "0".into(),
+        value => value.into(),
+    }
+}
+
+/// Helper function for formatting an exposure time
+///
+/// Exposure times shorter than a second are printed as fractions of a second,
+/// e.g. `1/500 s`, while longer exposure times are printed as decimals.
+fn format_seconds(time: f64) -> String {
+    match time {
+        time if time > 0.0 && time < 1.0 => format!("1/{} s", format_decimal(time.recip(), 0)),
+        time => format!("{} s", format_decimal(time, 4)),
+    }
+}
+
+/// Helper function for formatting an enumerated value by name
+///
+/// Returns `None` unless the value is a single element with a known name.
+fn format_enum<T: Copy + Into<u32>>(value: &[T], names: &[(u32, &str)]) -> Option<String> {
+    let [value] = value else {
+        return None;
+    };
+    let value = (*value).into();
+    names
+        .iter()
+        .find(|(known, _)| *known == value)
+        .map(|(_, name)| (*name).to_owned())
+}
+
+/// Helper function for formatting the value of the `Flash` tag
+///
+/// The value is a bit field, describing whether the flash fired and in which
+/// mode, as well as whether red-eye reduction was used.
+fn format_flash(flash: u16) -> String {
+    if flash & 0x20 != 0 {
+        return "No flash function".into();
+    }
+    let mode = match (flash >> 3) & 0b11 {
+        1 => Some("On"),
+        2 => Some("Off"),
+        3 => Some("Auto"),
+        _ => None,
+    };
+    let fired = match flash & 0x01 {
+        0 => Some("Did not fire"),
+        _ => Some("Fired"),
+    };
+    let red_eye = (flash & 0x40 != 0).then_some("Red-eye reduction");
+    [mode, fired, red_eye]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Helper function for formatting the raw value of an EXIF tag
+///
+/// The value is decoded according to the format of the tag. Rationals are
+/// printed as fractions, strings are printed without trailing NUL characters,
+/// and single-element arrays are printed without surrounding brackets.
+fn format_raw(tag: &ExifTag, endian: &little_exif::endian::Endian) -> String {
+    use little_exif::exif_tag_format::ExifTagFormat;
+    use little_exif::u8conversion::U8conversion;
+    fn decode<T: U8conversion<T>>(
+        bytes: &Vec<u8>,
+        endian: &little_exif::endian::Endian,
+        format: impl Fn(T) -> String,
+    ) -> Vec<String>
+    where
+        Vec<T>: U8conversion<Vec<T>>,
+    {
+        Vec::<T>::from_u8_vec(bytes, endian)
+            .into_iter()
+            .map(format)
+            .collect()
+    }
+    let bytes = tag.value_as_u8_vec(endian);
+    let values = match tag.format() {
+        ExifTagFormat::STRING => {
+            return String::from_u8_vec(&bytes, endian).replace('\0', "");
+        }
+        ExifTagFormat::INT8U | ExifTagFormat::UNDEF => {
+            decode(&bytes, endian, |v: u8| v.to_string())
+        }
+        ExifTagFormat::INT8S => decode(&bytes, endian, |v: i8| v.to_string()),
+        ExifTagFormat::INT16U => decode(&bytes, endian, |v: u16| v.to_string()),
+        ExifTagFormat::INT16S => decode(&bytes, endian, |v: i16| v.to_string()),
+        ExifTagFormat::INT32U => decode(&bytes, endian, |v: u32| v.to_string()),
+        ExifTagFormat::INT32S => decode(&bytes, endian, |v: i32| v.to_string()),
+        ExifTagFormat::FLOAT => decode(&bytes, endian, |v: f32| v.to_string()),
+        ExifTagFormat::DOUBLE => decode(&bytes, endian, |v: f64| v.to_string()),
+        ExifTagFormat::RATIONAL64U => decode(&bytes, endian, |v: uR64| {
+            format!("{}/{}", v.nominator, v.denominator)
+        }),
+        ExifTagFormat::RATIONAL64S => decode(&bytes, endian, |v: iR64| {
+            format!("{}/{}", v.nominator, v.denominator)
+        }),
+    };
+    match values.as_slice() {
+        [] => String::new(),
+        [value] => value.clone(),
+        values => format!("[{}]", values.join(", ")),
+    }
+}
+
+/// Tags of IFD0 describing the structure of the image data
+///
+/// These are never part of a metadata snapshot, since they have to match the
+/// image data actually stored in the file.
+const IMAGE_STRUCTURE: &[u16] = &[
+    0x00fe, // NewSubfileType
+    0x00ff, // SubfileType
+    0x0100, // ImageWidth
+    0x0101, // ImageLength
+    0x0102, // BitsPerSample
+    0x0103, // Compression
+    0x0106, // PhotometricInterpretation
+    0x0107, // Thresholding
+    0x010a, // FillOrder
+    0x0111, // StripOffsets
+    0x0115, // SamplesPerPixel
+    0x0116, // RowsPerStrip
+    0x0117, // StripByteCounts
+    0x0118, // MinSampleValue
+    0x0119, // MaxSampleValue
+    0x011a, // XResolution
+    0x011b, // YResolution
+    0x011c, // PlanarConfiguration
+    0x0128, // ResolutionUnit
+    0x013d, // Predictor
+    0x0140, // ColorMap
+    0x0142, // TileWidth
+    0x0143, // TileLength
+    0x0144, // TileOffsets
+    0x0145, // TileByteCounts
+    0x014a, // SubIFDs
+    0x0152, // ExtraSamples
+    0x0153, // SampleFormat
+    0x0201, // JPEGInterchangeFormat
+    0x0202, // JPEGInterchangeFormatLength
+    0x0211, // YCbCrCoefficients
+    0x0212, // YCbCrSubSampling
+    0x0213, // YCbCrPositioning
+    0x0214, // ReferenceBlackWhite
+    0x8773, // ICCProfile
+];
+
+/// Copy the descriptive metadata held by the given EXIF structure
+///
+/// The copy holds the value tags of IFD0 (except those describing the
+/// structure of the image data) and of its EXIF and GPS sub-IFDs, including
+/// the embedded XMP packet, but no image data.
+pub(super) fn copy_metadata(
+    exif: &little_exif::metadata::Metadata,
+) -> little_exif::metadata::Metadata {
+    let mut copy = little_exif::metadata::Metadata::new();
+    for (group, tag) in metadata_tags(exif) {
+        copy.get_ifd_mut(group, 0).set_tag(tag.clone());
+    }
+    copy
+}
+
+/// Replace the descriptive metadata held by the given EXIF structure
+///
+/// All tags which would be copied by [`copy_metadata`] are removed, and
+/// replaced by those held by `metadata`. Any image data is left untouched.
+pub(super) fn replace_metadata(
+    exif: &mut little_exif::metadata::Metadata,
+    metadata: &little_exif::metadata::Metadata,
+) {
+    let existing = metadata_tags(exif)
+        .map(|(group, tag)| (group, tag.clone()))
+        .collect::<Vec<_>>();
+    for (group, tag) in existing {
+        exif.get_ifd_mut(group, 0).remove_tag(&tag);
+    }
+    for (group, tag) in metadata_tags(metadata) {
+        exif.get_ifd_mut(group, 0).set_tag(tag.clone());
+    }
 }
 
-/// Helper function for setting the GPS latitude EXIF tags
-fn set_latitude(exif: &mut little_exif::metadata::Metadata, latitude: f64) {
-    use dms_coordinates::{Cardinal, DMS};
-    use num_traits::FromPrimitive;
-
-    let lat = DMS::from_ddeg_latitude(latitude);
-    exif.set_tag(ExifTag::GPSLatitude(vec![
-        uR64::from_rational(num_rational::Rational32::from_integer(lat.degrees.into())),
-        uR64::from_rational(num_rational::Rational32::from_integer(lat.minutes.into())),
-        uR64::from_rational(num_rational::Rational32::from_f64(lat.seconds).unwrap_or_default()),
-    ]));
-    match lat.cardinal {
-        Some(Cardinal::North) => exif.set_tag(ExifTag::GPSLatitudeRef("N".into())),
-        Some(Cardinal::South) => exif.set_tag(ExifTag::GPSLatitudeRef("S".into())),
-        _ => panic!("expected a valid latitude cardinal"),
+/// Iterate over the descriptive metadata tags of IFD0 and its sub-IFDs
+fn metadata_tags(
+    exif: &little_exif::metadata::Metadata,
+) -> impl Iterator<Item = (ExifTagGroup, &ExifTag)> {
+    use little_exif::exif_tag::TagType;
+    exif.get_ifds()
+        .iter()
+        .filter(|ifd| ifd.get_generic_ifd_nr() == 0)
+        .flat_map(|ifd| ifd.get_tags().iter().map(|tag| (ifd.get_ifd_type(), tag)))
+        .filter(|(group, tag)| match group {
+            ExifTagGroup::GENERIC => !IMAGE_STRUCTURE.contains(&tag.as_u16()),
+            ExifTagGroup::EXIF | ExifTagGroup::GPS => true,
+            ExifTagGroup::INTEROP => false,
+        })
+        .filter(|(_, tag)| matches!(tag.get_tag_type(), TagType::VALUE))
+}
+
+/// Helper function for reading a single EXIF tag
+fn get_tag<T>(
+    exif: &little_exif::metadata::Metadata,
+    tag: ExifTag,
+    value: impl FnOnce(&ExifTag) -> Option<T>,
+) -> Option<T> {
+    exif.get_tag(&tag).next().and_then(value)
+}
+
+/// Helper function for reading the GPS latitude EXIF tags
+fn get_latitude(exif: &little_exif::metadata::Metadata) -> Option<f64> {
+    let degrees = get_tag(exif, ExifTag::GPSLatitude(vec![]), |tag| match tag {
+        ExifTag::GPSLatitude(dms) => from_dms(dms),
+        _ => None,
+    })?;
+    match get_tag(
+        exif,
+        ExifTag::GPSLatitudeRef(String::new()),
+        |tag| match tag {
+            ExifTag::GPSLatitudeRef(cardinal) => Some(cardinal.trim_end_matches('\0') == "S"),
+            _ => None,
+        },
+    ) {
+        Some(true) => Some(-degrees),
+        _ => Some(degrees),
     }
 }
 
-/// Helper function for setting the GPS longitude EXIF tags
-fn set_longitude(exif: &mut little_exif::metadata::Metadata, longitude: f64) {
-    use dms_coordinates::{Cardinal, DMS};
-    use num_traits::FromPrimitive;
-
-    let lon = DMS::from_ddeg_longitude(longitude);
-    exif.set_tag(ExifTag::GPSLongitude(vec![
-        uR64::from_rational(num_rational::Rational32::from_integer(lon.degrees.into())),
-        uR64::from_rational(num_rational::Rational32::from_integer(lon.minutes.into())),
-        uR64::from_rational(num_rational::Rational32::from_f64(lon.seconds).unwrap_or_default()),
-    ]));
-    match lon.cardinal {
-        Some(Cardinal::East) => exif.set_tag(ExifTag::GPSLongitudeRef("E".into())),
-        Some(Cardinal::West) => exif.set_tag(ExifTag::GPSLongitudeRef("W".into())),
-        _ => panic!("expected a valid longitude cardinal"),
+/// Helper function for reading the GPS longitude EXIF tags
+fn get_longitude(exif: &little_exif::metadata::Metadata) -> Option<f64> {
+    let degrees = get_tag(exif, ExifTag::GPSLongitude(vec![]), |tag| match tag {
+        ExifTag::GPSLongitude(dms) => from_dms(dms),
+        _ => None,
+    })?;
+    match get_tag(
+        exif,
+        ExifTag::GPSLongitudeRef(String::new()),
+        |tag| match tag {
+            ExifTag::GPSLongitudeRef(cardinal) => Some(cardinal.trim_end_matches('\0') == "W"),
+            _ => None,
+        },
+    ) {
+        Some(true) => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+/// Convert EXIF degrees, minutes & seconds to decimal degrees
+fn from_dms(dms: &[uR64]) -> Option<f64> {
+    let [degrees, minutes, seconds] = dms else {
+        return None;
+    };
+    let value = |v: &uR64| (v.denominator != 0).then(|| v.nominator as f64 / v.denominator as f64);
+    Some(value(degrees)? + value(minutes)? / 60.0 + value(seconds)? / 3600.0)
+}
+
+/// Helper for setting the EXIF tags of a field group, honouring its merge mode
+struct Tags<'a> {
+    exif: &'a mut little_exif::metadata::Metadata,
+    merge: &'a mut Merger,
+    group: FieldGroup,
+}
+
+impl<'a> Tags<'a> {
+    fn new(
+        exif: &'a mut little_exif::metadata::Metadata,
+        merge: &'a mut Merger,
+        group: FieldGroup,
+    ) -> Self {
+        Self { exif, merge, group }
+    }
+
+    /// Set the given tag, unless the merge mode says otherwise
+    fn set(&mut self, tag: ExifTag) {
+        let field = format!("EXIF {:?} {:#06x}", tag.get_group(), tag.as_u16());
+        let present = self.exif.get_tag(&tag).next().is_some();
+        if self.merge.write(self.group, &field, present) {
+            self.exif.set_tag(tag);
+        }
+    }
+
+    /// Set the artist tag, optionally adding the artist to the existing ones
+    ///
+    /// Multiple artists are separated by semicolons, as recommended by the
+    /// EXIF specification; artists which are already listed are not repeated.
+    fn set_artist(&mut self, artist: &str) {
+        let existing = get_tag(self.exif, ExifTag::Artist(String::new()), |tag| match tag {
+            ExifTag::Artist(s) => Some(s.trim_end_matches('\0').to_owned()),
+            _ => None,
+        })
+        .unwrap_or_default();
+        self.set(ExifTag::Artist(match self.merge.mode(self.group) {
+            MergeMode::Append if existing.split(';').any(|name| name.trim() == artist) => existing,
+            MergeMode::Append if !existing.is_empty() => format!("{existing}; {artist}"),
+            _ => artist.to_owned(),
+        }));
+    }
+
+    /// Set the film name (user comment) tag
+    fn set_film(&mut self, film: &Film) {
+        let endian = self.exif.get_endian();
+        self.set(ExifTag::UserComment(to_exif_undef(
+            &film.to_string(),
+            endian,
+        )));
+    }
+
+    /// Set the film speed tags
+    fn set_film_speed(&mut self, speed: &FilmSpeed) {
+        let iso: i64 = speed.iso().as_rational().to_integer();
+        self.set(ExifTag::ISO(vec![
+            iso.clamp(u16::MIN as i64, u16::MAX as i64) as u16,
+        ]));
+        self.set(ExifTag::ISOSpeed(vec![
+            iso.clamp(u32::MIN as i64, u32::MAX as i64) as u32,
+        ]));
+        self.set(ExifTag::SensitivityType(vec![3u16])); // "ISO Speed"
+    }
+
+    /// Set the GPS latitude tags
+    fn set_latitude(&mut self, latitude: f64) {
+        use dms_coordinates::{Cardinal, DMS};
+        use num_traits::FromPrimitive;
+
+        let lat = DMS::from_ddeg_latitude(latitude);
+        self.set(ExifTag::GPSLatitude(vec![
+            uR64::from_rational(num_rational::Rational32::from_integer(lat.degrees.into())),
+            uR64::from_rational(num_rational::Rational32::from_integer(lat.minutes.into())),
+            uR64::from_rational(
+                num_rational::Rational32::from_f64(lat.seconds).unwrap_or_default(),
+            ),
+        ]));
+        match lat.cardinal {
+            Some(Cardinal::North) => self.set(ExifTag::GPSLatitudeRef("N".into())),
+            Some(Cardinal::South) => self.set(ExifTag::GPSLatitudeRef("S".into())),
+            _ => panic!("expected a valid latitude cardinal"),
+        }
+    }
+
+    /// Set the GPS longitude tags
+    fn set_longitude(&mut self, longitude: f64) {
+        use dms_coordinates::{Cardinal, DMS};
+        use num_traits::FromPrimitive;
+
+        let lon = DMS::from_ddeg_longitude(longitude);
+        self.set(ExifTag::GPSLongitude(vec![
+            uR64::from_rational(num_rational::Rational32::from_integer(lon.degrees.into())),
+            uR64::from_rational(num_rational::Rational32::from_integer(lon.minutes.into())),
+            uR64::from_rational(
+                num_rational::Rational32::from_f64(lon.seconds).unwrap_or_default(),
+            ),
+        ]));
+        match lon.cardinal {
+            Some(Cardinal::East) => self.set(ExifTag::GPSLongitudeRef("E".into())),
+            Some(Cardinal::West) => self.set(ExifTag::GPSLongitudeRef("W".into())),
+            _ => panic!("expected a valid longitude cardinal"),
+        }
     }
 }
 
@@ -274,9 +1080,30 @@ fn to_exif_undef(
     bytes
 }
 
+/// Convert an EXIF UNDEF value (with a character code prefix) to a string
+fn from_exif_undef(value: &[u8], endian: little_exif::endian::Endian) -> Option<String> {
+    let (code, data) = value.split_at_checked(8)?;
+    let value = match code {
+        b"ASCII\0\0\0" | b"\0\0\0\0\0\0\0\0" => data.iter().map(|&b| b as char).collect(),
+        b"UNICODE\0" => {
+            let units = data.chunks_exact(2).map(|pair| match endian {
+                little_exif::endian::Endian::Big => u16::from_be_bytes([pair[0], pair[1]]),
+                little_exif::endian::Endian::Little => u16::from_le_bytes([pair[0], pair[1]]),
+            });
+            char::decode_utf16(units.skip_while(|&unit| unit == 0xFEFF))
+                .collect::<Result<String, _>>()
+                .ok()?
+        }
+        _ => return None,
+    };
+    let value = value.trim_end_matches('\0').trim();
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merge::{MergePolicy, Merger};
     use crate::metadata::*;
     use crate::negative::ApplyMetadata;
     use crate::rolls::*;
@@ -343,22 +1170,174 @@ mod tests {
         );
     }
 
+    #[test]
+    fn exif_undef_decoding() {
+        use little_exif::endian::Endian;
+        for endian in [Endian::Little, Endian::Big] {
+            for value in ["hello", "həˈləʊ"] {
+                let encoded = to_exif_undef(value, endian.clone());
+                assert_eq!(
+                    from_exif_undef(&encoded, endian.clone()).as_deref(),
+                    Some(value)
+                );
+            }
+        }
+        assert_eq!(
+            from_exif_undef(b"ASCII\x00\x00\x00\x00", Endian::Little),
+            None
+        );
+        assert_eq!(
+            from_exif_undef(b"JIS\x00\x00\x00\x00\x00", Endian::Little),
+            None
+        );
+    }
+
+    #[test]
+    fn format_values() {
+        use little_exif::endian::Endian;
+        let format = |tag| format_value(&tag, &Endian::Little);
+        assert_eq!(format(ExifTag::Model("Bessa R2M\0".into())), "Bessa R2M");
+        assert_eq!(format(ExifTag::ISO(vec![3])), "3");
+        assert_eq!(format(ExifTag::ExifVersion(b"0231".into())), "0231");
+        assert_eq!(format(ExifTag::ExifVersion(vec![0, 2])), "[0, 2]");
+        assert_eq!(format(ExifTag::GPSVersionID(vec![2, 3, 0, 0])), "2.3.0.0");
+        assert_eq!(
+            format(ExifTag::ExposureTime(vec![uR64 {
+                nominator: 1,
+                denominator: 500
+            }])),
+            "1/500 s"
+        );
+        assert_eq!(
+            format(ExifTag::ExposureTime(vec![uR64 {
+                nominator: 5,
+                denominator: 2
+            }])),
+            "2.5 s"
+        );
+        assert_eq!(
+            format(ExifTag::ShutterSpeedValue(vec![iR64 {
+                nominator: 442653645,
+                denominator: 49371436
+            }])),
+            "8.9658 (1/500 s)"
+        );
+        assert_eq!(
+            format(ExifTag::FNumber(vec![uR64 {
+                nominator: 28,
+                denominator: 5
+            }])),
+            "f/5.6"
+        );
+        assert_eq!(
+            format(ExifTag::ApertureValue(vec![uR64 {
+                nominator: 337910176,
+                denominator: 67978299
+            }])),
+            "4.9709 (f/5.6)"
+        );
+        assert_eq!(
+            format(ExifTag::ExposureCompensation(vec![iR64 {
+                nominator: -2,
+                denominator: 3
+            }])),
+            "-0.6667"
+        );
+        assert_eq!(
+            format(ExifTag::GPSLatitude(vec![
+                uR64 {
+                    nominator: 57,
+                    denominator: 1
+                },
+                uR64 {
+                    nominator: 42,
+                    denominator: 1
+                },
+                uR64 {
+                    nominator: 331309670,
+                    denominator: 119987567
+                }
+            ])),
+            "57° 42' 2.76\""
+        );
+        assert_eq!(format(ExifTag::ExposureMode(vec![1])), "Manual");
+        assert_eq!(
+            format(ExifTag::ExposureProgram(vec![3])),
+            "Aperture-priority AE"
+        );
+        assert_eq!(format(ExifTag::FileSource(vec![1])), "Film Scanner");
+        assert_eq!(format(ExifTag::Flash(vec![0x10])), "Off, Did not fire");
+        assert_eq!(format(ExifTag::Flash(vec![0x20])), "No flash function");
+        // Unknown values of known tags are printed raw
+        assert_eq!(format(ExifTag::ExposureMode(vec![42])), "42");
+        assert_eq!(
+            format(ExifTag::FNumber(vec![uR64 {
+                nominator: 1,
+                denominator: 0
+            }])),
+            "1/0"
+        );
+        assert_eq!(
+            format(ExifTag::UnknownRATIONAL64S(
+                vec![
+                    iR64 {
+                        nominator: -1,
+                        denominator: 3
+                    },
+                    iR64 {
+                        nominator: 2,
+                        denominator: 1
+                    }
+                ],
+                0x1234,
+                ExifTagGroup::EXIF,
+            )),
+            "[-1/3, 2/1]"
+        );
+    }
+
+    #[test]
+    fn tag_names() {
+        assert_eq!(tag_name(&ExifTag::Model(String::new())), "Model");
+        assert_eq!(tag_name(&ExifTag::GPSLatitude(vec![])), "GPSLatitude");
+        assert_eq!(
+            tag_name(&ExifTag::UnknownSTRING(
+                String::new(),
+                0xfdea,
+                ExifTagGroup::EXIF
+            )),
+            "Lens"
+        );
+        assert_eq!(
+            tag_name(&ExifTag::UnknownSTRING(
+                String::new(),
+                0x001b,
+                ExifTagGroup::GPS
+            )),
+            "GPS 0x001B"
+        );
+    }
+
     #[test]
     fn apply_roll_data() {
         let mut exif = little_exif::metadata::Metadata::new();
         let roll = Roll {
             id: "A1234".into(),
+            sheet_film: false,
             film: Some(Film("Ilford Delta 100".into())),
             speed: FilmSpeed::from_din(21),
             camera: Some(Camera::MakeModel {
                 make: "Voigtländer".into(),
                 model: "Bessa R2M".into(),
             }),
+            camera_serial: Some("01234567".into()),
+            camera_owner: Some("Simon Sigurdhsson".into()),
             load: chrono::NaiveDateTime::MIN,
             unload: chrono::NaiveDateTime::MAX,
+            development: None,
             frames: vec![],
         };
-        exif.apply_roll_data(&roll)
+        exif.apply_roll_data(&roll, &mut Merger::default())
             .expect("roll data should be applicable as EXIF");
 
         assert_eq!(
@@ -389,6 +1368,14 @@ mod tests {
             exif.get_tag(&ExifTag::Model(String::new())).next(),
             Some(ExifTag::Model("Bessa R2M".into())).as_ref()
         );
+        assert_eq!(
+            exif.get_tag(&ExifTag::SerialNumber(String::new())).next(),
+            Some(ExifTag::SerialNumber("01234567".into())).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::OwnerName(String::new())).next(),
+            Some(ExifTag::OwnerName("Simon Sigurdhsson".into())).as_ref()
+        );
         assert_eq!(
             exif.get_tag(&ExifTag::UserComment(vec![])).next(),
             roll.film
@@ -416,10 +1403,14 @@ mod tests {
         let datetime = chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
             .and_then(|date| date.and_hms_opt(12, 15, 00));
         let frame = Frame {
+            film: None,
+            speed: None,
+            development: None,
             lens: Some(Lens::MakeModel {
                 make: "Voigtländer".into(),
                 model: "Color Skopar 35/2.5 Pancake II".into(),
             }),
+            lens_serial: Some("7654321".into()),
             aperture: Some(Aperture::Manual(dec!(2.5))),
             shutter_speed: Some(ShutterSpeed::Manual(Ratio::new(1, 125))),
             focal_length: Some(FocalLength {
@@ -427,11 +1418,17 @@ mod tests {
                 equiv: Some(dec!(35)),
             }),
             compensation: Some(ExposureBias(Ratio::new(-1, 3))),
+            flash: Some(Flash {
+                fired: true,
+                mode: Some(FlashMode::Compulsory),
+            }),
+            metering: Some(MeteringMode::Incident),
+            light_source: Some(LightSource::Cloudy),
             datetime: datetime.unwrap(),
             position: Position { lat: 0.0, lon: 0.0 },
             note: None,
         };
-        exif.apply_frame_data(&frame)
+        exif.apply_frame_data(&frame, &mut Merger::default())
             .expect("frame data should be applicable as EXIF");
 
         assert_eq!(
@@ -464,6 +1461,11 @@ mod tests {
             exif.get_tag(&ExifTag::LensModel(String::new())).next(),
             Some(ExifTag::LensModel("Color Skopar 35/2.5 Pancake II".into())).as_ref()
         );
+        assert_eq!(
+            exif.get_tag(&ExifTag::LensSerialNumber(String::new()))
+                .next(),
+            Some(ExifTag::LensSerialNumber("7654321".into())).as_ref()
+        );
         assert_eq!(
             exif.get_tag(&ExifTag::FocalLength(vec![])).next(),
             Some(ExifTag::FocalLength(vec![35.into()])).as_ref()
@@ -501,6 +1503,22 @@ mod tests {
             exif.get_tag(&ExifTag::ExposureProgram(vec![])).next(),
             Some(ExifTag::ExposureProgram(vec![1u16])).as_ref()
         );
+        assert_eq!(
+            exif.get_tag(&ExifTag::ExposureMode(vec![])).next(),
+            Some(ExifTag::ExposureMode(vec![1u16])).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::Flash(vec![])).next(),
+            Some(ExifTag::Flash(vec![0x09u16])).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::MeteringMode(vec![])).next(),
+            Some(ExifTag::MeteringMode(vec![255u16])).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::LightSource(vec![])).next(),
+            Some(ExifTag::LightSource(vec![10u16])).as_ref()
+        );
         assert_eq!(
             exif.get_tag(&ExifTag::ExposureCompensation(vec![])).next(),
             Some(ExifTag::ExposureCompensation(vec![iR64 {
@@ -528,6 +1546,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_frame_data_film_override() {
+        let mut exif = little_exif::metadata::Metadata::new();
+        let frame = Frame {
+            film: Some(Film("Ilford FP4 Plus".into())),
+            speed: Some(FilmSpeed::from_din(22)),
+            development: None,
+            lens: None,
+            lens_serial: None,
+            aperture: None,
+            shutter_speed: None,
+            focal_length: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: chrono::NaiveDateTime::default(),
+            position: Position { lat: 0.0, lon: 0.0 },
+            note: None,
+        };
+        exif.apply_frame_data(&frame, &mut Merger::default())
+            .expect("frame data should be applicable as EXIF");
+
+        assert_eq!(
+            exif.get_tag(&ExifTag::UserComment(vec![])).next(),
+            Some(ExifTag::UserComment(to_exif_undef(
+                "Ilford FP4 Plus",
+                exif.get_endian()
+            )))
+            .as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::ISO(vec![])).next(),
+            Some(ExifTag::ISO(vec![125u16])).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::ISOSpeed(vec![])).next(),
+            Some(ExifTag::ISOSpeed(vec![125u32])).as_ref()
+        );
+    }
+
     #[test]
     fn apply_author_data() {
         let mut exif = little_exif::metadata::Metadata::new();
@@ -538,8 +1597,9 @@ mod tests {
                 url: None,
             },
             license: None,
+            scan: None,
         };
-        exif.apply_author_data(&metadata, &datetime)
+        exif.apply_author_data(&metadata, &datetime, &mut Merger::default())
             .expect("author/license data should be applicable as EXIF");
 
         assert_eq!(
@@ -551,4 +1611,192 @@ mod tests {
             Some(ExifTag::Copyright(metadata.copyright(datetime.unwrap()))).as_ref()
         );
     }
+
+    #[test]
+    fn apply_author_data_append() {
+        let mut exif = little_exif::metadata::Metadata::new();
+        exif.set_tag(ExifTag::Artist("Existing Author".into()));
+        let metadata = Metadata {
+            author: Author {
+                name: "Simon Sigurdhsson".into(),
+                url: None,
+            },
+            license: None,
+            scan: None,
+        };
+        let policy = MergePolicy::default()
+            .with_rules(["author=append".parse().expect("merge rule should be valid")]);
+
+        // Applying the same author twice adds it only once
+        for _ in 0..2 {
+            exif.apply_author_data(&metadata, &None, &mut Merger::new(&policy))
+                .expect("author/license data should be applicable as EXIF");
+        }
+
+        assert_eq!(
+            exif.get_tag(&ExifTag::Artist(String::new())).next(),
+            Some(ExifTag::Artist("Existing Author; Simon Sigurdhsson".into())).as_ref()
+        );
+    }
+
+    #[test]
+    fn apply_frame_data_keep_location() {
+        let mut exif = little_exif::metadata::Metadata::new();
+        let latitude = vec![57.into(), 42.into(), 0.into()];
+        exif.set_tag(ExifTag::GPSLatitude(latitude.clone()));
+        exif.set_tag(ExifTag::GPSLatitudeRef("N".into()));
+        let frame = Frame {
+            position: Position {
+                lat: -33.9,
+                lon: 18.4,
+            },
+            ..crate::rolls::fixtures::frame("2016-05-13T14:12:40")
+        };
+        let policy = MergePolicy::default()
+            .with_rules(["location=keep".parse().expect("merge rule should be valid")]);
+        exif.apply_frame_data(&frame, &mut Merger::new(&policy))
+            .expect("frame data should be applicable as EXIF");
+
+        // Existing GPS tags are untouched, and no new ones are added
+        assert_eq!(
+            exif.get_tag(&ExifTag::GPSLatitude(vec![])).next(),
+            Some(ExifTag::GPSLatitude(latitude)).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::GPSLatitudeRef(String::new())).next(),
+            Some(ExifTag::GPSLatitudeRef("N".into())).as_ref()
+        );
+        assert_eq!(exif.get_tag(&ExifTag::GPSLongitude(vec![])).next(), None);
+        assert_eq!(
+            exif.get_tag(&ExifTag::GPSLongitudeRef(String::new()))
+                .next(),
+            None
+        );
+
+        // Other field groups are still written
+        assert_eq!(
+            exif.get_tag(&ExifTag::DateTimeOriginal(String::new()))
+                .next(),
+            Some(ExifTag::DateTimeOriginal("2016:05:13 14:12:40".into())).as_ref()
+        );
+    }
+
+    #[test]
+    fn apply_scan_data() {
+        let mut exif = little_exif::metadata::Metadata::new();
+        exif.set_tag(ExifTag::Make("Voigtländer".into()));
+        exif.set_tag(ExifTag::Model("Bessa R2M".into()));
+        let scan = Scan {
+            scanner: Some("Epson Perfection V850 Pro".into()),
+            software: Some("SilverFast 9".into()),
+            resolution: Some(3200),
+            date: chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+                .and_then(|date| date.and_hms_opt(12, 15, 00)),
+            holder: None,
+        };
+        exif.apply_scan_data(&scan, &mut Merger::default())
+            .expect("scan data should be applicable as EXIF");
+
+        assert_eq!(
+            exif.get_tag(&ExifTag::FileSource(vec![])).next(),
+            Some(ExifTag::FileSource(vec![1])).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::CreateDate(String::new())).next(),
+            Some(ExifTag::CreateDate("2025:06:01 12:15:00".into())).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::Software(String::new())).next(),
+            Some(ExifTag::Software("SilverFast 9".into())).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::Make(String::new())).next(),
+            Some(ExifTag::Make("Voigtländer".into())).as_ref()
+        );
+        assert_eq!(
+            exif.get_tag(&ExifTag::Model(String::new())).next(),
+            Some(ExifTag::Model("Bessa R2M".into())).as_ref()
+        );
+    }
+
+    #[test]
+    fn read_details_roundtrip() {
+        let mut exif = little_exif::metadata::Metadata::new();
+        assert_eq!(read_details(&exif), Default::default());
+
+        let roll = Roll {
+            id: "A1234".into(),
+            sheet_film: false,
+            film: Some(Film("Ilford Delta 100".into())),
+            speed: FilmSpeed::from_din(21),
+            camera: Some(Camera::MakeModel {
+                make: "Voigtländer".into(),
+                model: "Bessa R2M".into(),
+            }),
+            camera_serial: None,
+            camera_owner: None,
+            load: chrono::NaiveDateTime::MIN,
+            unload: chrono::NaiveDateTime::MAX,
+            development: None,
+            frames: vec![],
+        };
+        let frame = Frame {
+            film: None,
+            speed: None,
+            development: None,
+            lens: Some(Lens::MakeModel {
+                make: "Voigtländer".into(),
+                model: "Color Skopar 35/2.5 Pancake II".into(),
+            }),
+            lens_serial: None,
+            aperture: Some(Aperture::Manual(dec!(5.6))),
+            shutter_speed: Some(ShutterSpeed::Manual(Ratio::new(1, 125))),
+            focal_length: Some(FocalLength {
+                real: dec!(35),
+                equiv: Some(dec!(35)),
+            }),
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: chrono::NaiveDateTime::default(),
+            position: Position {
+                lat: 57.75,
+                lon: -11.5,
+            },
+            note: None,
+        };
+        let metadata = Metadata {
+            author: Author {
+                name: "Simon Sigurdhsson".into(),
+                url: None,
+            },
+            license: None,
+            scan: None,
+        };
+        exif.apply_roll_data(&roll, &mut Merger::default())
+            .expect("roll data should be applicable as EXIF");
+        exif.apply_frame_data(&frame, &mut Merger::default())
+            .expect("frame data should be applicable as EXIF");
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 6, 1);
+        exif.apply_author_data(&metadata, &date, &mut Merger::default())
+            .expect("author/license data should be applicable as EXIF");
+
+        let details = read_details(&exif);
+        assert_eq!(details.camera.as_deref(), Some("Voigtländer Bessa R2M"));
+        assert_eq!(
+            details.lens.as_deref(),
+            Some("Voigtländer Color Skopar 35/2.5 Pancake II")
+        );
+        assert_eq!(details.film.as_deref(), Some("Ilford Delta 100"));
+        assert_eq!(details.iso, Some(100));
+        assert_eq!(details.aperture, frame.aperture);
+        assert_eq!(details.shutter_speed, frame.shutter_speed);
+        assert_eq!(details.focal_length, frame.focal_length);
+        let position = details.position.expect("position should be read back");
+        assert!((position.lat - frame.position.lat).abs() < 1e-6);
+        assert!((position.lon - frame.position.lon).abs() < 1e-6);
+        assert_eq!(details.author.as_deref(), Some("Simon Sigurdhsson"));
+        assert_eq!(details.copyright, Some(metadata.copyright(date.unwrap())));
+    }
 }
@@ -0,0 +1,192 @@
+//! Crash-safe replacement of on-disk images
+//!
+//! Images are never modified in place. Instead, the new contents are written
+//! to a temporary file in the same directory, which is given the owner,
+//! permissions and extended attributes of the original, synced to disk, and
+//! finally renamed over the original. A crash or full disk at any point thus
+//! leaves either the original or the new file in place, never a truncated one.
+use std::io::Write;
+use std::path::Path;
+
+/// Atomically replace the contents of the file at the given path
+///
+/// Symbolic links are followed, such that the file they point to is replaced
+/// rather than the link itself.
+pub(super) fn replace(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let path = std::fs::canonicalize(path)?;
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!(".{name}."))
+        .suffix(".tmp")
+        .tempfile_in(dir)?;
+    file.write_all(data)?;
+    let metadata = std::fs::metadata(&path)?;
+    copy_owner(&metadata, file.as_file())?;
+    file.as_file().set_permissions(metadata.permissions())?;
+    copy_xattrs(&path, file.as_file())?;
+    file.as_file().sync_all()?;
+    file.persist(&path).map_err(|error| error.error)?;
+    sync_dir(dir)
+}
+
+/// Copy all extended attributes of the given path to the given file
+///
+/// File systems which don't support extended attributes are treated as having
+/// none. Failing to copy any attribute is an error naming the attribute, since
+/// it would otherwise be lost when replacing the original.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn copy_xattrs(path: &Path, file: &std::fs::File) -> std::io::Result<()> {
+    use rustix::fs::{fsetxattr, getxattr, listxattr, XattrFlags};
+    let list = || -> rustix::io::Result<Vec<u8>> {
+        let mut names = vec![0; listxattr(path, &mut [0u8; 0])?];
+        let len = listxattr(path, &mut names[..])?;
+        names.truncate(len);
+        Ok(names)
+    };
+    let copy = |name: &[u8]| -> rustix::io::Result<()> {
+        let mut value = vec![0; getxattr(path, name, &mut [0u8; 0])?];
+        let len = getxattr(path, name, &mut value[..])?;
+        fsetxattr(file, name, &value[..len], XattrFlags::empty())
+    };
+    for name in xattr_names(list())? {
+        copy(&name).map_err(|error| {
+            let name = String::from_utf8_lossy(&name);
+            std::io::Error::new(
+                std::io::Error::from(error).kind(),
+                format!("Unable to copy extended attribute `{name}`: {error}"),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Split a list of extended attribute names, as returned by `listxattr`
+///
+/// A file system without support for extended attributes has none.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn xattr_names(list: rustix::io::Result<Vec<u8>>) -> std::io::Result<Vec<Vec<u8>>> {
+    let list = match list {
+        Err(rustix::io::Errno::NOTSUP) => return Ok(vec![]),
+        list => list?,
+    };
+    Ok(list
+        .split(|&c| c == 0)
+        .filter(|name| !name.is_empty())
+        .map(<[u8]>::to_vec)
+        .collect())
+}
+
+/// Copy all extended attributes of the given path to the given file
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn copy_xattrs(_path: &Path, _file: &std::fs::File) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Give the given file the owner & group of the original file
+///
+/// This is only attempted if they differ, and fails if not permitted, since
+/// the original would otherwise change owner when replaced.
+#[cfg(unix)]
+fn copy_owner(original: &std::fs::Metadata, file: &std::fs::File) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    let current = file.metadata()?;
+    if (current.uid(), current.gid()) == (original.uid(), original.gid()) {
+        return Ok(());
+    }
+    std::os::unix::fs::fchown(file, Some(original.uid()), Some(original.gid())).map_err(|error| {
+        std::io::Error::new(
+            error.kind(),
+            format!("Unable to keep the owner & group of the original: {error}"),
+        )
+    })
+}
+
+/// Give the given file the owner & group of the original file
+#[cfg(not(unix))]
+fn copy_owner(_original: &std::fs::Metadata, _file: &std::fs::File) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Sync the given directory, making a preceding rename durable
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Sync the given directory, making a preceding rename durable
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn replace_file() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("image.tiff");
+        std::fs::write(&path, b"original")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640))?;
+        }
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        let xattrs = rustix::fs::setxattr(
+            &path,
+            "user.filmrolls",
+            b"test",
+            rustix::fs::XattrFlags::empty(),
+        )
+        .is_ok();
+        #[cfg(unix)]
+        let chowned = std::os::unix::fs::chown(&path, Some(65534), Some(65534)).is_ok();
+
+        // The contents are replaced, keeping owner, permissions & attributes
+        replace(&path, b"replaced")?;
+        assert_eq!(std::fs::read(&path)?, b"replaced");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+            let metadata = std::fs::metadata(&path)?;
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+            if chowned {
+                assert_eq!((metadata.uid(), metadata.gid()), (65534, 65534));
+            }
+        }
+        #[cfg(any(target_os = "linux", target_os = "macos"))]
+        if xattrs {
+            let mut value = [0u8; 4];
+            rustix::fs::getxattr(&path, "user.filmrolls", &mut value)?;
+            assert_eq!(&value, b"test");
+        }
+
+        // No temporary files are left behind, and missing files are not created
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+        assert!(replace(&dir.path().join("missing.tiff"), b"data").is_err());
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn list_xattrs() {
+        use rustix::io::Errno;
+        assert_eq!(
+            xattr_names(Ok(b"user.a\0user.b\0".to_vec())).unwrap(),
+            [b"user.a".to_vec(), b"user.b".to_vec()]
+        );
+
+        // Only unsupported file systems are treated as having no attributes
+        assert_eq!(
+            xattr_names(Err(Errno::NOTSUP)).unwrap(),
+            Vec::<Vec<u8>>::new()
+        );
+        assert!(xattr_names(Err(Errno::PERM)).is_err());
+        assert!(xattr_names(Err(Errno::ACCESS)).is_err());
+    }
+}
@@ -1,27 +1,285 @@
 //! Implements [`super::ApplyMetadata`] for [xmp_toolkit]
 //!
 //! [xmp_toolkit]: https://docs.rs/xmp_toolkit/latest/xmp_toolkit/
-use xmp_toolkit::xmp_ns::{DC, PHOTOSHOP, XMP_RIGHTS};
-use xmp_toolkit::XmpValue;
+use std::collections::BTreeMap;
+use std::path::Path;
 
-use crate::metadata::{License, Metadata};
+use xmp_toolkit::xmp_ns::{DC, EXIF, PHOTOSHOP, RESOURCE_REF, XMP, XMP_MM, XMP_RIGHTS};
+use xmp_toolkit::{IterOptions, XmpDate, XmpDateTime, XmpMeta, XmpResult, XmpValue};
+
+use crate::development::Development;
+use crate::merge::{FieldGroup, MergeMode, Merger};
+use crate::metadata::{License, Metadata, Scan};
 use crate::rolls::{Frame, Roll};
 
 /// Creative commons XMP namespace
 const CC: &str = "http://creativecommons.org/ns#";
 
-impl super::ApplyMetadata for xmp_toolkit::XmpMeta {
-    fn apply_roll_data(&mut self, _data: &Roll) -> Result<(), super::NegativeError> {
+/// EXIF 2.3 (CIPA) XMP namespace
+const EXIF_EX: &str = "http://cipa.jp/exif/1.0/";
+
+/// Film development XMP namespace
+const FILMDEV: &str = "https://github.com/urdh/filmrolls-rs/ns/development/1.0/";
+
+/// Film scanning XMP namespace
+const FILMSCAN: &str = "https://github.com/urdh/filmrolls-rs/ns/scan/1.0/";
+
+/// Tagging provenance XMP namespace
+const FILMROLLS: &str = "https://github.com/urdh/filmrolls-rs/ns/1.0/";
+
+/// Properties in the film development XMP namespace
+const FILMDEV_PROPERTIES: [&str; 8] = [
+    "Developer",
+    "Dilution",
+    "Time",
+    "Temperature",
+    "Agitation",
+    "PushPull",
+    "Lab",
+    "Date",
+];
+
+/// Read all XMP properties as a map from property paths to values
+pub(super) fn read_fields(xmp: &XmpMeta) -> BTreeMap<String, String> {
+    let options = IterOptions::default().leaf_nodes_only().omit_qualifiers();
+    xmp.iter(options)
+        .filter(|property| !property.name.is_empty())
+        .map(|property| (property.name, property.value.value))
+        .collect()
+}
+
+/// Read the license of a negative from XMP, if any
+///
+/// This is the Creative Commons license URL if available, or the usage terms
+/// otherwise.
+pub(super) fn read_license(xmp: &XmpMeta) -> Option<String> {
+    xmp.property(CC, "license")
+        .map(|value| value.value)
+        .or_else(|| {
+            xmp.localized_text(XMP_RIGHTS, "UsageTerms", None, "x-default")
+                .map(|(value, _)| value.value)
+        })
+}
+
+/// Read the roll ID & frame number recorded when tagging a negative, if any
+pub(super) fn read_provenance(xmp: &XmpMeta) -> (Option<String>, Option<usize>) {
+    let roll = xmp.property(FILMROLLS, "Roll").map(|value| value.value);
+    let frame = xmp
+        .property_i32(FILMROLLS, "Frame")
+        .and_then(|value| value.value.try_into().ok());
+    (roll, frame)
+}
+
+/// Record the provenance of the metadata written when tagging a negative
+///
+/// Any previously recorded provenance is replaced, since it describes
+/// metadata which has now been overwritten. The source is recorded as an
+/// absolute path, and left out if the roll data was read from standard input.
+pub(super) fn set_provenance(xmp: &mut XmpMeta, data: &super::Provenance) -> XmpResult<()> {
+    let _ = XmpMeta::register_namespace(FILMROLLS, "filmrolls")?;
+    for name in [
+        "Roll",
+        "Frame",
+        "Source",
+        "Tool",
+        "Timestamp",
+        "Load",
+        "Unload",
+    ] {
+        xmp.delete_property(FILMROLLS, name)?;
+    }
+    xmp.set_property(FILMROLLS, "Roll", &XmpValue::new(data.roll.clone()))?;
+    if let Some(frame) = data.frame {
+        let frame = XmpValue::new(frame.try_into().unwrap_or(i32::MAX));
+        xmp.set_property_i32(FILMROLLS, "Frame", &frame)?;
+    }
+    if let Some(source) = data
+        .source
+        .as_deref()
+        .filter(|&path| path != Path::new("-"))
+    {
+        let source = std::path::absolute(source).unwrap_or_else(|_| source.to_owned());
+        let source = XmpValue::new(source.display().to_string());
+        xmp.set_property(FILMROLLS, "Source", &source)?;
+    }
+    let tool = format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    xmp.set_property(FILMROLLS, "Tool", &XmpValue::new(tool))?;
+    let dates = [
+        ("Timestamp", data.timestamp.fixed_offset()),
+        ("Load", data.load.and_utc().fixed_offset()),
+        ("Unload", data.unload.and_utc().fixed_offset()),
+    ];
+    for (name, date) in dates {
+        xmp.set_property_date(FILMROLLS, name, &XmpValue::new(date.into()))?;
+    }
+    Ok(())
+}
+
+/// Decide whether to set an XMP property, honouring the merge mode of its field group
+fn should_set(xmp: &XmpMeta, merge: &mut Merger, group: FieldGroup, ns: &str, name: &str) -> bool {
+    let present = xmp.contains_property(ns, name);
+    merge.write(group, &format!("XMP {ns}{name}"), present)
+}
+
+/// Set an array of authors, optionally adding the author to the existing ones
+///
+/// Unless appending, any existing authors are removed first; authors which
+/// are already listed are not repeated.
+fn set_authors(
+    xmp: &mut XmpMeta,
+    merge: &mut Merger,
+    ns: &str,
+    name: &str,
+    author: &XmpValue<String>,
+) -> XmpResult<()> {
+    if !should_set(xmp, merge, FieldGroup::Author, ns, name) {
+        return Ok(());
+    }
+    if merge.mode(FieldGroup::Author) != MergeMode::Append {
+        xmp.delete_property(ns, name)?;
+    } else if xmp
+        .property_array(ns, name)
+        .any(|item| item.value == author.value)
+    {
+        return Ok(());
+    }
+    let array = XmpValue::new(name.to_owned()).set_is_array(true);
+    xmp.append_array_item(ns, &array, author)
+}
+
+/// Write a development record to the film development XMP namespace
+///
+/// Any previously written record is removed first, so that a per-frame
+/// record completely replaces the record of the roll. Properties which the
+/// merge mode says to keep are left as they are.
+fn set_development(xmp: &mut XmpMeta, merge: &mut Merger, data: &Development) -> XmpResult<()> {
+    let _ = XmpMeta::register_namespace(FILMDEV, "filmdev")?;
+    let names = FILMDEV_PROPERTIES
+        .into_iter()
+        .filter(|name| should_set(xmp, merge, FieldGroup::Development, FILMDEV, name))
+        .collect::<Vec<_>>();
+    for name in &names {
+        xmp.delete_property(FILMDEV, name)?;
+    }
+
+    let set = |name| names.contains(&name);
+    let text = [
+        ("Developer", &data.developer),
+        ("Dilution", &data.dilution),
+        ("Agitation", &data.agitation),
+        ("Lab", &data.lab),
+    ];
+    for (name, value) in text {
+        if let Some(value) = value.as_ref().filter(|_| set(name)) {
+            xmp.set_property(FILMDEV, name, &XmpValue::new(value.clone()))?;
+        }
+    }
+    if let Some(time) = data.time.filter(|_| set("Time")) {
+        let seconds = time.0.as_secs().try_into().unwrap_or(i64::MAX);
+        xmp.set_property_i64(FILMDEV, "Time", &XmpValue::new(seconds))?;
+    }
+    if let Some(temperature) = data.temperature.filter(|_| set("Temperature")) {
+        let value = XmpValue::new(temperature.normalize().to_string());
+        xmp.set_property(FILMDEV, "Temperature", &value)?;
+    }
+    if let Some(push) = data.push.filter(|_| set("PushPull")) {
+        xmp.set_property_i32(FILMDEV, "PushPull", &XmpValue::new(push.into()))?;
+    }
+    if let Some(date) = data.date.filter(|_| set("Date")) {
+        use chrono::Datelike;
+        let date = XmpDateTime {
+            date: Some(XmpDate {
+                year: date.year(),
+                month: date.month() as i32,
+                day: date.day() as i32,
+            }),
+            time: None,
+        };
+        xmp.set_property_date(FILMDEV, "Date", &XmpValue::new(date))?;
+    }
+    Ok(())
+}
+
+/// Link a variant (e.g. a crop or rescan) to the original scan of a frame
+///
+/// All variants share the same `xmpMM:DocumentID`, and variants other than
+/// the original also refer to it using `xmpMM:DerivedFrom`.
+pub(super) fn set_variant_link(
+    xmp: &mut XmpMeta,
+    document_id: &str,
+    original: Option<&std::path::Path>,
+) -> XmpResult<()> {
+    xmp.set_property(XMP_MM, "DocumentID", &XmpValue::new(document_id.into()))?;
+    xmp.delete_property(XMP_MM, "DerivedFrom")?;
+    if let Some(original) = original {
+        let document_id = XmpValue::new(document_id.into());
+        xmp.set_struct_field(
+            XMP_MM,
+            "DerivedFrom",
+            RESOURCE_REF,
+            "documentID",
+            &document_id,
+        )?;
+        let file_path = XmpValue::new(original.display().to_string());
+        xmp.set_struct_field(XMP_MM, "DerivedFrom", RESOURCE_REF, "filePath", &file_path)?;
+    }
+    Ok(())
+}
+
+impl super::ApplyMetadata for XmpMeta {
+    fn apply_roll_data(
+        &mut self,
+        data: &Roll,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
+        // EXIF 2.3 tags
+        let _ = Self::register_namespace(EXIF_EX, "exifEX")?;
+        if let Some(serial) = &data.camera_serial {
+            if should_set(self, merge, FieldGroup::Camera, EXIF_EX, "BodySerialNumber") {
+                self.set_property(EXIF_EX, "BodySerialNumber", &XmpValue::new(serial.clone()))?;
+            }
+        }
+        if let Some(owner) = &data.camera_owner {
+            if should_set(self, merge, FieldGroup::Camera, EXIF_EX, "CameraOwnerName") {
+                self.set_property(EXIF_EX, "CameraOwnerName", &XmpValue::new(owner.clone()))?;
+            }
+        }
+
+        // Film development tags
+        if let Some(development) = &data.development {
+            set_development(self, merge, development)?;
+        }
+
+        // Success!
         Ok(())
     }
 
-    fn apply_frame_data(&mut self, data: &Frame) -> Result<(), super::NegativeError> {
+    fn apply_frame_data(
+        &mut self,
+        data: &Frame,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
         // Photoshop tags
-        self.set_property_date(
-            PHOTOSHOP,
-            "DateCreated",
-            &XmpValue::new(data.datetime.and_utc().fixed_offset().into()),
-        )?;
+        if should_set(self, merge, FieldGroup::Date, PHOTOSHOP, "DateCreated") {
+            self.set_property_date(
+                PHOTOSHOP,
+                "DateCreated",
+                &XmpValue::new(data.datetime.and_utc().fixed_offset().into()),
+            )?;
+        }
+
+        // EXIF 2.3 tags
+        let _ = Self::register_namespace(EXIF_EX, "exifEX")?;
+        if let Some(serial) = &data.lens_serial {
+            if should_set(self, merge, FieldGroup::Lens, EXIF_EX, "LensSerialNumber") {
+                self.set_property(EXIF_EX, "LensSerialNumber", &XmpValue::new(serial.clone()))?;
+            }
+        }
+
+        // Film development tags (overriding the roll)
+        if let Some(development) = &data.development {
+            set_development(self, merge, development)?;
+        }
 
         // Success!
         Ok(())
@@ -31,49 +289,101 @@ impl super::ApplyMetadata for xmp_toolkit::XmpMeta {
         &mut self,
         data: &Metadata,
         date: &Option<chrono::NaiveDate>,
+        merge: &mut Merger,
     ) -> Result<(), super::NegativeError> {
         // Figure out what year this negative was shot, for the copyright
         let date = date.unwrap_or_else(|| chrono::Utc::now().date_naive());
         let author = XmpValue::new(data.author.name.clone());
-
-        // Clear the array tags, to have a clean slate
-        self.delete_property(DC, "creator")?;
-        self.delete_property(XMP_RIGHTS, "Owner")?;
+        let group = FieldGroup::Author;
 
         // Dublin Core tags
-        self.append_array_item(
-            DC,
-            &XmpValue::new("creator".into()).set_is_array(true),
-            &author,
-        )?;
-        self.set_localized_text(DC, "rights", None, "x-default", &data.copyright(date))?;
+        set_authors(self, merge, DC, "creator", &author)?;
+        if should_set(self, merge, group, DC, "rights") {
+            self.set_localized_text(DC, "rights", None, "x-default", &data.copyright(date))?;
+        }
 
         // Photoshop tags
-        self.set_property(
-            PHOTOSHOP,
-            "AuthorsPosition",
-            &XmpValue::new("Photographer".into()),
-        )?;
+        if should_set(self, merge, group, PHOTOSHOP, "AuthorsPosition") {
+            self.set_property(
+                PHOTOSHOP,
+                "AuthorsPosition",
+                &XmpValue::new("Photographer".into()),
+            )?;
+        }
 
         // XMP Rights tags
-        self.append_array_item(
-            XMP_RIGHTS,
-            &XmpValue::new("Owner".into()).set_is_array(true),
-            &author,
-        )?;
+        set_authors(self, merge, XMP_RIGHTS, "Owner", &author)?;
         if let Some(terms) = data.usage_terms() {
             let marked = data.license != Some(License::PublicDomain);
-            self.set_property_bool(XMP_RIGHTS, "Marked", &XmpValue::new(marked))?;
-            self.set_localized_text(XMP_RIGHTS, "UsageTerms", None, "x-default", &terms)?;
+            if should_set(self, merge, group, XMP_RIGHTS, "Marked") {
+                self.set_property_bool(XMP_RIGHTS, "Marked", &XmpValue::new(marked))?;
+            }
+            if should_set(self, merge, group, XMP_RIGHTS, "UsageTerms") {
+                self.set_localized_text(XMP_RIGHTS, "UsageTerms", None, "x-default", &terms)?;
+            }
         }
 
         // Set the Artist & Copyright EXIF tags
         if let Some(license) = &data.license {
             let _ = Self::register_namespace(CC, "cc")?;
-            self.set_property(CC, "license", &XmpValue::new(license.url().into()))?;
-            self.set_property(CC, "attributionName", &author)?;
+            if should_set(self, merge, group, CC, "license") {
+                self.set_property(CC, "license", &XmpValue::new(license.url().into()))?;
+            }
+            if should_set(self, merge, group, CC, "attributionName") {
+                self.set_property(CC, "attributionName", &author)?;
+            }
             if let Some(url) = &data.author.url {
-                self.set_property(CC, "attributionURL", &XmpValue::new(url.clone()))?;
+                if should_set(self, merge, group, CC, "attributionURL") {
+                    self.set_property(CC, "attributionURL", &XmpValue::new(url.clone()))?;
+                }
+            }
+        }
+
+        // Success!
+        Ok(())
+    }
+
+    fn apply_scan_data(
+        &mut self,
+        data: &Scan,
+        merge: &mut Merger,
+    ) -> Result<(), super::NegativeError> {
+        let group = FieldGroup::Scan;
+
+        // EXIF tags (film scanner)
+        if should_set(self, merge, group, EXIF, "FileSource") {
+            self.set_property_i32(EXIF, "FileSource", &XmpValue::new(1))?;
+        }
+
+        // XMP tags
+        if let Some(date) = data.date {
+            if should_set(self, merge, group, XMP, "CreateDate") {
+                let date = date.and_utc().fixed_offset().into();
+                self.set_property_date(XMP, "CreateDate", &XmpValue::new(date))?;
+            }
+        }
+        if let Some(software) = &data.software {
+            if should_set(self, merge, group, XMP, "CreatorTool") {
+                self.set_property(XMP, "CreatorTool", &XmpValue::new(software.clone()))?;
+            }
+        }
+
+        // Film scanning tags
+        let _ = Self::register_namespace(FILMSCAN, "filmscan")?;
+        if let Some(scanner) = &data.scanner {
+            if should_set(self, merge, group, FILMSCAN, "Scanner") {
+                self.set_property(FILMSCAN, "Scanner", &XmpValue::new(scanner.clone()))?;
+            }
+        }
+        if let Some(resolution) = data.resolution {
+            if should_set(self, merge, group, FILMSCAN, "Resolution") {
+                let resolution = XmpValue::new(resolution.try_into().unwrap_or(i32::MAX));
+                self.set_property_i32(FILMSCAN, "Resolution", &resolution)?;
+            }
+        }
+        if let Some(holder) = &data.holder {
+            if should_set(self, merge, group, FILMSCAN, "Holder") {
+                self.set_property(FILMSCAN, "Holder", &XmpValue::new(holder.clone()))?;
             }
         }
 
@@ -85,6 +395,8 @@ impl super::ApplyMetadata for xmp_toolkit::XmpMeta {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::development::*;
+    use crate::merge::{MergePolicy, Merger};
     use crate::metadata::*;
     use crate::negative::ApplyMetadata;
     use crate::rolls::*;
@@ -100,18 +412,104 @@ mod tests {
             .expect("should be possible to initialize empty XMP data");
         let roll = Roll {
             id: "A1234".into(),
+            sheet_film: false,
             film: Some(Film("Ilford Delta 100".into())),
             speed: FilmSpeed::from_din(21),
             camera: Some(Camera::MakeModel {
                 make: "Voigtländer".into(),
                 model: "Bessa R2M".into(),
             }),
+            camera_serial: Some("01234567".into()),
+            camera_owner: Some("Simon Sigurdhsson".into()),
             load: chrono::NaiveDateTime::MIN,
             unload: chrono::NaiveDateTime::MAX,
+            development: Some(Development {
+                developer: Some("Kodak HC-110".into()),
+                dilution: Some("B".into()),
+                time: Some(DevelopmentTime(std::time::Duration::from_secs(390))),
+                temperature: Some(dec!(20.0)),
+                push: Some(1),
+                date: chrono::NaiveDate::from_ymd_opt(2016, 5, 22),
+                ..Default::default()
+            }),
             frames: vec![],
         };
-        xmp.apply_roll_data(&roll)
+        xmp.apply_roll_data(&roll, &mut Merger::default())
             .expect("roll data should be applicable as XMP");
+
+        assert_eq!(
+            xmp.property(FILMDEV, "Developer"),
+            Some(XmpValue::new("Kodak HC-110".into()))
+        );
+        assert_eq!(
+            xmp.property(FILMDEV, "Dilution"),
+            Some(XmpValue::new("B".into()))
+        );
+        assert_eq!(xmp.property_i64(FILMDEV, "Time"), Some(XmpValue::new(390)));
+        assert_eq!(
+            xmp.property(FILMDEV, "Temperature"),
+            Some(XmpValue::new("20".into()))
+        );
+        assert_eq!(
+            xmp.property_i32(FILMDEV, "PushPull"),
+            Some(XmpValue::new(1))
+        );
+        assert_eq!(
+            xmp.property(FILMDEV, "Date"),
+            Some(XmpValue::new("2016-05-22".into()))
+        );
+        assert_eq!(
+            xmp.property(EXIF_EX, "BodySerialNumber"),
+            Some(XmpValue::new("01234567".into()))
+        );
+        assert_eq!(
+            xmp.property(EXIF_EX, "CameraOwnerName"),
+            Some(XmpValue::new("Simon Sigurdhsson".into()))
+        );
+        assert_eq!(xmp.property(FILMDEV, "Agitation"), None);
+        assert_eq!(xmp.property(FILMDEV, "Lab"), None);
+    }
+
+    #[test]
+    fn apply_frame_data_development_override() {
+        let mut xmp = xmp_toolkit::XmpMeta::new() //
+            .expect("should be possible to initialize empty XMP data");
+        let roll_development = Development {
+            developer: Some("Kodak HC-110".into()),
+            dilution: Some("B".into()),
+            ..Default::default()
+        };
+        set_development(&mut xmp, &mut Merger::default(), &roll_development)
+            .expect("development data should be applicable as XMP");
+
+        let frame = Frame {
+            film: None,
+            speed: None,
+            development: Some(Development {
+                developer: Some("Ilford ID-11".into()),
+                ..Default::default()
+            }),
+            lens: None,
+            lens_serial: None,
+            aperture: None,
+            shutter_speed: None,
+            focal_length: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: chrono::NaiveDateTime::default(),
+            position: Position { lat: 0.0, lon: 0.0 },
+            note: None,
+        };
+        xmp.apply_frame_data(&frame, &mut Merger::default())
+            .expect("frame data should be applicable as XMP");
+
+        assert_eq!(
+            xmp.property(FILMDEV, "Developer"),
+            Some(XmpValue::new("Ilford ID-11".into()))
+        );
+        assert_eq!(xmp.property(FILMDEV, "Dilution"), None);
     }
 
     #[test]
@@ -121,10 +519,14 @@ mod tests {
         let datetime = chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
             .and_then(|date| date.and_hms_opt(12, 15, 00));
         let frame = Frame {
+            film: None,
+            speed: None,
+            development: None,
             lens: Some(Lens::MakeModel {
                 make: "Voigtländer".into(),
                 model: "Color Skopar 35/2.5 Pancake II".into(),
             }),
+            lens_serial: Some("7654321".into()),
             aperture: Some(Aperture::Manual(dec!(2.5))),
             shutter_speed: Some(ShutterSpeed::Manual(Ratio::new(1, 125))),
             focal_length: Some(FocalLength {
@@ -132,11 +534,14 @@ mod tests {
                 equiv: Some(dec!(35)),
             }),
             compensation: Some(ExposureBias(Ratio::new(-1, 3))),
+            flash: None,
+            metering: None,
+            light_source: None,
             datetime: datetime.unwrap(),
             position: Position { lat: 0.0, lon: 0.0 },
             note: None,
         };
-        xmp.apply_frame_data(&frame)
+        xmp.apply_frame_data(&frame, &mut Merger::default())
             .expect("frame data should be applicable as XMP");
 
         assert_eq!(
@@ -145,6 +550,10 @@ mod tests {
                 frame.datetime.and_utc().fixed_offset().into()
             ))
         );
+        assert_eq!(
+            xmp.property(EXIF_EX, "LensSerialNumber"),
+            Some(XmpValue::new("7654321".into()))
+        );
     }
 
     #[test]
@@ -159,8 +568,9 @@ mod tests {
                 url: None,
             },
             license: None,
+            scan: None,
         };
-        xmp.apply_author_data(&metadata, &datetime)
+        xmp.apply_author_data(&metadata, &datetime, &mut Merger::default())
             .expect("author/license data should be applicable as XMP");
 
         assert_equal(
@@ -204,6 +614,7 @@ mod tests {
                 url: None,
             },
             license: None,
+            scan: None,
         };
 
         // Start with populated creator/owner arrays
@@ -220,7 +631,7 @@ mod tests {
         )
         .expect("should be possible to set XMP rights owner");
 
-        xmp.apply_author_data(&metadata, &None)
+        xmp.apply_author_data(&metadata, &None, &mut Merger::default())
             .expect("author/license data should be applicable as XMP");
 
         assert_equal(
@@ -233,6 +644,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_author_data_append_arrays() {
+        let mut xmp = xmp_toolkit::XmpMeta::new() //
+            .expect("should be possible to initialize empty XMP data");
+        let metadata = Metadata {
+            author: Author {
+                name: "Simon Sigurdhsson".into(),
+                url: None,
+            },
+            license: None,
+            scan: None,
+        };
+        let policy = MergePolicy::default()
+            .with_rules(["author=append".parse().expect("merge rule should be valid")]);
+
+        // Start with a co-author in the creator/owner arrays
+        for (ns, name) in [(DC, "creator"), (XMP_RIGHTS, "Owner")] {
+            xmp.append_array_item(
+                ns,
+                &XmpValue::new(name.into()).set_is_array(true),
+                &XmpValue::new("Existing Author".into()),
+            )
+            .expect("should be possible to set author arrays");
+        }
+
+        // Applying the same author twice adds it only once
+        for _ in 0..2 {
+            xmp.apply_author_data(&metadata, &None, &mut Merger::new(&policy))
+                .expect("author/license data should be applicable as XMP");
+        }
+
+        for (ns, name) in [(DC, "creator"), (XMP_RIGHTS, "Owner")] {
+            assert_equal(
+                xmp.property_array(ns, name),
+                [
+                    XmpValue::new("Existing Author".into()),
+                    XmpValue::new(metadata.author.name.clone()),
+                ],
+            );
+        }
+    }
+
     #[test]
     fn apply_author_data_with_license() {
         let mut xmp = xmp_toolkit::XmpMeta::new() //
@@ -243,8 +696,9 @@ mod tests {
                 url: Some("http://photography.sigurdhsson.org/".into()),
             },
             license: Some(License::Attribution),
+            scan: None,
         };
-        xmp.apply_author_data(&metadata, &None)
+        xmp.apply_author_data(&metadata, &None, &mut Merger::default())
             .expect("author/license data should be applicable as XMP");
 
         assert_eq!(
@@ -271,4 +725,119 @@ mod tests {
             metadata.author.url.map(XmpValue::new)
         );
     }
+
+    #[test]
+    fn apply_scan_data() {
+        let mut xmp = xmp_toolkit::XmpMeta::new() //
+            .expect("should be possible to initialize empty XMP data");
+        let scan = Scan {
+            scanner: Some("Epson Perfection V850 Pro".into()),
+            software: Some("SilverFast 9".into()),
+            resolution: Some(3200),
+            date: chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+                .and_then(|date| date.and_hms_opt(12, 15, 00)),
+            holder: Some("Betterscanning 35mm".into()),
+        };
+        xmp.apply_scan_data(&scan, &mut Merger::default())
+            .expect("scan data should be applicable as XMP");
+
+        assert_eq!(xmp.property_i32(EXIF, "FileSource"), Some(XmpValue::new(1)));
+        assert_eq!(
+            xmp.property_date(XMP, "CreateDate"),
+            Some(XmpValue::new(
+                scan.date.unwrap().and_utc().fixed_offset().into()
+            ))
+        );
+        assert_eq!(
+            xmp.property(XMP, "CreatorTool"),
+            Some(XmpValue::new("SilverFast 9".into()))
+        );
+        assert_eq!(
+            xmp.property(FILMSCAN, "Scanner"),
+            Some(XmpValue::new("Epson Perfection V850 Pro".into()))
+        );
+        assert_eq!(
+            xmp.property_i32(FILMSCAN, "Resolution"),
+            Some(XmpValue::new(3200))
+        );
+        assert_eq!(
+            xmp.property(FILMSCAN, "Holder"),
+            Some(XmpValue::new("Betterscanning 35mm".into()))
+        );
+    }
+
+    #[test]
+    fn variant_links() {
+        let mut xmp = xmp_toolkit::XmpMeta::new() //
+            .expect("should be possible to initialize empty XMP data");
+        let path = std::path::Path::new("001.tiff");
+        set_variant_link(&mut xmp, "xmp.did:A0012+001", Some(path))
+            .expect("should be possible to link variants");
+        assert_eq!(
+            xmp.property(XMP_MM, "DocumentID"),
+            Some(XmpValue::new("xmp.did:A0012+001".into()))
+        );
+        assert_eq!(
+            xmp.struct_field(XMP_MM, "DerivedFrom", RESOURCE_REF, "documentID"),
+            Some(XmpValue::new("xmp.did:A0012+001".into()))
+        );
+        assert_eq!(
+            xmp.struct_field(XMP_MM, "DerivedFrom", RESOURCE_REF, "filePath"),
+            Some(XmpValue::new("001.tiff".into()))
+        );
+
+        set_variant_link(&mut xmp, "xmp.did:A0012+001", None)
+            .expect("should be possible to link variants");
+        assert!(!xmp.contains_property(XMP_MM, "DerivedFrom"));
+    }
+
+    #[test]
+    fn provenance() {
+        let mut xmp = xmp_toolkit::XmpMeta::new() //
+            .expect("should be possible to initialize empty XMP data");
+        assert_eq!(read_provenance(&xmp), (None, None));
+
+        let date = chrono::NaiveDate::from_ymd_opt(2016, 5, 13).unwrap();
+        let provenance = crate::negative::Provenance {
+            roll: "A0012".into(),
+            frame: Some(1),
+            source: Some("filmrolls.xml".into()),
+            load: date.and_hms_opt(14, 12, 40).unwrap(),
+            unload: date.and_hms_opt(15, 26, 49).unwrap(),
+            timestamp: chrono::DateTime::UNIX_EPOCH,
+        };
+        set_provenance(&mut xmp, &provenance).expect("provenance should be recordable as XMP");
+        assert_eq!(read_provenance(&xmp), (Some("A0012".into()), Some(1)));
+        let source = std::path::absolute("filmrolls.xml").unwrap();
+        assert_eq!(
+            xmp.property(FILMROLLS, "Source"),
+            Some(XmpValue::new(source.display().to_string()))
+        );
+        assert_eq!(
+            xmp.property(FILMROLLS, "Load"),
+            Some(XmpValue::new("2016-05-13T14:12:40Z".into()))
+        );
+        assert_eq!(
+            xmp.property(FILMROLLS, "Timestamp"),
+            Some(XmpValue::new("1970-01-01T00:00:00Z".into()))
+        );
+
+        // Recording provenance again replaces the previous record
+        let provenance = crate::negative::Provenance {
+            frame: None,
+            source: None,
+            ..provenance
+        };
+        set_provenance(&mut xmp, &provenance).expect("provenance should be recordable as XMP");
+        assert_eq!(read_provenance(&xmp), (Some("A0012".into()), None));
+        assert!(!xmp.contains_property(FILMROLLS, "Source"));
+
+        // Roll data read from standard input has no source
+        let provenance = crate::negative::Provenance {
+            source: Some("-".into()),
+            ..provenance
+        };
+        set_provenance(&mut xmp, &provenance).expect("provenance should be recordable as XMP");
+        assert!(!xmp.contains_property(FILMROLLS, "Source"));
+    }
 }
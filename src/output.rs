@@ -0,0 +1,311 @@
+//! Command output formatting
+//!
+//! Commands listing rolls, frames or images print a human-readable table by
+//! default, but can instead print their data as machine-readable records.
+//! Records use a fixed set of stable field names (see [`Record`]) and raw
+//! values (e.g. ISO film speeds, decimal apertures and coordinates, rational
+//! shutter speeds and RFC 3339 timestamps) rather than the display strings
+//! used in tables.
+use comfy_table::Table;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use serde_with::SerializeAs;
+
+/// Output format of commands listing rolls, frames or images
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(clap::ValueEnum)]
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable table
+    Table,
+    /// JSON array, one object per row
+    Json,
+    /// Comma-separated values, with a header row
+    Csv,
+    /// Tab-separated values, with a header row
+    Tsv,
+    /// Markdown table
+    Markdown,
+}
+
+/// Output formatting errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum OutputError {
+    /// JSON serialization error
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
+    /// CSV serialization error
+    #[error(transparent)]
+    CsvError(#[from] csv::Error),
+
+    /// UTF8 conversion error
+    #[error(transparent)]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+
+    /// Record is not a JSON object
+    #[error("Record is not a struct or map")]
+    InvalidRecord,
+
+    /// Record has a field missing from its schema
+    #[error("Record field `{0}` is not part of its schema")]
+    UnknownField(String),
+}
+
+/// A record with a fixed schema, listed by some command
+///
+/// The schema determines the columns of the tabular formats, such that the
+/// same columns are printed regardless of which fields are set (and even if
+/// there are no records at all). Records should not contain nested values.
+pub trait Record: Serialize {
+    /// The names of all fields, in order
+    const FIELDS: &'static [&'static str];
+}
+
+/// Timestamp written in RFC 3339 format, e.g. `2016-05-13T14:12:40Z`
+///
+/// Logged dates & times carry no time zone, and are written as UTC just like
+/// the XMP dates written when tagging.
+pub struct Rfc3339;
+
+impl SerializeAs<chrono::NaiveDateTime> for Rfc3339 {
+    fn serialize_as<S>(source: &chrono::NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let value = source
+            .and_utc()
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+        serializer.serialize_str(&value)
+    }
+}
+
+/// Apply the common table style to the given table
+pub fn format_table(mut table: Table) -> Table {
+    use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
+    use comfy_table::ContentArrangement;
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+    table
+}
+
+/// Format the given records in the given output format
+///
+/// The returned string ends with a newline. The tabular formats always
+/// include a header listing the fields of the record schema, and fields
+/// which are `null` are left empty. Tables list the raw record values;
+/// commands with a more readable table of their own should use that instead.
+pub fn format_records<T: Record>(
+    records: &[T],
+    format: OutputFormat,
+) -> Result<String, OutputError> {
+    if format == OutputFormat::Json {
+        return Ok(serde_json::to_string_pretty(records)? + "\n");
+    }
+    let header = T::FIELDS.to_vec();
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            let mut fields = match serde_json::to_value(record)? {
+                Value::Object(object) => object,
+                _ => return Err(OutputError::InvalidRecord),
+            };
+            let row = header
+                .iter()
+                .map(|field| fields.remove(*field).map(format_value).unwrap_or_default())
+                .collect();
+            match fields.into_iter().next() {
+                Some((field, _)) => Err(OutputError::UnknownField(field)),
+                None => Ok(row),
+            }
+        })
+        .try_collect()?;
+    let values = rows
+        .iter()
+        .map(|row| row.iter().map(String::as_str).collect_vec());
+    match format {
+        OutputFormat::Json => unreachable!("handled above"),
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.set_header(header).add_rows(values);
+            Ok(format!("{}\n", format_table(table).trim_fmt()))
+        }
+        OutputFormat::Csv => write_delimited(b',', header, values),
+        OutputFormat::Tsv => write_delimited(b'\t', header, values),
+        OutputFormat::Markdown => {
+            let line = |cells: Vec<&str>| {
+                let cells = cells.into_iter().map(|cell| {
+                    cell.replace('|', "\\|")
+                        .replace("\r\n", "<br>")
+                        .replace('\n', "<br>")
+                });
+                format!("| {} |\n", cells.format(" | "))
+            };
+            let separator = format!("|{}\n", "---|".repeat(header.len()));
+            Ok(line(header) + &separator + &values.map(line).join(""))
+        }
+    }
+}
+
+/// Write a header and rows as delimiter-separated values
+fn write_delimited<'a, I>(delimiter: u8, header: Vec<&str>, rows: I) -> Result<String, OutputError>
+where
+    I: Iterator<Item = Vec<&'a str>>,
+{
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    let buf = writer
+        .into_inner()
+        .map_err(|error| csv::Error::from(error.into_error()))?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// Format a JSON value as a field value
+///
+/// `null` values become empty strings, and strings are used as-is. Any other
+/// values (numbers, booleans, arrays and objects) are written as JSON.
+fn format_value(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value,
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[serde_with::serde_as]
+    #[derive(Serialize)]
+    struct TestRecord {
+        id: &'static str,
+        speed: u32,
+        aperture: Option<f64>,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        #[serde_as(as = "Option<Rfc3339>")]
+        date: Option<chrono::NaiveDateTime>,
+        note: Option<&'static str>,
+    }
+
+    impl Record for TestRecord {
+        const FIELDS: &'static [&'static str] = &[
+            "id",
+            "speed",
+            "aperture",
+            "latitude",
+            "longitude",
+            "date",
+            "note",
+        ];
+    }
+
+    fn records() -> [TestRecord; 2] {
+        [
+            TestRecord {
+                id: "A0012",
+                speed: 100,
+                aperture: Some(5.6),
+                latitude: Some(57.700767),
+                longitude: Some(11.953715),
+                date: "2016-05-13T14:12:40".parse().ok(),
+                note: Some("Pier | harbour"),
+            },
+            TestRecord {
+                id: "A0020",
+                speed: 200,
+                aperture: None,
+                latitude: None,
+                longitude: None,
+                date: None,
+                note: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn json_records() -> Result<(), OutputError> {
+        let output = format_records(&records(), OutputFormat::Json)?;
+        assert!(output.starts_with("[\n  {\n    \"id\": \"A0012\",\n    \"speed\": 100,"));
+        assert!(output.contains("\"date\": \"2016-05-13T14:12:40Z\""));
+        assert_eq!(
+            format_records::<TestRecord>(&[], OutputFormat::Json)?,
+            "[]\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn delimited_records() -> Result<(), OutputError> {
+        assert_eq!(
+            format_records(&records(), OutputFormat::Csv)?,
+            "id,speed,aperture,latitude,longitude,date,note\n\
+             A0012,100,5.6,57.700767,11.953715,2016-05-13T14:12:40Z,Pier | harbour\n\
+             A0020,200,,,,,\n"
+        );
+        assert_eq!(
+            format_records(&records(), OutputFormat::Tsv)?,
+            "id\tspeed\taperture\tlatitude\tlongitude\tdate\tnote\n\
+             A0012\t100\t5.6\t57.700767\t11.953715\t2016-05-13T14:12:40Z\tPier | harbour\n\
+             A0020\t200\t\t\t\t\t\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn empty_records() -> Result<(), OutputError> {
+        assert_eq!(
+            format_records::<TestRecord>(&[], OutputFormat::Csv)?,
+            "id,speed,aperture,latitude,longitude,date,note\n"
+        );
+        assert_eq!(
+            format_records::<TestRecord>(&[], OutputFormat::Markdown)?,
+            "| id | speed | aperture | latitude | longitude | date | note |\n\
+             |---|---|---|---|---|---|---|\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_fields() {
+        #[derive(Serialize)]
+        struct Incomplete {
+            id: &'static str,
+            extra: u32,
+        }
+        impl Record for Incomplete {
+            const FIELDS: &'static [&'static str] = &["id"];
+        }
+        let record = Incomplete {
+            id: "A0012",
+            extra: 1,
+        };
+        assert!(matches!(
+            format_records(&[record], OutputFormat::Csv),
+            Err(OutputError::UnknownField(field)) if field == "extra"
+        ));
+    }
+
+    #[test]
+    fn markdown_records() -> Result<(), OutputError> {
+        assert_eq!(
+            format_records(&records(), OutputFormat::Markdown)?,
+            "| id | speed | aperture | latitude | longitude | date | note |\n\
+             |---|---|---|---|---|---|---|\n\
+             | A0012 | 100 | 5.6 | 57.700767 | 11.953715 | 2016-05-13T14:12:40Z | Pier \\| harbour |\n\
+             | A0020 | 200 |  |  |  |  |  |\n"
+        );
+        Ok(())
+    }
+}
@@ -1,18 +1,25 @@
 //! Common film roll & frame definitions
 //!
 //! This module provides a common film roll definition which can be
-//! deserialized from all supported input data formats, and converted
-//! to EXIF data or displayed using the `Display` trait.
+//! deserialized from all supported input data formats, serialized to
+//! the same formats (or flat CSV), and converted to EXIF data or
+//! displayed using the `Display` trait.
 use std::str::FromStr;
 
 use chrono::NaiveDateTime;
 use itertools::Itertools;
 use lazy_regex::regex_replace;
-use serde_with::DeserializeFromStr;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 
+use crate::development::{Development, Records};
+use crate::gear::Inventory;
 use crate::types::*;
 mod filmrolls;
+mod flat;
 mod lightme;
+mod native;
 
 /// Data deserialization errors
 #[derive(Debug)]
@@ -26,6 +33,14 @@ pub enum SourceError {
     #[error(transparent)]
     InvalidJson(#[from] serde_json::error::Error),
 
+    /// Invalid TOML input
+    #[error(transparent)]
+    InvalidToml(#[from] toml::de::Error),
+
+    /// Generic I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
     /// Missing input data
     #[error("Missing data: {0}")]
     MissingData(&'static str),
@@ -39,6 +54,31 @@ pub enum SourceError {
     UnsupportedFormat(String),
 }
 
+/// Data serialization errors
+#[derive(Debug)]
+#[derive(thiserror::Error)]
+pub enum SinkError {
+    /// XML serialization error
+    #[error(transparent)]
+    InvalidXml(#[from] quick_xml::se::SeError),
+
+    /// JSON serialization error
+    #[error(transparent)]
+    InvalidJson(#[from] serde_json::error::Error),
+
+    /// TOML serialization error
+    #[error(transparent)]
+    InvalidToml(#[from] toml::ser::Error),
+
+    /// CSV serialization error
+    #[error(transparent)]
+    InvalidCsv(#[from] ::csv::Error),
+
+    /// Generic I/O error
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+}
+
 impl PartialEq for SourceError {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -52,6 +92,7 @@ impl PartialEq for SourceError {
 
 /// A film type, e.g. "Ilford Delta 100"
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(SerializeDisplay)]
 pub struct Film(pub String);
 
 impl From<&str> for Film {
@@ -68,7 +109,7 @@ impl std::fmt::Display for Film {
 
 /// A camera make/model, e.g. "Voigtländer Bessa R2M"
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(DeserializeFromStr)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
 pub enum Camera {
     Simple { full_name: String },
     MakeModel { make: String, model: String },
@@ -97,6 +138,9 @@ impl Camera {
     }
 }
 
+// Parsing names never fails, but the conversion is kept fallible like those of
+// the other roll data types (`unknown_lints` covers toolchains predating the lint)
+#[allow(unknown_lints, clippy::infallible_try_from)]
 impl TryFrom<&str> for Camera {
     type Error = <Self as FromStr>::Err;
 
@@ -126,6 +170,7 @@ impl std::fmt::Display for Camera {
 
 /// A lens make/model, e.g. "Voigtländer Color Skopar 35/2.5 Pancake II"
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(SerializeDisplay)]
 pub enum Lens {
     Simple { full_name: String },
     MakeModel { make: String, model: String },
@@ -154,6 +199,9 @@ impl Lens {
     }
 }
 
+// Parsing names never fails, but the conversion is kept fallible like those of
+// the other roll data types (`unknown_lints` covers toolchains predating the lint)
+#[allow(unknown_lints, clippy::infallible_try_from)]
 impl TryFrom<&str> for Lens {
     type Error = <Self as FromStr>::Err;
 
@@ -182,33 +230,63 @@ impl std::fmt::Display for Lens {
 }
 
 /// A single exposed frame
+///
+/// The film, film speed and development are normally given by the [`Roll`],
+/// but may be overridden on a per-frame basis (e.g. for sheet film, where
+/// every sheet may be of a different film stock and developed separately).
 #[derive(Clone, PartialEq, Debug)]
 pub struct Frame {
+    pub film: Option<Film>,
+    pub speed: Option<FilmSpeed>,
+    pub development: Option<Development>,
     pub lens: Option<Lens>,
+    pub lens_serial: Option<String>,
     pub aperture: Option<Aperture>,
     pub shutter_speed: Option<ShutterSpeed>,
     pub focal_length: Option<FocalLength>,
     pub compensation: Option<ExposureBias>,
+    pub flash: Option<Flash>,
+    pub metering: Option<MeteringMode>,
+    pub light_source: Option<LightSource>,
     pub datetime: NaiveDateTime,
     pub position: Position,
     pub note: Option<String>,
 }
 
+/// Extract a structured `key: value` line from a free-form note
+///
+/// Keys are matched case-insensitively, and values which cannot be
+/// parsed are ignored (since the note is, after all, free-form text).
+fn note_field<T: FromStr>(note: Option<&str>, key: &str) -> Option<T> {
+    note?
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+        .and_then(|(_, v)| v.trim().parse().ok())
+}
+
 impl TryFrom<filmrolls::Frame<'_>> for Frame {
     type Error = SourceError;
 
     fn try_from(value: filmrolls::Frame<'_>) -> Result<Self, Self::Error> {
         Ok(Self {
+            film: None,
+            speed: None,
+            development: None,
             lens: value
                 .lens
                 .as_deref()
                 .map(TryInto::try_into)
                 .transpose()
                 .map_err(|_| SourceError::InvalidData("lens (`<lens>`)"))?,
+            lens_serial: None,
             aperture: value.aperture,
             shutter_speed: value.shutter_speed,
             focal_length: None,
             compensation: value.compensation,
+            flash: note_field(value.note.as_deref(), "flash"),
+            metering: note_field(value.note.as_deref(), "metering"),
+            light_source: note_field(value.note.as_deref(), "light source"),
             datetime: value.date.into(),
             position: Position {
                 lat: value.latitude,
@@ -224,10 +302,14 @@ impl TryFrom<lightme::Frame<'_>> for Frame {
 
     fn try_from(value: lightme::Frame<'_>) -> Result<Self, Self::Error> {
         Ok(Self {
+            film: None,
+            speed: None,
+            development: None,
             lens: value
                 .lens_model
                 .map(|v| regex_replace!(r"(\s+\(.*?\))$", v.as_ref(), "").into_owned())
                 .map(|m| Lens::from_make_model(value.lens_make.map(Into::into), m)),
+            lens_serial: None,
             aperture: value.f_number,
             shutter_speed: value.exposure_time,
             focal_length: (|| {
@@ -237,6 +319,9 @@ impl TryFrom<lightme::Frame<'_>> for Frame {
                 })
             })(),
             compensation: None,
+            flash: value.flash,
+            metering: value.metering_mode,
+            light_source: value.light_source,
             datetime: value.date_time_original.into(),
             position: Position {
                 lat: value.gps_latitude,
@@ -247,6 +332,52 @@ impl TryFrom<lightme::Frame<'_>> for Frame {
     }
 }
 
+impl TryFrom<native::Frame> for Frame {
+    type Error = SourceError;
+
+    fn try_from(value: native::Frame) -> Result<Self, Self::Error> {
+        Ok(Self {
+            film: value.film.as_deref().map(Into::into),
+            speed: value
+                .speed
+                .map(|speed| FilmSpeed::from_iso(speed.into()))
+                .transpose()
+                .map_err(|_| SourceError::InvalidData("film speed (`speed`)"))?,
+            development: value.development,
+            lens: value
+                .lens
+                .as_deref()
+                .map(TryInto::try_into)
+                .transpose()
+                .map_err(|_| SourceError::InvalidData("lens (`lens`)"))?,
+            lens_serial: value.lens_serial,
+            aperture: value.aperture,
+            shutter_speed: value.shutter_speed,
+            focal_length: value
+                .focal_length
+                .map(|real| -> Result<_, SourceError> {
+                    Ok(FocalLength {
+                        real: real
+                            .try_into()
+                            .map_err(|_| SourceError::InvalidData("focal length"))?,
+                        equiv: value.focal_length_equiv.and_then(|v| v.try_into().ok()),
+                    })
+                })
+                .transpose()?,
+            compensation: value.compensation,
+            flash: value.flash,
+            metering: value.metering,
+            light_source: value.light_source,
+            datetime: value.date.into(),
+            position: Position {
+                lat: value.latitude,
+                lon: value.longitude,
+            },
+            note: value.note,
+        })
+    }
+}
+
 /// A complete film roll
 ///
 /// The film roll contains a `Vec<Option<Frame>>`, which includes all
@@ -255,17 +386,85 @@ impl TryFrom<lightme::Frame<'_>> for Frame {
 /// the last known frame of the input, it will be replaced with `None`;
 /// this allows users to index into the list without knowing whether
 /// there are any missing frames.
+///
+/// A roll may also represent a box of sheet film, in which case each
+/// frame is a single sheet, identified by its holder ID (which is used
+/// as the frame number).
+///
+/// The development record is only available from the native TOML format,
+/// but may also be attached from a separate sidecar file using
+/// [`Roll::with_development`].
 #[derive(Clone, PartialEq, Debug)]
 pub struct Roll {
     pub id: String,
+    pub sheet_film: bool,
     pub film: Option<Film>,
     pub speed: FilmSpeed,
     pub camera: Option<Camera>,
+    pub camera_serial: Option<String>,
+    pub camera_owner: Option<String>,
     pub load: NaiveDateTime,
     pub unload: NaiveDateTime,
+    pub development: Option<Development>,
     pub frames: Vec<Option<Frame>>,
 }
 
+impl Roll {
+    /// Attach a development record from a sidecar file
+    ///
+    /// If the given records contain an entry matching the roll ID, it will
+    /// replace any development record already present in the roll data.
+    pub fn with_development(mut self, records: &Records) -> Self {
+        if let Some(development) = records.get(&self.id) {
+            self.development = Some(development.clone());
+        }
+        self
+    }
+
+    /// Attach serial numbers and owners from a gear inventory
+    ///
+    /// The camera and lenses are looked up in the inventory by name; serial
+    /// numbers and owners already present in the roll data take precedence
+    /// over those found in the inventory.
+    pub fn with_gear(mut self, inventory: &Inventory) -> Self {
+        let camera = self.camera.as_ref().map(ToString::to_string);
+        if let Some(details) = camera.and_then(|name| inventory.camera.get(&name)) {
+            self.camera_serial = self.camera_serial.or_else(|| details.serial.clone());
+            self.camera_owner = self.camera_owner.or_else(|| details.owner.clone());
+        }
+        for frame in self.frames.iter_mut().flatten() {
+            let lens = frame.lens.as_ref().map(ToString::to_string);
+            if let Some(details) = lens.and_then(|name| inventory.lens.get(&name)) {
+                frame.lens_serial = frame.lens_serial.take().or_else(|| details.serial.clone());
+            }
+        }
+        self
+    }
+
+    /// Get the frame number of a logged frame of this roll
+    ///
+    /// The frame is identified by reference rather than by value, since
+    /// several frames of a roll may well hold identical data.
+    pub fn frame_number(&self, frame: &Frame) -> Option<usize> {
+        self.numbered_frames()
+            .find(|(_, other)| std::ptr::eq(*other, frame))
+            .map(|(number, _)| number)
+    }
+
+    /// Iterate over all logged frames, along with their frame numbers
+    fn numbered_frames(&self) -> impl Iterator<Item = (usize, &Frame)> {
+        self.frames
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, frame)| Some((idx + 1, frame.as_ref()?)))
+    }
+}
+
+/// The arithmetic (ISO) film speed, rounded to an integer
+fn iso_speed(speed: FilmSpeed) -> u32 {
+    speed.iso().round().to_u32().unwrap_or_default()
+}
+
 impl TryFrom<filmrolls::FilmRoll<'_>> for Roll {
     type Error = SourceError;
 
@@ -275,6 +474,7 @@ impl TryFrom<filmrolls::FilmRoll<'_>> for Roll {
                 .note
                 .map(Into::into)
                 .ok_or(SourceError::MissingData("roll ID (`<note>`)"))?,
+            sheet_film: false,
             film: value
                 .title
                 .as_deref()
@@ -289,8 +489,11 @@ impl TryFrom<filmrolls::FilmRoll<'_>> for Roll {
                 .map(TryInto::try_into)
                 .transpose()
                 .map_err(|_| SourceError::InvalidData("camera (`<camera>`)"))?,
+            camera_serial: None,
+            camera_owner: None,
             load: value.load.into(),
             unload: value.unload.into(),
+            development: None,
             frames: expand_indexed(
                 value
                     .frames
@@ -320,6 +523,7 @@ impl TryFrom<lightme::Data<'_>> for Roll {
                 .reel_name
                 .map(Into::into)
                 .ok_or(SourceError::MissingData("roll ID (`ReelName`)"))?,
+            sheet_film: false,
             film: first
                 .document_name
                 .as_deref()
@@ -332,8 +536,11 @@ impl TryFrom<lightme::Data<'_>> for Roll {
                 .model
                 .map(|v| regex_replace!(r"(\s+\(.*?\))$", v.as_ref(), "").into_owned())
                 .map(|m| Camera::from_make_model(first.make.map(Into::into), m)),
+            camera_serial: None,
+            camera_owner: None,
             load: comment.load_date.into(),
             unload: comment.unload_date.into(),
+            development: None,
             frames: expand_indexed(value.into_iter().map(|frame| -> (usize, Result<Frame, _>) {
                 (frame.image_number, frame.try_into())
             }))
@@ -343,6 +550,247 @@ impl TryFrom<lightme::Data<'_>> for Roll {
     }
 }
 
+impl TryFrom<native::FilmRoll> for Roll {
+    type Error = SourceError;
+
+    fn try_from(value: native::FilmRoll) -> Result<Self, Self::Error> {
+        let frames: Vec<_> = expand_indexed(
+            value
+                .frame
+                .into_iter()
+                .map(|frame| -> (usize, Result<Frame, _>) { (frame.number, frame.try_into()) }),
+        )
+        .map(Option::transpose)
+        .try_collect()?;
+        let dates = frames.iter().flatten().map(|frame| frame.datetime);
+        Ok(Self {
+            id: value.id,
+            sheet_film: value.sheet_film,
+            film: value.film.as_deref().map(Into::into),
+            speed: match value.speed {
+                Some(speed) => FilmSpeed::from_iso(speed.into())
+                    .map_err(|_| SourceError::InvalidData("film speed (`speed`)"))?,
+                None if value.sheet_film => {
+                    frames
+                        .iter()
+                        .flatten()
+                        .find_map(|frame| frame.speed)
+                        .ok_or(SourceError::MissingData("film speed (`speed`)"))?
+                }
+                None => Err(SourceError::MissingData("film speed (`speed`)"))?,
+            },
+            camera: value
+                .camera
+                .as_deref()
+                .map(TryInto::try_into)
+                .transpose()
+                .map_err(|_| SourceError::InvalidData("camera (`camera`)"))?,
+            camera_serial: value.camera_serial,
+            camera_owner: value.camera_owner,
+            load: match value.load {
+                Some(load) => load.into(),
+                None if value.sheet_film => dates
+                    .clone()
+                    .min()
+                    .ok_or(SourceError::MissingData("load date (`load`)"))?,
+                None => Err(SourceError::MissingData("load date (`load`)"))?,
+            },
+            unload: match value.unload {
+                Some(unload) => unload.into(),
+                None if value.sheet_film => dates
+                    .max()
+                    .ok_or(SourceError::MissingData("unload date (`unload`)"))?,
+                None => Err(SourceError::MissingData("unload date (`unload`)"))?,
+            },
+            development: value.development,
+            frames,
+        })
+    }
+}
+
+impl From<&Roll> for filmrolls::FilmRoll<'_> {
+    fn from(value: &Roll) -> Self {
+        let frame = value.numbered_frames().map(|(number, frame)| {
+            // Flash, metering & light source are kept in the note, if not already there
+            let note = frame.note.as_deref();
+            let fields = [
+                ("flash", frame.flash.map(|v| v.description())),
+                ("metering", frame.metering.map(|v| v.to_string())),
+                ("light source", frame.light_source.map(|v| v.to_string())),
+            ]
+            .into_iter()
+            .filter(|(key, _)| note_field::<String>(note, key).is_none())
+            .filter_map(|(key, value)| Some(format!("{key}: {}", value?)));
+            let note = note
+                .map(ToOwned::to_owned)
+                .into_iter()
+                .chain(fields)
+                .join("\n");
+            filmrolls::Frame {
+                lens: frame.lens.as_ref().map(|lens| lens.to_string().into()),
+                aperture: frame.aperture,
+                shutter_speed: frame.shutter_speed,
+                compensation: frame.compensation,
+                accessory: None,
+                number,
+                date: frame.datetime.into(),
+                latitude: frame.position.lat,
+                longitude: frame.position.lon,
+                note: (!note.is_empty()).then(|| note.into()),
+            }
+        });
+        Self {
+            title: value.film.as_ref().map(|film| film.to_string().into()),
+            speed: iso_speed(value.speed),
+            camera: value
+                .camera
+                .as_ref()
+                .map(|camera| camera.to_string().into()),
+            load: value.load.into(),
+            unload: value.unload.into(),
+            note: Some(value.id.clone().into()),
+            frames: filmrolls::Frames {
+                frame: frame.collect(),
+            },
+        }
+    }
+}
+
+impl From<&Roll> for lightme::Data<'_> {
+    fn from(value: &Roll) -> Self {
+        let camera = value.camera.as_ref();
+        let notes = lightme::Notes {
+            load_date: value.load.into(),
+            unload_date: value.unload.into(),
+        };
+        value
+            .numbered_frames()
+            .map(|(number, frame)| lightme::Frame {
+                date_time_original: frame.datetime.into(),
+                description: None,
+                document_name: (frame.film.as_ref())
+                    .or(value.film.as_ref())
+                    .map(|film| film.to_string().into()),
+                exposure_time: frame
+                    .shutter_speed
+                    .filter(|v| matches!(v, ShutterSpeed::Manual(_))),
+                f_number: frame.aperture.filter(|v| matches!(v, Aperture::Manual(_))),
+                flash: frame.flash,
+                focal_length: frame.focal_length.and_then(|v| v.real.to_f64()),
+                focal_length_equiv: frame.focal_length.and_then(|v| v.equiv?.to_f64()),
+                gps_latitude: frame.position.lat,
+                gps_longitude: frame.position.lon,
+                image_number: number,
+                iso_speed: iso_speed(frame.speed.unwrap_or(value.speed)),
+                lens_make: (frame.lens.as_ref())
+                    .and_then(Lens::make)
+                    .map(|make| make.to_owned().into()),
+                light_source: frame.light_source,
+                lens_model: (frame.lens.as_ref()).map(|lens| lens.model().to_owned().into()),
+                make: camera
+                    .and_then(Camera::make)
+                    .map(|make| make.to_owned().into()),
+                metering_mode: frame.metering,
+                model: camera.map(|camera| camera.model().to_owned().into()),
+                reel_name: Some(value.id.clone().into()),
+                user_comment: Some(notes.clone()),
+            })
+            .collect()
+    }
+}
+
+impl From<&Roll> for native::FilmRoll {
+    fn from(value: &Roll) -> Self {
+        let frame = value
+            .numbered_frames()
+            .map(|(number, frame)| native::Frame {
+                number,
+                film: frame.film.as_ref().map(ToString::to_string),
+                speed: frame.speed.map(iso_speed),
+                development: frame.development.clone(),
+                lens: frame.lens.as_ref().map(ToString::to_string),
+                lens_serial: frame.lens_serial.clone(),
+                aperture: frame.aperture,
+                shutter_speed: frame.shutter_speed,
+                focal_length: frame.focal_length.and_then(|v| v.real.to_f64()),
+                focal_length_equiv: frame.focal_length.and_then(|v| v.equiv?.to_f64()),
+                compensation: frame.compensation,
+                flash: frame.flash,
+                metering: frame.metering,
+                light_source: frame.light_source,
+                date: frame.datetime.into(),
+                latitude: frame.position.lat,
+                longitude: frame.position.lon,
+                note: frame.note.clone(),
+            });
+        Self {
+            id: value.id.clone(),
+            sheet_film: value.sheet_film,
+            film: value.film.as_ref().map(ToString::to_string),
+            speed: Some(iso_speed(value.speed)),
+            camera: value.camera.as_ref().map(ToString::to_string),
+            camera_serial: value.camera_serial.clone(),
+            camera_owner: value.camera_owner.clone(),
+            load: Some(value.load.into()),
+            unload: Some(value.unload.into()),
+            development: value.development.clone(),
+            frame: frame.collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a Roll> for Vec<flat::Row<'a>> {
+    fn from(value: &'a Roll) -> Self {
+        let row = |number: Option<usize>, frame: Option<&'a Frame>| {
+            let development =
+                (frame.and_then(|f| f.development.as_ref())).or(value.development.as_ref());
+            flat::Row {
+                roll: &value.id,
+                sheet_film: value.sheet_film,
+                film: frame.and_then(|f| f.film.as_ref()).or(value.film.as_ref()),
+                speed: frame.and_then(|f| f.speed).unwrap_or(value.speed),
+                camera: value.camera.as_ref(),
+                camera_serial: value.camera_serial.as_deref(),
+                camera_owner: value.camera_owner.as_deref(),
+                load: value.load,
+                unload: value.unload,
+                frame: number,
+                lens: frame.and_then(|f| f.lens.as_ref()),
+                lens_serial: frame.and_then(|f| f.lens_serial.as_deref()),
+                aperture: frame.and_then(|f| f.aperture),
+                shutter_speed: frame.and_then(|f| f.shutter_speed),
+                focal_length: frame.and_then(|f| f.focal_length).map(|v| v.real),
+                focal_length_equiv: frame.and_then(|f| f.focal_length?.equiv),
+                compensation: frame.and_then(|f| f.compensation),
+                flash: frame.and_then(|f| f.flash),
+                metering: frame.and_then(|f| f.metering),
+                light_source: frame.and_then(|f| f.light_source),
+                date: frame.map(|f| f.datetime),
+                latitude: frame.map(|f| f.position.lat),
+                longitude: frame.map(|f| f.position.lon),
+                developer: development.and_then(|d| d.developer.as_deref()),
+                dilution: development.and_then(|d| d.dilution.as_deref()),
+                development_time: development.and_then(|d| d.time),
+                temperature: development.and_then(|d| d.temperature),
+                agitation: development.and_then(|d| d.agitation.as_deref()),
+                push: development.and_then(|d| d.push),
+                lab: development.and_then(|d| d.lab.as_deref()),
+                development_date: development.and_then(|d| d.date),
+                note: frame.and_then(|f| f.note.as_deref()),
+            }
+        };
+        let rows = value
+            .numbered_frames()
+            .map(|(number, frame)| row(Some(number), Some(frame)))
+            .collect_vec();
+        if rows.is_empty() {
+            vec![row(None, None)]
+        } else {
+            rows
+        }
+    }
+}
+
 /// Read Film Rolls iOS app XML data
 ///
 /// Attempts to read film roll data from the Film Rolls iOS app using the provided
@@ -382,6 +830,121 @@ where
     }
 }
 
+/// Read native TOML roll data
+///
+/// Attempts to read film roll (or sheet film) data from the native TOML format
+/// using the provided reader. If a parsing error occurs, or any data is missing
+/// or invalid, the resulting iterator will return exactly one `Err` element,
+/// otherwise an iterator of film rolls is returned.
+pub fn from_toml<R>(mut reader: R) -> impl Iterator<Item = Result<Roll, SourceError>>
+where
+    R: std::io::BufRead,
+{
+    use itertools::Either::{Left, Right};
+    let mut buf = String::new();
+    match reader
+        .read_to_string(&mut buf)
+        .map_err(SourceError::from)
+        .and_then(|_| Ok(toml::de::from_str::<native::Data>(&buf)?))
+    {
+        Ok(data) => Left(data.roll.into_iter().map(TryInto::try_into)),
+        Err(error) => Right(std::iter::once(Err(error))),
+    }
+}
+
+/// Write Film Rolls iOS app XML data
+///
+/// Writes the given film rolls to the provided writer, in the XML format used
+/// by the Film Rolls iOS app. The camera and lens lists are populated with the
+/// cameras and lenses used by the rolls. Flash, metering and light source are
+/// not part of the format, and are instead appended to the frame notes (using
+/// the same `key: value` syntax recognized when reading).
+pub fn to_filmrolls<'a, I, W>(rolls: I, mut writer: W) -> Result<(), SinkError>
+where
+    I: IntoIterator<Item = &'a Roll>,
+    W: std::io::Write,
+{
+    let film_roll: Vec<filmrolls::FilmRoll> = rolls.into_iter().map(Into::into).collect();
+    let camera = film_roll
+        .iter()
+        .filter_map(|roll| roll.camera.clone())
+        .unique()
+        .map(|value| filmrolls::Camera { value })
+        .collect();
+    let lens = film_roll
+        .iter()
+        .flat_map(|roll| &roll.frames.frame)
+        .filter_map(|frame| frame.lens.clone())
+        .unique()
+        .map(|value| filmrolls::Lens { value })
+        .collect();
+    let data = filmrolls::Data {
+        cameras: filmrolls::Cameras { camera },
+        lenses: filmrolls::Lenses { lens },
+        accessories: Default::default(),
+        film_rolls: filmrolls::FilmRolls { film_roll },
+    };
+    let mut buf = String::new();
+    let mut serializer = quick_xml::se::Serializer::new(&mut buf);
+    serializer.indent(' ', 2);
+    data.serialize(serializer)?;
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, "{buf}")?;
+    Ok(())
+}
+
+/// Write lightme iOS app JSON data
+///
+/// Writes the given film rolls to the provided writer, as a single JSON array
+/// of frames in the format exported by the lightme iOS app. Since the format
+/// has no notion of rolls without frames, any such rolls are omitted.
+pub fn to_lightme<'a, I, W>(rolls: I, mut writer: W) -> Result<(), SinkError>
+where
+    I: IntoIterator<Item = &'a Roll>,
+    W: std::io::Write,
+{
+    let data: lightme::Data = rolls.into_iter().flat_map(lightme::Data::from).collect();
+    serde_json::to_writer_pretty(&mut writer, &data)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write native TOML roll data
+///
+/// Writes the given film rolls (including any development records) to the
+/// provided writer, in the native TOML format.
+pub fn to_toml<'a, I, W>(rolls: I, mut writer: W) -> Result<(), SinkError>
+where
+    I: IntoIterator<Item = &'a Roll>,
+    W: std::io::Write,
+{
+    let data = native::Data {
+        roll: rolls.into_iter().map(Into::into).collect(),
+    };
+    writer.write_all(toml::to_string_pretty(&data)?.as_bytes())?;
+    Ok(())
+}
+
+/// Write flat CSV data
+///
+/// Writes the given film rolls to the provided writer as CSV, with a header
+/// row followed by one row per logged frame (repeating the roll data on each
+/// row). Rolls without any logged frames are written as a single row.
+pub fn to_csv<'a, I, W>(rolls: I, writer: W) -> Result<(), SinkError>
+where
+    I: IntoIterator<Item = &'a Roll>,
+    W: std::io::Write,
+{
+    let mut writer = ::csv::Writer::from_writer(writer);
+    for roll in rolls {
+        for row in Vec::<flat::Row>::from(roll) {
+            writer.serialize(row)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
 /// Expand an `(index, item)` iterator into `Option<item>`
 ///
 /// This function iterates over the given index/value pairs, inserting
@@ -406,6 +969,57 @@ where
         .flatten()
 }
 
+/// Roll & frame fixtures shared by the unit tests of all modules
+///
+/// Tests needing specific values start from these and override the fields
+/// they care about using struct update syntax.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    /// A frame logged at the given date, without any other data
+    pub fn frame(datetime: &str) -> Frame {
+        Frame {
+            film: None,
+            speed: None,
+            development: None,
+            lens: None,
+            lens_serial: None,
+            aperture: None,
+            shutter_speed: None,
+            focal_length: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: datetime.parse().expect("fixture dates should be valid"),
+            position: Position::default(),
+            note: None,
+        }
+    }
+
+    /// A roll of Ilford Delta 100 shot using a Voigtländer Bessa R2M
+    pub fn roll(id: &str, frames: Vec<Option<Frame>>) -> Roll {
+        Roll {
+            id: id.into(),
+            sheet_film: false,
+            film: Some(Film("Ilford Delta 100".into())),
+            speed: FilmSpeed::from_din(21), // ISO 100/21°
+            camera: "Voigtländer Bessa R2M".try_into().ok(),
+            camera_serial: None,
+            camera_owner: None,
+            load: "2016-03-28T15:16:36"
+                .parse()
+                .expect("fixture dates should be valid"),
+            unload: "2016-05-21T14:13:15"
+                .parse()
+                .expect("fixture dates should be valid"),
+            development: None,
+            frames,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -528,13 +1142,20 @@ mod tests {
             note: Some("Notes for this frame!".into()),
         };
         let expected = Frame {
+            film: None,
+            speed: None,
+            development: None,
             lens: Some(Lens::Simple {
                 full_name: "Voigtländer Color Skopar 35/2.5 Pancake II".into(),
             }),
+            lens_serial: None,
             aperture: base_frame.aperture,
             shutter_speed: base_frame.shutter_speed,
             focal_length: None,
             compensation: base_frame.compensation,
+            flash: None,
+            metering: None,
+            light_source: None,
             datetime: base_frame.date.clone().into(),
             position: Position {
                 lat: base_frame.latitude,
@@ -564,6 +1185,22 @@ mod tests {
                 ..expected.clone()
             })
         );
+        assert_eq!(
+            Frame::try_from(filmrolls::Frame {
+                note: Some("Flash: auto, fired\nmetering: sunny 16\nLight source: cosmic".into()),
+                ..base_frame.clone()
+            }),
+            Ok(Frame {
+                flash: Some(Flash {
+                    fired: true,
+                    mode: Some(FlashMode::Auto)
+                }),
+                metering: Some(MeteringMode::Sunny16),
+                light_source: None,
+                note: Some("Flash: auto, fired\nmetering: sunny 16\nLight source: cosmic".into()),
+                ..expected.clone()
+            })
+        );
     }
 
     #[test]
@@ -585,13 +1222,17 @@ mod tests {
         };
         let expected = Roll {
             id: base_roll.note.clone().unwrap().into(),
+            sheet_film: false,
             film: Some(Film("Ilford Delta 100".into())),
             speed: FilmSpeed::from_din(21), // ISO 100/21°
             camera: Some(Camera::Simple {
                 full_name: "Voigtländer Bessa R2M".into(),
             }),
+            camera_serial: None,
+            camera_owner: None,
             load: base_roll.load.clone().into(),
             unload: base_roll.unload.clone().into(),
+            development: None,
             frames: vec![],
         };
 
@@ -632,6 +1273,195 @@ mod tests {
         );
     }
 
+    #[test]
+    fn convert_native_roll() {
+        let base_frame = native::Frame {
+            number: 2,
+            film: Some("Ilford FP4 Plus".into()),
+            speed: Some(125),
+            development: None,
+            lens: Some("Nikkor-W 150mm f/5.6".into()),
+            lens_serial: None,
+            aperture: Some(rust_decimal::Decimal::new(22, 0).into()),
+            shutter_speed: Some(num_rational::Rational32::new(1, 2).into()),
+            focal_length: Some(150.),
+            focal_length_equiv: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            date: NaiveDate::from_ymd_opt(2023, 6, 1)
+                .and_then(|d| d.and_hms_opt(10, 30, 0))
+                .unwrap()
+                .into(),
+            latitude: 57.700767,
+            longitude: 11.953715,
+            note: None,
+        };
+        let base_roll = native::FilmRoll {
+            id: "S0001".into(),
+            sheet_film: true,
+            film: None,
+            speed: None,
+            camera: Some("Chamonix 045N-2".into()),
+            camera_serial: None,
+            camera_owner: None,
+            load: None,
+            unload: None,
+            development: None,
+            frame: vec![base_frame.clone()],
+        };
+        let expected_frame = Frame {
+            film: Some(Film("Ilford FP4 Plus".into())),
+            speed: Some(FilmSpeed::from_din(22)), // ISO 125/22°
+            development: None,
+            lens: Some(Lens::Simple {
+                full_name: "Nikkor-W 150mm f/5.6".into(),
+            }),
+            lens_serial: None,
+            aperture: base_frame.aperture,
+            shutter_speed: base_frame.shutter_speed,
+            focal_length: Some(FocalLength {
+                real: rust_decimal::Decimal::new(150, 0),
+                equiv: None,
+            }),
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: base_frame.date.clone().into(),
+            position: Position {
+                lat: base_frame.latitude,
+                lon: base_frame.longitude,
+            },
+            note: None,
+        };
+        let expected = Roll {
+            id: "S0001".into(),
+            sheet_film: true,
+            film: None,
+            speed: FilmSpeed::from_din(22),
+            camera: Some(Camera::Simple {
+                full_name: "Chamonix 045N-2".into(),
+            }),
+            camera_serial: None,
+            camera_owner: None,
+            load: base_frame.date.clone().into(),
+            unload: base_frame.date.clone().into(),
+            development: None,
+            frames: vec![None, Some(expected_frame.clone())],
+        };
+
+        assert_eq!(Roll::try_from(base_roll.clone()), Ok(expected.clone()));
+        let frame = expected.frames[1].as_ref().unwrap();
+        assert_eq!(expected.frame_number(frame), Some(2));
+        assert_eq!(expected.frame_number(&expected_frame), None);
+        assert_eq!(
+            Roll::try_from(native::FilmRoll {
+                speed: Some(400),
+                ..base_roll.clone()
+            }),
+            Ok(Roll {
+                speed: FilmSpeed::from_din(27), // ISO 400/27°
+                ..expected.clone()
+            })
+        );
+        assert_eq!(
+            Roll::try_from(native::FilmRoll {
+                frame: vec![native::Frame {
+                    speed: None,
+                    ..base_frame.clone()
+                }],
+                ..base_roll.clone()
+            }),
+            Err(SourceError::MissingData("..."))
+        );
+        assert_eq!(
+            Roll::try_from(native::FilmRoll {
+                sheet_film: false,
+                ..base_roll.clone()
+            }),
+            Err(SourceError::MissingData("..."))
+        );
+        assert_eq!(
+            Roll::try_from(native::FilmRoll {
+                frame: vec![native::Frame {
+                    speed: Some(0),
+                    ..base_frame.clone()
+                }],
+                ..base_roll.clone()
+            }),
+            Err(SourceError::InvalidData("..."))
+        );
+    }
+
+    #[test]
+    fn roll_with_gear() {
+        let frame = Frame {
+            film: None,
+            speed: None,
+            development: None,
+            lens: "Color Skopar 35/2.5 Pancake II".try_into().ok(),
+            lens_serial: None,
+            aperture: None,
+            shutter_speed: None,
+            focal_length: None,
+            compensation: None,
+            flash: None,
+            metering: None,
+            light_source: None,
+            datetime: NaiveDateTime::default(),
+            position: Position::default(),
+            note: None,
+        };
+        let roll = Roll {
+            id: "A0012".into(),
+            sheet_film: false,
+            film: None,
+            speed: FilmSpeed::from_din(21),
+            camera: "Voigtländer Bessa R2M".try_into().ok(),
+            camera_serial: None,
+            camera_owner: Some("Göteborgs Fotoklubb".into()),
+            load: NaiveDateTime::default(),
+            unload: NaiveDateTime::default(),
+            development: None,
+            frames: vec![None, Some(frame.clone())],
+        };
+        let inventory = Inventory {
+            camera: [(
+                "Voigtländer Bessa R2M".into(),
+                crate::gear::CameraDetails {
+                    serial: Some("01234567".into()),
+                    owner: Some("Simon Sigurdhsson".into()),
+                },
+            )]
+            .into(),
+            lens: [(
+                "Color Skopar 35/2.5 Pancake II".into(),
+                crate::gear::LensDetails {
+                    serial: Some("7654321".into()),
+                },
+            )]
+            .into(),
+        };
+
+        assert_eq!(
+            roll.clone().with_gear(&inventory),
+            Roll {
+                camera_serial: Some("01234567".into()),
+                frames: vec![
+                    None,
+                    Some(Frame {
+                        lens_serial: Some("7654321".into()),
+                        ..frame.clone()
+                    })
+                ],
+                ..roll.clone()
+            }
+        );
+        assert_eq!(roll.clone().with_gear(&Inventory::default()), roll);
+    }
+
     #[test]
     fn convert_lightme_frame() {
         let base_frame = lightme::Frame {
@@ -643,6 +1473,7 @@ mod tests {
             document_name: Some("Ilford SFX 200".into()),
             exposure_time: Some(num_rational::Rational32::new(1, 125).into()),
             f_number: Some(rust_decimal::Decimal::new(8, 0).into()),
+            flash: Some(Flash::from_exif(0x10)),
             focal_length: Some(35.),
             focal_length_equiv: Some(35.),
             gps_latitude: 57.700833333333335,
@@ -651,7 +1482,9 @@ mod tests {
             iso_speed: 200,
             lens_make: Some("Voigtländer".into()),
             lens_model: Some("35mm f/2,5 Color Skopar Pancake II (35mm)".into()),
+            light_source: None,
             make: Some("Voigtländer".into()),
+            metering_mode: Some(MeteringMode::Spot),
             model: Some("Bessa R2M (Voigtländer)".into()),
             reel_name: Some("A0020".into()),
             user_comment: Some(lightme::Notes {
@@ -666,10 +1499,14 @@ mod tests {
             }),
         };
         let expected = Frame {
+            film: None,
+            speed: None,
+            development: None,
             lens: Some(Lens::MakeModel {
                 make: "Voigtländer".into(),
                 model: "35mm f/2,5 Color Skopar Pancake II".into(),
             }),
+            lens_serial: None,
             aperture: base_frame.f_number,
             shutter_speed: base_frame.exposure_time,
             focal_length: Some(FocalLength {
@@ -677,6 +1514,9 @@ mod tests {
                 equiv: Some(rust_decimal::Decimal::new(35, 0)),
             }),
             compensation: None,
+            flash: base_frame.flash,
+            metering: base_frame.metering_mode,
+            light_source: None,
             datetime: base_frame.date_time_original.clone().into(),
             position: Position {
                 lat: base_frame.gps_latitude,
@@ -744,6 +1584,7 @@ mod tests {
             document_name: Some("Ilford SFX 200".into()),
             exposure_time: Some(num_rational::Rational32::new(1, 125).into()),
             f_number: Some(rust_decimal::Decimal::new(8, 0).into()),
+            flash: None,
             focal_length: Some(35.),
             focal_length_equiv: Some(35.),
             gps_latitude: 57.700833333333335,
@@ -752,7 +1593,9 @@ mod tests {
             iso_speed: 200,
             lens_make: Some("Voigtländer".into()),
             lens_model: Some("35mm f/2,5 Color Skopar Pancake II (35mm)".into()),
+            light_source: None,
             make: Some("Voigtländer".into()),
+            metering_mode: None,
             model: Some("Bessa R2M (Voigtländer)".into()),
             reel_name: Some("A0020".into()),
             user_comment: Some(lightme::Notes {
@@ -768,19 +1611,27 @@ mod tests {
         };
         let expected = Roll {
             id: base_frame.reel_name.clone().unwrap().into(),
+            sheet_film: false,
             film: Some(Film("Ilford SFX 200".into())),
             speed: FilmSpeed::from_din(24), // ISO 200/24°
             camera: Some(Camera::MakeModel {
                 make: "Voigtländer".into(),
                 model: "Bessa R2M".into(),
             }),
+            camera_serial: None,
+            camera_owner: None,
             load: base_frame.user_comment.clone().unwrap().load_date.into(),
             unload: base_frame.user_comment.clone().unwrap().unload_date.into(),
+            development: None,
             frames: vec![Some(Frame {
+                film: None,
+                speed: None,
+                development: None,
                 lens: Some(Lens::MakeModel {
                     make: "Voigtländer".into(),
                     model: "35mm f/2,5 Color Skopar Pancake II".into(),
                 }),
+                lens_serial: None,
                 aperture: base_frame.f_number,
                 shutter_speed: base_frame.exposure_time,
                 focal_length: Some(FocalLength {
@@ -788,6 +1639,9 @@ mod tests {
                     equiv: Some(rust_decimal::Decimal::new(35, 0)),
                 }),
                 compensation: None,
+                flash: None,
+                metering: None,
+                light_source: None,
                 datetime: base_frame.date_time_original.clone().into(),
                 position: Position {
                     lat: base_frame.gps_latitude,
@@ -848,4 +1702,82 @@ mod tests {
             })
         );
     }
+
+    /// Read all rolls from the given test data file
+    fn read_rolls<F, I>(path: &str, read: F) -> Vec<Roll>
+    where
+        F: FnOnce(std::io::BufReader<std::fs::File>) -> I,
+        I: Iterator<Item = Result<Roll, SourceError>>,
+    {
+        let file = std::fs::File::open(path).unwrap();
+        read(std::io::BufReader::new(file)).try_collect().unwrap()
+    }
+
+    #[test]
+    fn write_filmrolls() {
+        let mut rolls = read_rolls("tests/data/filmrolls.xml", from_filmrolls);
+        let mut buf = Vec::new();
+        to_filmrolls(&rolls, &mut buf).unwrap();
+        assert_eq!(from_filmrolls(&buf[..]).try_collect(), Ok(rolls.clone()));
+
+        // Flash, metering & light source are appended to the note
+        let frame = rolls[0].frames[0].as_mut().unwrap();
+        frame.flash = Some(Flash::from_exif(0x19));
+        frame.metering = Some(MeteringMode::Incident);
+        frame.light_source = Some(LightSource::Cloudy);
+        frame.note = Some("Portrait".into());
+        let mut buf = Vec::new();
+        to_filmrolls(&rolls, &mut buf).unwrap();
+        let output: Vec<_> = from_filmrolls(&buf[..]).try_collect().unwrap();
+        let frame = output[0].frames[0].as_ref().unwrap();
+        assert_eq!(frame.flash, Some(Flash::from_exif(0x19)));
+        assert_eq!(frame.metering, Some(MeteringMode::Incident));
+        assert_eq!(frame.light_source, Some(LightSource::Cloudy));
+        assert_eq!(
+            frame.note.as_deref(),
+            Some("Portrait\nflash: Auto, Fired\nmetering: Incident\nlight source: Cloudy")
+        );
+    }
+
+    #[test]
+    fn write_lightme() {
+        let rolls = read_rolls("tests/data/lightme.json", from_lightme);
+        let mut buf = Vec::new();
+        to_lightme(&rolls, &mut buf).unwrap();
+        let output: Vec<_> = from_lightme(&buf[..]).try_collect().unwrap();
+        let position = |roll: &Roll| roll.frames[0].as_ref().unwrap().position;
+        assert!((position(&output[0]).lat - position(&rolls[0]).lat).abs() < 1e-6);
+        assert!((position(&output[0]).lon - position(&rolls[0]).lon).abs() < 1e-6);
+        let mut expected = rolls[0].clone();
+        expected.frames[0].as_mut().unwrap().position = position(&output[0]);
+        assert_eq!(output, [expected]);
+    }
+
+    #[test]
+    fn write_toml() {
+        let records: Records =
+            toml::from_str(&std::fs::read_to_string("tests/data/development.toml").unwrap())
+                .unwrap();
+        let rolls = read_rolls("tests/data/filmrolls.xml", from_filmrolls)
+            .into_iter()
+            .map(|roll| roll.with_development(&records))
+            .chain(read_rolls("tests/data/sheets.toml", from_toml))
+            .collect_vec();
+        let mut buf = Vec::new();
+        to_toml(&rolls, &mut buf).unwrap();
+        assert_eq!(from_toml(&buf[..]).try_collect(), Ok(rolls));
+    }
+
+    #[test]
+    fn write_csv() {
+        let rolls = read_rolls("tests/data/sheets.toml", from_toml);
+        let mut buf = Vec::new();
+        to_csv(&rolls, &mut buf).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+        let lines = output.lines().collect_vec();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("roll,sheet_film,film,speed,camera,"));
+        assert!(lines[2].starts_with("S0001,true,Ilford HP5 Plus,400,Chamonix 045N-2,"));
+        assert!(lines[2].ends_with(",Kodak HC-110,H,6:00,20,,-1,,,Front standard tilted"));
+    }
 }
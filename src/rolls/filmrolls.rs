@@ -1,15 +1,15 @@
-//! Deserialization for *Film Rolls* XML data
+//! Serialization & deserialization for *Film Rolls* XML data
 use chrono::NaiveDateTime;
 use quick_xml::serde_helpers::text_content;
-use serde::Deserialize;
-use serde_with::DeserializeFromStr;
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 
 use crate::types::{Aperture, ExposureBias, ShutterSpeed};
 
 /// Outer `<data>` element
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize, Default)]
-#[serde(rename_all = "camelCase")]
+#[derive(Deserialize, Serialize, Default)]
+#[serde(rename = "data", rename_all = "camelCase")]
 pub(super) struct Data<'a> {
     #[serde(default)]
     pub cameras: Cameras<'a>,
@@ -23,7 +23,7 @@ pub(super) struct Data<'a> {
 
 /// Camera list element (`<cameras>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct Cameras<'a> {
     #[serde(default)]
@@ -32,7 +32,7 @@ pub(super) struct Cameras<'a> {
 
 /// Camera container (`<camera>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 pub(super) struct Camera<'a> {
     #[serde(rename = "$text")]
     pub value: Text<'a>,
@@ -40,7 +40,7 @@ pub(super) struct Camera<'a> {
 
 /// Lens list element (`<lenses>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct Lenses<'a> {
     #[serde(default)]
@@ -49,7 +49,7 @@ pub(super) struct Lenses<'a> {
 
 /// Lens container (`<lens>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 pub(super) struct Lens<'a> {
     #[serde(rename = "$text")]
     pub value: Text<'a>,
@@ -57,7 +57,7 @@ pub(super) struct Lens<'a> {
 
 /// Accessory list element (`<accessories>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct Accessories<'a> {
     #[serde(default)]
@@ -66,7 +66,7 @@ pub(super) struct Accessories<'a> {
 
 /// Accessory container (`<accessory>`)
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 pub(super) struct Accessory<'a> {
     #[serde(rename = "$text")]
     pub value: Text<'a>,
@@ -74,7 +74,7 @@ pub(super) struct Accessory<'a> {
 
 /// Film roll list element (`<filmRolls>`)
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct FilmRolls<'a> {
     #[serde(default)]
@@ -83,7 +83,7 @@ pub(super) struct FilmRolls<'a> {
 
 /// Film roll container (`<filmRoll>`)
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct FilmRoll<'a> {
     #[serde(with = "text_content")]
@@ -103,7 +103,7 @@ pub(super) struct FilmRoll<'a> {
 
 /// Frame list element (`<frames>`)
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct Frames<'a> {
     #[serde(default)]
@@ -112,7 +112,7 @@ pub(super) struct Frames<'a> {
 
 /// Frame container (`<frame>`)
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(super) struct Frame<'a> {
     #[serde(with = "text_content")]
@@ -146,7 +146,7 @@ pub(super) type Text<'a> = std::borrow::Cow<'a, str>;
 /// values without timezone but *with* fractional seconds, as well as supporting
 /// plain ISO8601 dates without an associated time (falling back to midnight).
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
-#[derive(DeserializeFromStr)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
 pub(super) struct XmlDateTime(NaiveDateTime);
 
 impl From<XmlDateTime> for NaiveDateTime {
@@ -176,6 +176,12 @@ impl std::str::FromStr for XmlDateTime {
     }
 }
 
+impl std::fmt::Display for XmlDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.format("%Y-%m-%dT%H:%M:%S%.f").fmt(f)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
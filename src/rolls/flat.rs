@@ -0,0 +1,54 @@
+//! Serialization for flat CSV data
+//!
+//! Every logged frame is written as a single row, repeating the roll data
+//! (and the development record, unless overridden per frame) on each row.
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use super::{Camera, Film, Lens};
+use crate::development::DevelopmentTime;
+use crate::types::{
+    Aperture, ExposureBias, FilmSpeed, Flash, LightSource, MeteringMode, ShutterSpeed,
+};
+
+/// Frame row
+///
+/// Rolls without any logged frames are written as a single row, in which
+/// case all frame-specific columns are left empty.
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub(super) struct Row<'a> {
+    pub roll: &'a str,
+    pub sheet_film: bool,
+    pub film: Option<&'a Film>,
+    pub speed: FilmSpeed,
+    pub camera: Option<&'a Camera>,
+    pub camera_serial: Option<&'a str>,
+    pub camera_owner: Option<&'a str>,
+    pub load: NaiveDateTime,
+    pub unload: NaiveDateTime,
+    pub frame: Option<usize>,
+    pub lens: Option<&'a Lens>,
+    pub lens_serial: Option<&'a str>,
+    pub aperture: Option<Aperture>,
+    pub shutter_speed: Option<ShutterSpeed>,
+    pub focal_length: Option<Decimal>,
+    pub focal_length_equiv: Option<Decimal>,
+    pub compensation: Option<ExposureBias>,
+    pub flash: Option<Flash>,
+    pub metering: Option<MeteringMode>,
+    pub light_source: Option<LightSource>,
+    pub date: Option<NaiveDateTime>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub developer: Option<&'a str>,
+    pub dilution: Option<&'a str>,
+    pub development_time: Option<DevelopmentTime>,
+    pub temperature: Option<Decimal>,
+    pub agitation: Option<&'a str>,
+    pub push: Option<i8>,
+    pub lab: Option<&'a str>,
+    pub development_date: Option<NaiveDate>,
+    pub note: Option<&'a str>,
+}
@@ -1,39 +1,60 @@
-//! Deserialization for *lightme* JSON data
+//! Serialization & deserialization for *lightme* JSON data
 use chrono::NaiveDateTime;
-use serde::Deserialize;
-use serde_with::{serde_as, DeserializeFromStr};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DeserializeFromStr, PickFirst, SerializeDisplay};
 
-use crate::types::{Aperture, ShutterSpeed};
+use crate::types::{Aperture, ExifCode, Flash, LightSource, MeteringMode, ShutterSpeed};
 
 /// Outer JSON array
 pub(super) type Data<'a> = Vec<Frame<'a>>;
 
 /// Frame JSON object
+#[serde_with::skip_serializing_none]
 #[serde_as]
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub(super) struct Frame<'a> {
     pub date_time_original: CustomDateTime,
     pub description: Option<Text<'a>>,
     pub document_name: Option<Text<'a>>,
-    #[serde_as(as = "Option<f64>")]
+    #[serde(
+        default,
+        deserialize_with = "serde_with::As::<Option<f64>>::deserialize",
+        serialize_with = "serialize_exposure_time"
+    )]
     pub exposure_time: Option<ShutterSpeed>,
     #[serde_as(as = "Option<f64>")]
     pub f_number: Option<Aperture>,
+    #[serde_as(as = "Option<PickFirst<(u16, _)>>")]
+    pub flash: Option<Flash>,
     pub focal_length: Option<f64>,
     #[serde(rename = "FocalLengthIn35mmFormat")]
     pub focal_length_equiv: Option<f64>,
-    #[serde(rename = "GPSLatitude", deserialize_with = "deserialize_gps_coord")]
+    #[serde(
+        rename = "GPSLatitude",
+        deserialize_with = "deserialize_gps_coord",
+        serialize_with = "serialize_gps_latitude"
+    )]
     pub gps_latitude: f64,
-    #[serde(rename = "GPSLongitude", deserialize_with = "deserialize_gps_coord")]
+    #[serde(
+        rename = "GPSLongitude",
+        deserialize_with = "deserialize_gps_coord",
+        serialize_with = "serialize_gps_longitude"
+    )]
     pub gps_longitude: f64,
     pub image_number: usize,
     #[serde(rename = "ISOSpeed")]
     pub iso_speed: u32,
     pub lens_make: Option<Text<'a>>,
+    #[serde_as(as = "ExifCode")]
+    #[serde(default)]
+    pub light_source: Option<LightSource>,
     pub lens_model: Option<Text<'a>>,
     pub make: Option<Text<'a>>,
+    #[serde_as(as = "ExifCode")]
+    #[serde(default)]
+    pub metering_mode: Option<MeteringMode>,
     pub model: Option<Text<'a>>,
     pub reel_name: Option<Text<'a>>,
     pub user_comment: Option<Notes>,
@@ -44,8 +65,11 @@ pub(super) struct Frame<'a> {
 /// The user comment field provided by lightme contains the roll load/unload
 /// dates, so we must parse its contents to extract this information. It also
 /// includes roll & development notes, but these are discarded.
+///
+/// When serializing, the dates are written in the (lossless) EXIF-like
+/// date/time format rather than the human-readable one.
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
-#[derive(DeserializeFromStr)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
 pub(super) struct Notes {
     pub load_date: CustomDateTime,
     pub unload_date: CustomDateTime,
@@ -65,6 +89,16 @@ impl std::str::FromStr for Notes {
     }
 }
 
+impl std::fmt::Display for Notes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "load_date:\n{}\nunload_date:\n{}",
+            self.load_date, self.unload_date
+        )
+    }
+}
+
 /// Copy-on-write text value from the JSON source
 pub(super) type Text<'a> = std::borrow::Cow<'a, str>;
 
@@ -73,7 +107,7 @@ pub(super) type Text<'a> = std::borrow::Cow<'a, str>;
 /// This allows us to parse both EXIF-like date/time values *and* the
 /// human-readable date/time format used in the user comment (see `Notes`).
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
-#[derive(DeserializeFromStr)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
 pub(super) struct CustomDateTime(NaiveDateTime);
 
 impl From<CustomDateTime> for NaiveDateTime {
@@ -102,6 +136,12 @@ impl std::str::FromStr for CustomDateTime {
     }
 }
 
+impl std::fmt::Display for CustomDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.format("%Y:%m:%d %H:%M:%S").fmt(f)
+    }
+}
+
 /// Convert textual GPS coordinates to decimal latitude/longitude
 fn deserialize_gps_coord<'de, D>(de: D) -> Result<f64, D::Error>
 where
@@ -135,6 +175,49 @@ where
     .to_ddeg_angle())
 }
 
+/// Convert decimal latitude to textual GPS coordinates
+fn serialize_gps_latitude<S>(value: &f64, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serialize_gps_coord(dms_coordinates::DMS::from_ddeg_latitude(*value), ser)
+}
+
+/// Convert a (manual) shutter speed to its value in seconds
+fn serialize_exposure_time<S>(value: &Option<ShutterSpeed>, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use num_traits::ToPrimitive;
+    let seconds = value.and_then(|value| match value {
+        ShutterSpeed::Manual(value) => value.to_f64(),
+        ShutterSpeed::AperturePriority => None,
+    });
+    seconds.serialize(ser)
+}
+
+/// Convert decimal longitude to textual GPS coordinates
+fn serialize_gps_longitude<S>(value: &f64, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serialize_gps_coord(dms_coordinates::DMS::from_ddeg_longitude(*value), ser)
+}
+
+/// Format DMS coordinates, e.g. `57deg 42' 2.76" N`
+fn serialize_gps_coord<S>(dms: dms_coordinates::DMS, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    ser.collect_str(&format_args!(
+        "{}deg {}' {:.2}\" {}",
+        dms.degrees,
+        dms.minutes,
+        dms.seconds,
+        dms.cardinal.map(|c| c.to_string()).unwrap_or_default()
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,6 +277,7 @@ mod tests {
                 "DocumentName" : "Ilford SFX 200",
                 "ExposureTime" : 0.008,
                 "FileSource" : 1,
+                "Flash" : "Off, Did not fire",
                 "FNumber" : 8,
                 "FocalLength" : 35,
                 "FocalLengthIn35mmFormat" : 35,
@@ -207,7 +291,9 @@ mod tests {
                 "ISOSpeed" : 200,
                 "LensMake" : "Voigtländer",
                 "LensModel" : "35mm f\/2,5 Color Skopar Pancake II (35mm)",
+                "LightSource" : 1,
                 "Make" : "Voigtländer",
+                "MeteringMode" : 3,
                 "Model" : "Bessa R2M (Voigtländer)",
                 "Notes" : "",
                 "ReelName" : "A0020",
@@ -229,6 +315,10 @@ mod tests {
                 document_name: Some("Ilford SFX 200".into()),
                 exposure_time: Some(Rational32::new(1, 125).into()),
                 f_number: Some(Decimal::new(8, 0).into()),
+                flash: Some(Flash {
+                    fired: false,
+                    mode: Some(crate::types::FlashMode::Suppressed),
+                }),
                 focal_length: Some(35.),
                 focal_length_equiv: Some(35.),
                 gps_latitude: 57.700833333333335,
@@ -237,7 +327,9 @@ mod tests {
                 iso_speed: 200,
                 lens_make: Some("Voigtländer".into()),
                 lens_model: Some("35mm f/2,5 Color Skopar Pancake II (35mm)".into()),
+                light_source: Some(LightSource::Daylight),
                 make: Some("Voigtländer".into()),
+                metering_mode: Some(MeteringMode::Spot),
                 model: Some("Bessa R2M (Voigtländer)".into()),
                 reel_name: Some("A0020".into()),
                 user_comment: Some(Notes {
@@ -254,4 +346,61 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn unknown_values() -> Result<(), Error> {
+        let parse = |metering: &str, light_source: &str| {
+            let json = format!(
+                r#"
+                {{
+                  "DateTimeOriginal" : "2022:04:30 18:29:15",
+                  "GPSLatitude" : "57deg 42' 3\" N",
+                  "GPSLongitude" : "11deg 58' 27\" E",
+                  "ImageNumber" : 1,
+                  "ISOSpeed" : 200,
+                  "LightSource" : {light_source},
+                  "MeteringMode" : {metering}
+                }}
+                "#
+            );
+            from_str::<Frame>(&json).map(|frame| (frame.metering_mode, frame.light_source))
+        };
+
+        // Unknown values are valid, but carry no information
+        assert_eq!(parse("0", "0")?, (None, None));
+        assert_eq!(parse(r#""Unknown""#, r#""Unknown""#)?, (None, None));
+        assert_eq!(parse("255", "255")?, (None, Some(LightSource::Other)));
+        assert_eq!(
+            parse(r#""Spot""#, r#""Cloudy""#)?,
+            (Some(MeteringMode::Spot), Some(LightSource::Cloudy))
+        );
+        assert!(parse("7", "0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_codes() -> Result<(), Error> {
+        let frame = from_str::<Frame>(
+            r#"
+            {
+              "DateTimeOriginal" : "2022:04:30 18:29:15",
+              "ExposureTime" : 0.008,
+              "Flash" : 16,
+              "GPSLatitude" : "57deg 42' 3\" N",
+              "GPSLongitude" : "11deg 58' 27\" E",
+              "ImageNumber" : 1,
+              "ISOSpeed" : 200,
+              "LightSource" : 1,
+              "MeteringMode" : 3
+            }
+            "#,
+        )?;
+        let value = serde_json::to_value(&frame)?;
+        assert_eq!(value["ExposureTime"], 0.008);
+        assert_eq!(value["Flash"], 16);
+        assert_eq!(value["LightSource"], 1);
+        assert_eq!(value["MeteringMode"], 3);
+        assert_eq!(serde_json::from_value::<Frame>(value)?, frame);
+        Ok(())
+    }
 }
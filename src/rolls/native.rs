@@ -0,0 +1,229 @@
+//! Serialization & deserialization for native TOML roll data
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, PickFirst};
+
+use crate::development::Development;
+use crate::types::{
+    Aperture, ExposureBias, Flash, LightSource, MeteringMode, ShutterSpeed, TomlDateTime,
+};
+
+/// Outer TOML document
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+#[derive(Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub(super) struct Data {
+    #[serde(default)]
+    pub roll: Vec<FilmRoll>,
+}
+
+/// Film roll table (`[[roll]]`)
+///
+/// A "roll" may also represent a box of sheet film, in which case each
+/// frame corresponds to one sheet and may carry its own film and speed.
+/// Load and unload dates are optional for sheet film; if missing, the
+/// first and last exposure dates will be used instead.
+#[serde_with::skip_serializing_none]
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FilmRoll {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sheet_film: bool,
+    pub film: Option<String>,
+    pub speed: Option<u32>,
+    pub camera: Option<String>,
+    pub camera_serial: Option<String>,
+    pub camera_owner: Option<String>,
+    pub load: Option<TomlDateTime>,
+    pub unload: Option<TomlDateTime>,
+    pub development: Option<Development>,
+    #[serde(default)]
+    pub frame: Vec<Frame>,
+}
+
+/// Frame table (`[[roll.frame]]`)
+///
+/// For sheet film, the holder ID is used as the frame number.
+#[serde_with::skip_serializing_none]
+#[serde_as]
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct Frame {
+    #[serde(alias = "holder")]
+    pub number: usize,
+    pub film: Option<String>,
+    pub speed: Option<u32>,
+    pub development: Option<Development>,
+    pub lens: Option<String>,
+    pub lens_serial: Option<String>,
+    #[serde_as(as = "Option<PickFirst<(_, f64)>>")]
+    pub aperture: Option<Aperture>,
+    #[serde_as(as = "Option<PickFirst<(_, f64)>>")]
+    pub shutter_speed: Option<ShutterSpeed>,
+    pub focal_length: Option<f64>,
+    pub focal_length_equiv: Option<f64>,
+    pub compensation: Option<ExposureBias>,
+    #[serde_as(as = "Option<PickFirst<(_, bool)>>")]
+    pub flash: Option<Flash>,
+    pub metering: Option<MeteringMode>,
+    pub light_source: Option<LightSource>,
+    pub date: TomlDateTime,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub note: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use num_rational::Rational32;
+    use pretty_assertions::assert_eq;
+    use rust_decimal::Decimal;
+    use toml::{de::Error, from_str};
+
+    #[test]
+    fn empty_document() -> Result<(), Error> {
+        assert_eq!(from_str::<Data>("")?, Default::default());
+        Ok(())
+    }
+
+    #[test]
+    fn full_document() -> Result<(), Error> {
+        assert_eq!(
+            from_str::<Data>(
+                r#"
+                [[roll]]
+                id = "A0012"
+                film = "Ilford Delta 100"
+                speed = 100
+                camera = "Voigtländer Bessa R2M"
+                load = 2016-03-28T15:16:36Z
+                unload = 2016-05-21T14:13:15Z
+
+                [[roll.frame]]
+                number = 1
+                lens = "Color Skopar 35/2.5 Pancake II"
+                lens_serial = "7654321"
+                aperture = 5.6
+                shutter_speed = "1/500"
+                focal_length = 35
+                date = 2016-05-13T14:12:40Z
+                latitude = 57.700767
+                longitude = 11.953715
+
+                [[roll]]
+                id = "S0001"
+                sheet_film = true
+                camera = "Chamonix 045N-2"
+                camera_serial = "45N2-0123"
+                camera_owner = "Göteborgs Fotoklubb"
+
+                [[roll.frame]]
+                holder = 3
+                film = "Ilford FP4 Plus"
+                speed = 125
+                development = { developer = "Kodak HC-110", dilution = "H" }
+                aperture = "22"
+                shutter_speed = 0.5
+                compensation = "1/3"
+                flash = true
+                metering = "incident"
+                light_source = "cloudy"
+                date = 2023-06-01
+                latitude = 57.700767
+                longitude = 11.953715
+                note = "Front standard tilted"
+                "#
+            )?,
+            Data {
+                roll: vec![
+                    FilmRoll {
+                        id: "A0012".into(),
+                        sheet_film: false,
+                        film: Some("Ilford Delta 100".into()),
+                        speed: Some(100),
+                        camera: Some("Voigtländer Bessa R2M".into()),
+                        camera_serial: None,
+                        camera_owner: None,
+                        load: NaiveDate::from_ymd_opt(2016, 3, 28)
+                            .and_then(|d| d.and_hms_opt(15, 16, 36))
+                            .map(Into::into),
+                        unload: NaiveDate::from_ymd_opt(2016, 5, 21)
+                            .and_then(|d| d.and_hms_opt(14, 13, 15))
+                            .map(Into::into),
+                        development: None,
+                        frame: vec![Frame {
+                            number: 1,
+                            film: None,
+                            speed: None,
+                            development: None,
+                            lens: Some("Color Skopar 35/2.5 Pancake II".into()),
+                            lens_serial: Some("7654321".into()),
+                            aperture: Some(Decimal::new(56, 1).into()),
+                            shutter_speed: Some(Rational32::new(1, 500).into()),
+                            focal_length: Some(35.),
+                            focal_length_equiv: None,
+                            compensation: None,
+                            flash: None,
+                            metering: None,
+                            light_source: None,
+                            date: NaiveDate::from_ymd_opt(2016, 5, 13)
+                                .and_then(|d| d.and_hms_opt(14, 12, 40))
+                                .unwrap()
+                                .into(),
+                            latitude: 57.700767,
+                            longitude: 11.953715,
+                            note: None,
+                        }],
+                    },
+                    FilmRoll {
+                        id: "S0001".into(),
+                        sheet_film: true,
+                        film: None,
+                        speed: None,
+                        camera_serial: Some("45N2-0123".into()),
+                        camera_owner: Some("Göteborgs Fotoklubb".into()),
+                        camera: Some("Chamonix 045N-2".into()),
+                        load: None,
+                        unload: None,
+                        development: None,
+                        frame: vec![Frame {
+                            number: 3,
+                            film: Some("Ilford FP4 Plus".into()),
+                            speed: Some(125),
+                            development: Some(Development {
+                                developer: Some("Kodak HC-110".into()),
+                                dilution: Some("H".into()),
+                                ..Default::default()
+                            }),
+                            lens: None,
+                            lens_serial: None,
+                            aperture: Some(Decimal::new(22, 0).into()),
+                            shutter_speed: Some(Rational32::new(1, 2).into()),
+                            focal_length: None,
+                            focal_length_equiv: None,
+                            compensation: Some(Rational32::new(1, 3).into()),
+                            flash: Some(Flash {
+                                fired: true,
+                                mode: None,
+                            }),
+                            metering: Some(MeteringMode::Incident),
+                            light_source: Some(LightSource::Cloudy),
+                            date: NaiveDate::from_ymd_opt(2023, 6, 1)
+                                .map(|d| d.and_time(chrono::NaiveTime::default()))
+                                .unwrap()
+                                .into(),
+                            latitude: 57.700767,
+                            longitude: 11.953715,
+                            note: Some("Front standard tilted".into()),
+                        }],
+                    },
+                ]
+            }
+        );
+        Ok(())
+    }
+}
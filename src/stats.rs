@@ -0,0 +1,370 @@
+//! Shooting statistics
+//!
+//! This module summarizes a set of film rolls: how many frames were shot
+//! using each camera, lens and film stock (and in each year), how apertures,
+//! shutter speeds and focal lengths are distributed, and how long rolls stay
+//! in the camera. Only logged frames are counted, and frames lacking a value
+//! (or using an automatic exposure mode) are left out of the corresponding
+//! counts.
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+use itertools::Itertools;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+
+use crate::rolls::{Film, Frame, Roll};
+use crate::types::{Aperture, ShutterSpeed};
+
+/// Number of frames sharing a value
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize)]
+pub struct Count<T> {
+    pub value: T,
+    pub frames: usize,
+}
+
+/// Minimum, mean & maximum of a set of values
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct Summary {
+    pub min: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    /// Summarize the given values, if there are any
+    fn of<I>(values: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        let (count, sum, min, max) = values.into_iter().fold(
+            (0, 0.0, f64::INFINITY, f64::NEG_INFINITY),
+            |(count, sum, min, max), value| {
+                (count + 1, sum + value, min.min(value), max.max(value))
+            },
+        );
+        (count > 0).then(|| Self {
+            min,
+            mean: sum / f64::from(count),
+            max,
+        })
+    }
+}
+
+/// Most-used film stock of a camera
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Serialize)]
+pub struct CameraFilm {
+    pub camera: String,
+    pub film: Film,
+    pub frames: usize,
+}
+
+/// Shooting statistics of a set of film rolls
+///
+/// Cameras, lenses and film stocks are sorted by decreasing number of frames,
+/// while years and exposure settings are sorted by value. Cameras & lenses are
+/// counted by name, since different roll data formats may split the name of
+/// the same camera or lens into make & model differently.
+#[derive(Clone, PartialEq, Debug)]
+#[derive(Serialize)]
+pub struct Statistics {
+    pub rolls: usize,
+    pub frames: usize,
+    pub frames_per_roll: Option<Summary>,
+    pub days_loaded: Option<Summary>,
+    pub cameras: Vec<Count<String>>,
+    pub lenses: Vec<Count<String>>,
+    pub films: Vec<Count<Film>>,
+    pub years: Vec<Count<i32>>,
+    pub apertures: Vec<Count<Aperture>>,
+    pub shutter_speeds: Vec<Count<ShutterSpeed>>,
+    pub focal_lengths: Vec<Count<f64>>,
+    pub camera_films: Vec<CameraFilm>,
+}
+
+impl Statistics {
+    /// Compute statistics of the given rolls
+    pub fn new<'a, I>(rolls: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Roll>,
+    {
+        let rolls = rolls.into_iter().collect_vec();
+        let frames = rolls
+            .iter()
+            .flat_map(|roll| {
+                roll.frames
+                    .iter()
+                    .flatten()
+                    .map(move |frame| (*roll, frame))
+            })
+            .collect_vec();
+        let film =
+            |(roll, frame): &(&'a Roll, &'a Frame)| frame.film.as_ref().or(roll.film.as_ref());
+
+        let camera_films = histogram(
+            frames
+                .iter()
+                .filter_map(|pair| Some((pair.0.camera.as_ref()?.to_string(), film(pair)?))),
+        )
+        .into_iter()
+        .chunk_by(|count| count.value.0.clone())
+        .into_iter()
+        .filter_map(|(camera, counts)| {
+            let count =
+                counts.max_by(|a, b| a.frames.cmp(&b.frames).then(b.value.cmp(&a.value)))?;
+            Some(CameraFilm {
+                camera,
+                film: count.value.1.clone(),
+                frames: count.frames,
+            })
+        })
+        .collect();
+
+        Self {
+            rolls: rolls.len(),
+            frames: frames.len(),
+            frames_per_roll: Summary::of(
+                rolls
+                    .iter()
+                    .filter_map(|roll| u32::try_from(roll.frames.iter().flatten().count()).ok())
+                    .map(f64::from),
+            ),
+            days_loaded: Summary::of(rolls.iter().map(|roll| {
+                let seconds = (roll.unload - roll.load).num_seconds();
+                seconds.to_f64().unwrap_or_default() / 86400.0
+            })),
+            cameras: ranking(
+                frames
+                    .iter()
+                    .filter_map(|(roll, _)| Some(roll.camera.as_ref()?.to_string())),
+            ),
+            lenses: ranking(
+                frames
+                    .iter()
+                    .filter_map(|(_, frame)| Some(frame.lens.as_ref()?.to_string())),
+            ),
+            films: ranking(frames.iter().filter_map(film).cloned()),
+            years: histogram(frames.iter().map(|(_, frame)| frame.datetime.year())),
+            apertures: histogram(
+                frames
+                    .iter()
+                    .filter_map(|(_, frame)| frame.aperture)
+                    .filter(|aperture| *aperture != Aperture::ShutterPriority),
+            ),
+            shutter_speeds: histogram(
+                frames
+                    .iter()
+                    .filter_map(|(_, frame)| frame.shutter_speed)
+                    .filter(|speed| *speed != ShutterSpeed::AperturePriority),
+            ),
+            focal_lengths: histogram(
+                frames
+                    .iter()
+                    .filter_map(|(_, frame)| frame.focal_length)
+                    .map(|focal_length| focal_length.real.normalize()),
+            )
+            .into_iter()
+            .map(|count| Count {
+                value: count.value.to_f64().unwrap_or_default(),
+                frames: count.frames,
+            })
+            .collect(),
+            camera_films,
+        }
+    }
+}
+
+/// Count the number of occurrences of each value, sorted by value
+fn histogram<T: Ord, I: IntoIterator<Item = T>>(values: I) -> Vec<Count<T>> {
+    values
+        .into_iter()
+        .fold(BTreeMap::new(), |mut counts, value| {
+            *counts.entry(value).or_default() += 1;
+            counts
+        })
+        .into_iter()
+        .map(|(value, frames)| Count { value, frames })
+        .collect()
+}
+
+/// Count the number of occurrences of each value, sorted by decreasing count
+fn ranking<T: Ord, I: IntoIterator<Item = T>>(values: I) -> Vec<Count<T>> {
+    let mut counts = histogram(values);
+    counts.sort_by_key(|count| std::cmp::Reverse(count.frames));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rolls::fixtures;
+    use crate::rolls::Camera;
+    use crate::types::FocalLength;
+    use chrono::NaiveDateTime;
+    use pretty_assertions::assert_eq;
+    use rust_decimal::Decimal;
+
+    fn frame(film: Option<&str>, lens: &str, aperture: Aperture, date: &str) -> Option<Frame> {
+        Some(Frame {
+            film: film.map(Film::from),
+            lens: lens.try_into().ok(),
+            aperture: Some(aperture),
+            shutter_speed: Some(ShutterSpeed::from(num_rational::Ratio::new(1, 125))),
+            focal_length: Some(FocalLength {
+                real: Decimal::new(350, 1),
+                equiv: None,
+            }),
+            ..fixtures::frame(date)
+        })
+    }
+
+    fn roll(id: &str, film: Option<&str>, days: i64, frames: Vec<Option<Frame>>) -> Roll {
+        let load: NaiveDateTime = "2016-03-28T12:00:00".parse().unwrap();
+        Roll {
+            sheet_film: film.is_none(),
+            film: film.map(Film::from),
+            load,
+            unload: load + chrono::TimeDelta::days(days),
+            ..fixtures::roll(id, frames)
+        }
+    }
+
+    #[test]
+    fn empty_statistics() {
+        let stats = Statistics::new(&[]);
+        assert_eq!(stats.rolls, 0);
+        assert_eq!(stats.frames, 0);
+        assert_eq!(stats.frames_per_roll, None);
+        assert_eq!(stats.days_loaded, None);
+        assert!(stats.cameras.is_empty());
+        assert!(stats.camera_films.is_empty());
+    }
+
+    #[test]
+    fn roll_statistics() {
+        let f8 = Aperture::from(Decimal::new(8, 0));
+        let f56 = Aperture::from(Decimal::new(56, 1));
+        let mut rolls = [
+            roll(
+                "A0012",
+                Some("Ilford Delta 100"),
+                10,
+                vec![
+                    frame(None, "Color Skopar", f8, "2016-03-28T12:00:00"),
+                    None,
+                    frame(None, "Color Skopar", f56, "2016-04-01T12:00:00"),
+                    frame(
+                        None,
+                        "Nokton",
+                        Aperture::ShutterPriority,
+                        "2017-01-01T12:00:00",
+                    ),
+                ],
+            ),
+            roll(
+                "S0001",
+                None,
+                0,
+                vec![frame(
+                    Some("Ilford HP5 Plus"),
+                    "Nokton",
+                    f8,
+                    "2017-01-01T12:00:00",
+                )],
+            ),
+        ];
+        rolls[1].camera = Some(Camera::from_make_model(
+            Some("Voigtländer".into()),
+            "Bessa R2M".into(),
+        ));
+        let stats = Statistics::new(&rolls);
+        assert_eq!(stats.rolls, 2);
+        assert_eq!(stats.frames, 4);
+        assert_eq!(
+            stats.frames_per_roll,
+            Some(Summary {
+                min: 1.0,
+                mean: 2.0,
+                max: 3.0
+            })
+        );
+        assert_eq!(stats.days_loaded.map(|days| days.mean), Some(5.0));
+        assert_eq!(
+            stats.cameras,
+            [Count {
+                value: "Voigtländer Bessa R2M".to_owned(),
+                frames: 4
+            }]
+        );
+        assert_eq!(
+            stats.films,
+            [
+                Count {
+                    value: Film::from("Ilford Delta 100"),
+                    frames: 3
+                },
+                Count {
+                    value: Film::from("Ilford HP5 Plus"),
+                    frames: 1
+                }
+            ]
+        );
+        assert_eq!(
+            stats.lenses,
+            [
+                Count {
+                    value: "Color Skopar".to_owned(),
+                    frames: 2
+                },
+                Count {
+                    value: "Nokton".to_owned(),
+                    frames: 2
+                }
+            ]
+        );
+        assert_eq!(
+            stats.years,
+            [
+                Count {
+                    value: 2016,
+                    frames: 2
+                },
+                Count {
+                    value: 2017,
+                    frames: 2
+                }
+            ]
+        );
+        assert_eq!(
+            stats.apertures,
+            [
+                Count {
+                    value: f56,
+                    frames: 1
+                },
+                Count {
+                    value: f8,
+                    frames: 2
+                }
+            ]
+        );
+        assert_eq!(
+            stats.focal_lengths,
+            [Count {
+                value: 35.0,
+                frames: 4
+            }]
+        );
+        assert_eq!(
+            stats.camera_films,
+            [CameraFilm {
+                camera: "Voigtländer Bessa R2M".to_owned(),
+                film: Film::from("Ilford Delta 100"),
+                frames: 3
+            }]
+        );
+    }
+}
@@ -2,14 +2,15 @@
 use std::num::{NonZeroU8, TryFromIntError};
 
 use rust_decimal::{
-    prelude::{FromPrimitive, Zero},
+    prelude::{FromPrimitive, ToPrimitive, Zero},
     Decimal, MathematicalOps,
 };
-use serde::{Deserialize, Deserializer};
-use serde_with::{DeserializeAs, DeserializeFromStr};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{DeserializeAs, DeserializeFromStr, SerializeAs, SerializeDisplay};
 
 /// A geographical position
 #[derive(Copy, Clone, Default, PartialEq, PartialOrd, Debug)]
+#[derive(serde::Serialize)]
 pub struct Position {
     pub lat: f64,
     pub lon: f64,
@@ -36,6 +37,43 @@ impl std::fmt::Display for Position {
     }
 }
 
+impl Position {
+    /// Mean radius of the earth, in kilometres
+    const EARTH_RADIUS: f64 = 6371.0088;
+
+    /// Great-circle distance to another position, in kilometres
+    ///
+    /// This uses the haversine formula, assuming a spherical earth.
+    pub fn distance(&self, other: &Self) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * Self::EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+}
+
+impl std::str::FromStr for Position {
+    type Err = ParsePositionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParsePositionError(s.to_owned());
+        let (lat, lon) = s.split_once(',').ok_or_else(error)?;
+        let lat: f64 = lat.trim().parse().map_err(|_| error())?;
+        let lon: f64 = lon.trim().parse().map_err(|_| error())?;
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            return Err(error());
+        }
+        Ok(Self { lat, lon })
+    }
+}
+
+/// Error parsing a geographical position
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(thiserror::Error)]
+#[error("invalid position `{0}`, expected decimal degrees `LAT,LON`")]
+pub struct ParsePositionError(String);
+
 /// A focal length (both real and 35mm equivalent)
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
 pub struct FocalLength {
@@ -78,6 +116,19 @@ impl<'de> DeserializeAs<'de, ShutterSpeed> for f64 {
     }
 }
 
+/// Serializes as a string which can be parsed back, e.g. "1/500" or "Av"
+impl Serialize for ShutterSpeed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Manual(value) => serializer.collect_str(value),
+            Self::AperturePriority => serializer.serialize_str("Av"),
+        }
+    }
+}
+
 impl std::str::FromStr for ShutterSpeed {
     type Err = num_rational::ParseRatioError;
 
@@ -113,6 +164,16 @@ impl std::fmt::Display for ShutterSpeed {
 #[derive(DeserializeFromStr)]
 pub struct ExposureBias(pub num_rational::Rational32);
 
+/// Serializes as a string which can be parsed back, e.g. "-1/3"
+impl Serialize for ExposureBias {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&self.0)
+    }
+}
+
 impl std::str::FromStr for ExposureBias {
     type Err = num_rational::ParseRatioError;
 
@@ -169,6 +230,35 @@ impl<'de> DeserializeAs<'de, Aperture> for f64 {
     }
 }
 
+impl SerializeAs<Aperture> for f64 {
+    fn serialize_as<S>(source: &Aperture, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+        match source {
+            Aperture::Manual(value) => value
+                .to_f64()
+                .ok_or(S::Error::custom("Decimal::to_f64 failed"))
+                .and_then(|v| serializer.serialize_f64(v)),
+            Aperture::ShutterPriority => Err(S::Error::custom("unknown aperture")),
+        }
+    }
+}
+
+/// Serializes as a decimal number, or "Tv" for shutter priority
+impl Serialize for Aperture {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Manual(_) => f64::serialize_as(self, serializer),
+            Self::ShutterPriority => serializer.serialize_str("Tv"),
+        }
+    }
+}
+
 impl std::str::FromStr for Aperture {
     type Err = rust_decimal::Error;
 
@@ -276,12 +366,430 @@ impl FilmSpeed {
     }
 }
 
+/// Serializes as the arithmetic (ISO) value, e.g. 100
+impl Serialize for FilmSpeed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+        let iso = self.iso();
+        match iso.to_u32() {
+            Some(value) if iso.is_integer() => serializer.serialize_u32(value),
+            _ => iso
+                .to_f64()
+                .ok_or(S::Error::custom("Decimal::to_f64 failed"))
+                .and_then(|v| serializer.serialize_f64(v)),
+        }
+    }
+}
+
 impl std::fmt::Display for FilmSpeed {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}/{}°", self.asa(), self.din())
     }
 }
 
+/// Error parsing an enumerated value (e.g. [`MeteringMode`])
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(thiserror::Error)]
+#[error("unrecognized {kind}: `{value}`")]
+pub struct ParseEnumError {
+    kind: &'static str,
+    value: String,
+}
+
+impl ParseEnumError {
+    fn new(kind: &'static str, value: &str) -> Self {
+        Self {
+            kind,
+            value: value.to_owned(),
+        }
+    }
+}
+
+/// Normalize an enumerated value for lax parsing
+///
+/// This lowercases the value and strips any non-alphanumeric characters,
+/// so that e.g. "Center-weighted" and "center weighted" compare equal.
+fn normalize_enum(s: &str) -> String {
+    s.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// A flash firing mode
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum FlashMode {
+    /// Flash always fires
+    Compulsory,
+
+    /// Flash never fires
+    Suppressed,
+
+    /// Flash fires automatically when needed
+    Auto,
+}
+
+/// Flash status, i.e. whether the flash fired (and in which mode)
+///
+/// This parses both simple values ("fired", "off", "auto") and the
+/// descriptions used by e.g. *exiftool* ("Auto, Did not fire").
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(DeserializeFromStr)]
+pub struct Flash {
+    pub fired: bool,
+    pub mode: Option<FlashMode>,
+}
+
+impl Flash {
+    /// Construct from an EXIF `Flash` value
+    pub fn from_exif(value: u16) -> Self {
+        Self {
+            fired: value & 0x01 != 0,
+            mode: match (value >> 3) & 0x03 {
+                1 => Some(FlashMode::Compulsory),
+                2 => Some(FlashMode::Suppressed),
+                3 => Some(FlashMode::Auto),
+                _ => None,
+            },
+        }
+    }
+
+    /// The EXIF `Flash` value
+    pub fn exif(&self) -> u16 {
+        let mode: u16 = match self.mode {
+            None => 0,
+            Some(FlashMode::Compulsory) => 1,
+            Some(FlashMode::Suppressed) => 2,
+            Some(FlashMode::Auto) => 3,
+        };
+        u16::from(self.fired) | (mode << 3)
+    }
+
+    /// An *exiftool*-like description, e.g. "Auto, Did not fire"
+    ///
+    /// Unlike the `Display` output, this can be parsed back using `FromStr`.
+    pub fn description(&self) -> String {
+        let fired = if self.fired { "Fired" } else { "Did not fire" };
+        match self.mode {
+            None if self.fired => fired.to_owned(),
+            None => "No flash".to_owned(),
+            Some(FlashMode::Compulsory) => format!("On, {fired}"),
+            Some(FlashMode::Suppressed) => format!("Off, {fired}"),
+            Some(FlashMode::Auto) => format!("Auto, {fired}"),
+        }
+    }
+}
+
+impl Serialize for Flash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.description())
+    }
+}
+
+impl SerializeAs<Flash> for u16 {
+    fn serialize_as<S>(source: &Flash, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u16(source.exif())
+    }
+}
+
+impl<'de> DeserializeAs<'de, Flash> for u16 {
+    fn deserialize_as<D>(deserializer: D) -> Result<Flash, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        u16::deserialize(deserializer).map(Flash::from_exif)
+    }
+}
+
+impl<'de> DeserializeAs<'de, Flash> for bool {
+    fn deserialize_as<D>(deserializer: D) -> Result<Flash, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        bool::deserialize(deserializer).map(|fired| Flash { fired, mode: None })
+    }
+}
+
+impl std::str::FromStr for Flash {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_ascii_lowercase();
+        let mode = match value.split(',').next().map(str::trim) {
+            Some("on") | Some("compulsory") => Some(FlashMode::Compulsory),
+            Some("off") | Some("suppressed") => Some(FlashMode::Suppressed),
+            Some("auto") => Some(FlashMode::Auto),
+            _ => None,
+        };
+        let fired = match value.as_str() {
+            v if ["not fire", "no flash"].iter().any(|p| v.contains(p)) => false,
+            v if v.contains("fired") => true,
+            "on" | "yes" | "true" => true,
+            "no" | "false" => false,
+            _ if mode.is_some() => mode == Some(FlashMode::Compulsory),
+            _ => Err(ParseEnumError::new("flash", s))?,
+        };
+        Ok(Self { fired, mode })
+    }
+}
+
+impl std::fmt::Display for Flash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fired = if self.fired { "Fired" } else { "Not fired" };
+        match self.mode {
+            None => write!(f, "{fired}"),
+            Some(FlashMode::Compulsory) => write!(f, "{fired} (on)"),
+            Some(FlashMode::Suppressed) => write!(f, "{fired} (off)"),
+            Some(FlashMode::Auto) => write!(f, "{fired} (auto)"),
+        }
+    }
+}
+
+/// Optional value given either as an EXIF code or by name
+///
+/// Codes and names meaning "unknown" (e.g. `0` or `"Unknown"`) are read as
+/// `None` rather than rejected, since some logging apps write them for any
+/// frame lacking the value. Values are written as EXIF codes.
+pub struct ExifCode;
+
+/// Deserialize an optional EXIF code or name, using `from_code` for codes
+fn deserialize_exif_code<'de, D, T>(
+    deserializer: D,
+    from_code: impl FnOnce(u16) -> Result<Option<T>, &'static str>,
+) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr<Err = ParseEnumError>,
+{
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Code(u16),
+        Name(String),
+    }
+
+    match Option::<Value>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Value::Code(code)) => from_code(code).map_err(D::Error::custom),
+        Some(Value::Name(name)) if normalize_enum(&name) == "unknown" => Ok(None),
+        Some(Value::Name(name)) => name.parse().map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// A light metering mode
+///
+/// In addition to the in-camera (reflected light) metering modes known
+/// to EXIF, this includes handheld incident light metering and the
+/// "sunny 16" rule (i.e. no metering at all).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
+pub enum MeteringMode {
+    Reflected,
+    CenterWeighted,
+    Spot,
+    MultiSpot,
+    Pattern,
+    Partial,
+    Incident,
+    Sunny16,
+}
+
+impl MeteringMode {
+    /// Construct from an EXIF `MeteringMode` value
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Self::Reflected),
+            2 => Some(Self::CenterWeighted),
+            3 => Some(Self::Spot),
+            4 => Some(Self::MultiSpot),
+            5 => Some(Self::Pattern),
+            6 => Some(Self::Partial),
+            _ => None,
+        }
+    }
+
+    /// The EXIF `MeteringMode` value
+    ///
+    /// Incident light metering and "sunny 16" have no EXIF equivalent,
+    /// and are represented as "other".
+    pub fn exif(&self) -> u16 {
+        match self {
+            Self::Reflected => 1,
+            Self::CenterWeighted => 2,
+            Self::Spot => 3,
+            Self::MultiSpot => 4,
+            Self::Pattern => 5,
+            Self::Partial => 6,
+            Self::Incident | Self::Sunny16 => 255,
+        }
+    }
+}
+
+impl SerializeAs<Option<MeteringMode>> for ExifCode {
+    fn serialize_as<S>(source: &Option<MeteringMode>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        source.map(|v| v.exif()).serialize(serializer)
+    }
+}
+
+impl<'de> DeserializeAs<'de, Option<MeteringMode>> for ExifCode {
+    fn deserialize_as<D>(deserializer: D) -> Result<Option<MeteringMode>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_exif_code(deserializer, |v| match v {
+            0 | 255 => Ok(None), // "unknown" and "other"
+            v => MeteringMode::from_exif(v)
+                .map(Some)
+                .ok_or("unsupported metering mode"),
+        })
+    }
+}
+
+impl std::str::FromStr for MeteringMode {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match normalize_enum(s).as_str() {
+            "reflected" | "average" => Ok(Self::Reflected),
+            "centerweighted" | "centreweighted" | "centerweightedaverage" => {
+                Ok(Self::CenterWeighted)
+            }
+            "spot" => Ok(Self::Spot),
+            "multispot" => Ok(Self::MultiSpot),
+            "pattern" | "matrix" | "evaluative" | "multisegment" => Ok(Self::Pattern),
+            "partial" => Ok(Self::Partial),
+            "incident" => Ok(Self::Incident),
+            "sunny16" => Ok(Self::Sunny16),
+            _ => Err(ParseEnumError::new("metering mode", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for MeteringMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reflected => write!(f, "Reflected"),
+            Self::CenterWeighted => write!(f, "Center-weighted"),
+            Self::Spot => write!(f, "Spot"),
+            Self::MultiSpot => write!(f, "Multi-spot"),
+            Self::Pattern => write!(f, "Pattern"),
+            Self::Partial => write!(f, "Partial"),
+            Self::Incident => write!(f, "Incident"),
+            Self::Sunny16 => write!(f, "Sunny 16"),
+        }
+    }
+}
+
+/// A (simplified) light source
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(DeserializeFromStr, SerializeDisplay)]
+pub enum LightSource {
+    Daylight,
+    Fluorescent,
+    Tungsten,
+    Flash,
+    FineWeather,
+    Cloudy,
+    Shade,
+    Other,
+}
+
+impl LightSource {
+    /// Construct from an EXIF `LightSource` value
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            0 => None,
+            1 | 20..=23 => Some(Self::Daylight),
+            2 | 12..=16 => Some(Self::Fluorescent),
+            3 | 17 | 24 => Some(Self::Tungsten),
+            4 => Some(Self::Flash),
+            9 => Some(Self::FineWeather),
+            10 => Some(Self::Cloudy),
+            11 => Some(Self::Shade),
+            _ => Some(Self::Other),
+        }
+    }
+
+    /// The EXIF `LightSource` value
+    pub fn exif(&self) -> u16 {
+        match self {
+            Self::Daylight => 1,
+            Self::Fluorescent => 2,
+            Self::Tungsten => 3,
+            Self::Flash => 4,
+            Self::FineWeather => 9,
+            Self::Cloudy => 10,
+            Self::Shade => 11,
+            Self::Other => 255,
+        }
+    }
+}
+
+impl SerializeAs<Option<LightSource>> for ExifCode {
+    fn serialize_as<S>(source: &Option<LightSource>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        source.map(|v| v.exif()).serialize(serializer)
+    }
+}
+
+impl<'de> DeserializeAs<'de, Option<LightSource>> for ExifCode {
+    fn deserialize_as<D>(deserializer: D) -> Result<Option<LightSource>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize_exif_code(deserializer, |v| Ok(LightSource::from_exif(v)))
+    }
+}
+
+impl std::str::FromStr for LightSource {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match normalize_enum(s).as_str() {
+            "daylight" => Ok(Self::Daylight),
+            v if v.contains("fluorescent") => Ok(Self::Fluorescent),
+            "tungsten" | "incandescent" | "tungstenincandescent" => Ok(Self::Tungsten),
+            "flash" => Ok(Self::Flash),
+            "fineweather" | "sunny" | "sun" => Ok(Self::FineWeather),
+            "cloudy" | "overcast" => Ok(Self::Cloudy),
+            "shade" => Ok(Self::Shade),
+            "other" => Ok(Self::Other),
+            _ => Err(ParseEnumError::new("light source", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for LightSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Daylight => write!(f, "Daylight"),
+            Self::Fluorescent => write!(f, "Fluorescent"),
+            Self::Tungsten => write!(f, "Tungsten"),
+            Self::Flash => write!(f, "Flash"),
+            Self::FineWeather => write!(f, "Fine weather"),
+            Self::Cloudy => write!(f, "Cloudy"),
+            Self::Shade => write!(f, "Shade"),
+            Self::Other => write!(f, "Other"),
+        }
+    }
+}
+
 /// Helper trait converting Decimal to Rational
 pub(crate) trait AsRational<T> {
     fn as_rational(&self) -> num_rational::Ratio<T>;
@@ -301,6 +809,67 @@ impl AsRational<i64> for rust_decimal::Decimal {
     }
 }
 
+/// TOML date/time type with lax conversion
+///
+/// This accepts TOML offset date/times (discarding the offset), local
+/// date/times, and local dates (falling back to midnight). The same syntax
+/// is also accepted when parsing from a string, e.g. on the command line.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct TomlDateTime(pub chrono::NaiveDateTime);
+
+impl From<TomlDateTime> for chrono::NaiveDateTime {
+    fn from(value: TomlDateTime) -> Self {
+        value.0
+    }
+}
+
+impl From<chrono::NaiveDateTime> for TomlDateTime {
+    fn from(value: chrono::NaiveDateTime) -> Self {
+        Self(value)
+    }
+}
+
+impl std::str::FromStr for TomlDateTime {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        chrono::DateTime::<chrono::FixedOffset>::parse_from_rfc3339(s)
+            .map(|d| d.naive_local())
+            .or_else(|_| chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f"))
+            .or_else(|_| {
+                chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .map(|date| date.and_time(chrono::NaiveTime::default()))
+            })
+            .map(Self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TomlDateTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        toml::value::Datetime::deserialize(deserializer)
+            .and_then(|value| value.to_string().parse().map_err(D::Error::custom))
+    }
+}
+
+impl Serialize for TomlDateTime {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::Error;
+        self.0
+            .format("%Y-%m-%dT%H:%M:%S%.f")
+            .to_string()
+            .parse::<toml::value::Datetime>()
+            .map_err(S::Error::custom)
+            .and_then(|value| value.serialize(serializer))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,6 +974,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_position() {
+        assert_eq!(
+            "57.700767, 11.953715".parse::<Position>(),
+            Ok(Position {
+                lat: 57.700767,
+                lon: 11.953715
+            })
+        );
+        assert!("57.700767".parse::<Position>().is_err());
+        assert!("91,0".parse::<Position>().is_err());
+        assert!("N57,E11".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn position_distance() {
+        let gothenburg = Position {
+            lat: 57.7089,
+            lon: 11.9746,
+        };
+        let stockholm = Position {
+            lat: 59.3293,
+            lon: 18.0686,
+        };
+        assert_eq!(gothenburg.distance(&gothenburg), 0.0);
+        assert!((gothenburg.distance(&stockholm) - 396.9).abs() < 0.1);
+    }
+
     #[test]
     fn parse_shutter_speed() {
         assert_eq!("Av".parse(), Ok(ShutterSpeed::AperturePriority));
@@ -427,4 +1024,135 @@ mod tests {
         assert_eq!(dec!(12.30).as_rational(), Ratio::<i64>::new(123, 10));
         assert_eq!(dec!(0.1230).as_rational(), Ratio::<i64>::new(123, 1000));
     }
+
+    #[test]
+    fn parse_toml_dates() -> Result<(), toml::de::Error> {
+        use toml::from_str;
+        #[derive(Deserialize)]
+        struct Wrapper {
+            date: TomlDateTime,
+        }
+        let expected = chrono::NaiveDate::from_ymd_opt(2016, 3, 28)
+            .and_then(|d| d.and_hms_opt(15, 16, 36))
+            .unwrap();
+        assert_eq!(
+            from_str::<Wrapper>("date = 2016-03-28T15:16:36+05:00")?
+                .date
+                .0,
+            expected
+        );
+        assert_eq!(
+            from_str::<Wrapper>("date = 2016-03-28T15:16:36Z")?.date.0,
+            expected
+        );
+        assert_eq!(
+            from_str::<Wrapper>("date = 2016-03-28T15:16:36")?.date.0,
+            expected
+        );
+        assert_eq!(
+            from_str::<Wrapper>("date = 2016-03-28")?.date.0,
+            chrono::NaiveDate::from_ymd_opt(2016, 3, 28)
+                .map(|d| d.and_time(chrono::NaiveTime::default()))
+                .unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_values() {
+        use serde_json::json;
+        fn value<T: Serialize>(v: T) -> serde_json::Value {
+            serde_json::to_value(v).unwrap()
+        }
+        assert_eq!(
+            value(ShutterSpeed::Manual(Ratio::new(1, 500))),
+            json!("1/500")
+        );
+        assert_eq!(value(ShutterSpeed::AperturePriority), json!("Av"));
+        assert_eq!(value(ExposureBias(Ratio::new(-1, 3))), json!("-1/3"));
+        assert_eq!(value(Aperture::Manual(dec!(5.6))), json!(5.6));
+        assert_eq!(value(Aperture::ShutterPriority), json!("Tv"));
+        assert_eq!(value(FilmSpeed::from_din(21)), json!(100));
+        assert_eq!(value(FilmSpeed::from_din(0)), json!(0.8));
+        assert_eq!(
+            value(MeteringMode::CenterWeighted),
+            json!("Center-weighted")
+        );
+        assert_eq!(value(LightSource::FineWeather), json!("Fine weather"));
+        for value in [0x00, 0x01, 0x08, 0x09, 0x10, 0x18, 0x19] {
+            let flash = Flash::from_exif(value);
+            assert_eq!(flash.description().parse(), Ok(flash));
+        }
+        assert_eq!(
+            toml::to_string(&std::collections::BTreeMap::from([(
+                "date",
+                TomlDateTime("2016-03-28T15:16:36".parse().unwrap())
+            )]))
+            .unwrap(),
+            "date = 2016-03-28T15:16:36\n"
+        );
+    }
+
+    #[test]
+    fn parse_flash() {
+        let flash = |s: &str| s.parse::<Flash>();
+        let fired = |fired, mode| Ok(Flash { fired, mode });
+        assert_eq!(flash("fired"), fired(true, None));
+        assert_eq!(flash("No Flash"), fired(false, None));
+        assert_eq!(flash("on"), fired(true, Some(FlashMode::Compulsory)));
+        assert_eq!(flash("off"), fired(false, Some(FlashMode::Suppressed)));
+        assert_eq!(flash("Auto, Fired"), fired(true, Some(FlashMode::Auto)));
+        assert_eq!(
+            flash("Auto, Did not fire"),
+            fired(false, Some(FlashMode::Auto))
+        );
+        assert!(flash("sometimes").is_err());
+    }
+
+    #[test]
+    fn flash_exif_values() {
+        for value in [0x00, 0x01, 0x08, 0x09, 0x10, 0x18, 0x19] {
+            assert_eq!(Flash::from_exif(value).exif(), value);
+        }
+        assert_eq!(
+            Flash {
+                fired: true,
+                mode: Some(FlashMode::Auto)
+            }
+            .to_string(),
+            "Fired (auto)"
+        );
+    }
+
+    #[test]
+    fn parse_metering_mode() {
+        assert_eq!("incident".parse(), Ok(MeteringMode::Incident));
+        assert_eq!("Spot".parse(), Ok(MeteringMode::Spot));
+        assert_eq!("reflected".parse(), Ok(MeteringMode::Reflected));
+        assert_eq!("sunny-16".parse(), Ok(MeteringMode::Sunny16));
+        assert_eq!(
+            "Center-weighted average".parse(),
+            Ok(MeteringMode::CenterWeighted)
+        );
+        assert!("guesswork".parse::<MeteringMode>().is_err());
+        assert_eq!(MeteringMode::Spot.exif(), 3);
+        assert_eq!(MeteringMode::Sunny16.exif(), 255);
+        assert_eq!(MeteringMode::from_exif(5), Some(MeteringMode::Pattern));
+        assert_eq!(MeteringMode::from_exif(255), None);
+    }
+
+    #[test]
+    fn parse_light_source() {
+        assert_eq!("Daylight".parse(), Ok(LightSource::Daylight));
+        assert_eq!("Tungsten (Incandescent)".parse(), Ok(LightSource::Tungsten));
+        assert_eq!(
+            "Cool White Fluorescent".parse(),
+            Ok(LightSource::Fluorescent)
+        );
+        assert_eq!("fine weather".parse(), Ok(LightSource::FineWeather));
+        assert!("moonlight".parse::<LightSource>().is_err());
+        assert_eq!(LightSource::Cloudy.exif(), 10);
+        assert_eq!(LightSource::from_exif(14), Some(LightSource::Fluorescent));
+        assert_eq!(LightSource::from_exif(0), None);
+    }
 }
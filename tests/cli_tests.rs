@@ -1,24 +1,90 @@
+/// Test cases isolated from any user configuration
+///
+/// No configuration file is read unless a case passes `--config` explicitly,
+/// since the default location is moved to a directory which does not exist.
+/// Backups can't be written to the default location either, such that cases
+/// which (unexpectedly) modify an image fail rather than modifying it.
+/// No `FILMROLLS_*` variables may be set when running the tests.
+fn test_cases() -> trycmd::TestCases {
+    let cases = trycmd::TestCases::new();
+    cases.env("XDG_CONFIG_HOME", "/nonexistent");
+    cases.env("XDG_STATE_HOME", "/dev/null");
+    cases
+}
+
 #[test]
 fn test_cli_examples() {
-    trycmd::TestCases::new().case("README.md");
+    test_cases().case("README.md");
+}
+
+#[test]
+fn test_cli_config() {
+    test_cases().case("tests/config.trycmd");
+}
+
+#[test]
+fn test_cli_config_env() {
+    let cases = test_cases();
+    cases.env("FILMROLLS_OUTPUT", "csv");
+    cases.case("tests/config-env.trycmd");
 }
 
 #[test]
 fn test_cli_list_rolls() {
-    trycmd::TestCases::new().case("tests/list-rolls.trycmd");
+    test_cases().case("tests/list-rolls.trycmd");
 }
 
 #[test]
 fn test_cli_list_frames() {
-    trycmd::TestCases::new().case("tests/list-frames.trycmd");
+    test_cases().case("tests/list-frames.trycmd");
+}
+
+#[test]
+fn test_cli_find_frames() {
+    test_cases().case("tests/find-frames.trycmd");
+}
+
+#[test]
+fn test_cli_show_roll() {
+    test_cases().case("tests/show-roll.trycmd");
+}
+
+#[test]
+fn test_cli_stats() {
+    test_cases().case("tests/stats.trycmd");
+}
+
+#[test]
+fn test_cli_map() {
+    test_cases().case("tests/map.trycmd");
 }
 
 #[test]
 fn test_cli_tag() {
-    trycmd::TestCases::new().case("tests/tag.trycmd");
+    test_cases().case("tests/tag.trycmd");
 }
 
 #[test]
 fn test_cli_apply_metadata() {
-    trycmd::TestCases::new().case("tests/apply-metadata.trycmd");
+    test_cases().case("tests/apply-metadata.trycmd");
+}
+
+#[test]
+fn test_cli_inspect() {
+    test_cases().case("tests/inspect.trycmd");
+}
+
+#[test]
+fn test_cli_verify() {
+    test_cases().case("tests/verify.trycmd");
+}
+
+#[test]
+fn test_cli_export() {
+    test_cases().case("tests/export.trycmd");
+}
+
+#[test]
+fn test_cli_restore() {
+    test_cases().case("tests/restore.trycmd");
 }